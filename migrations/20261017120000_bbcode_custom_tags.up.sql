@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS bbcode_custom_tags
+(
+    id serial NOT NULL,
+    tag character varying(32) NOT NULL,
+    title text NOT NULL,
+    open_template text NOT NULL,
+    close_template text NOT NULL,
+    argument_regex text,
+    allow_nesting boolean NOT NULL DEFAULT true,
+    active boolean NOT NULL DEFAULT true,
+    PRIMARY KEY (id)
+);
+
+CREATE UNIQUE INDEX ON bbcode_custom_tags ( lower(tag) );
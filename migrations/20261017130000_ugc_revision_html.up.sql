@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS ugc_revision_html
+(
+    ugc_revision_id int NOT NULL REFERENCES ugc_revisions ( id ) ON DELETE CASCADE,
+    version character(64) NOT NULL,
+    html text NOT NULL,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (ugc_revision_id)
+);
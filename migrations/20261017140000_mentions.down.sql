@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS notifications;
+DROP TYPE IF EXISTS NOTIFICATION_TYPE;
+DROP TABLE IF EXISTS ugc_mentions;
@@ -0,0 +1,27 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS ugc_mentions
+(
+    ugc_id int NOT NULL REFERENCES ugc ( id ) ON DELETE CASCADE,
+    user_id int NOT NULL REFERENCES users ( id ) ON DELETE CASCADE,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (ugc_id, user_id)
+);
+
+CREATE INDEX ON ugc_mentions ( user_id );
+
+DROP TYPE IF EXISTS NOTIFICATION_TYPE;
+CREATE TYPE NOTIFICATION_TYPE AS ENUM ('mention');
+
+CREATE TABLE IF NOT EXISTS notifications
+(
+    id serial NOT NULL,
+    user_id int NOT NULL REFERENCES users ( id ) ON DELETE CASCADE,
+    actor_id int NULL REFERENCES users ( id ) ON DELETE SET NULL,
+    notification_type NOTIFICATION_TYPE NOT NULL,
+    ugc_id int NULL REFERENCES ugc ( id ) ON DELETE CASCADE,
+    created_at timestamp NOT NULL,
+    read_at timestamp NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX ON notifications ( user_id, created_at DESC );
@@ -0,0 +1,7 @@
+-- Add down migration script here
+DELETE FROM permission_categories WHERE label = 'moderation';
+
+DROP INDEX IF EXISTS threads_forum_id_is_sticky_last_post_at_idx;
+
+ALTER TABLE threads DROP COLUMN IF EXISTS is_sticky;
+ALTER TABLE threads DROP COLUMN IF EXISTS is_locked;
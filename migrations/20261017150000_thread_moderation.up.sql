@@ -0,0 +1,18 @@
+-- Add up migration script here
+ALTER TABLE threads ADD COLUMN is_locked boolean NOT NULL DEFAULT false;
+ALTER TABLE threads ADD COLUMN is_sticky boolean NOT NULL DEFAULT false;
+
+CREATE INDEX ON threads ( forum_id, is_sticky DESC, last_post_at DESC );
+
+WITH category AS (
+    INSERT INTO permission_categories (label, sort) VALUES ('moderation', 100) RETURNING id
+)
+INSERT INTO permissions (category_id, label, sort)
+SELECT category.id, permission.label, permission.sort
+FROM category, (VALUES
+    ('lock_threads', 10),
+    ('sticky_threads', 20),
+    ('move_threads', 30),
+    ('merge_threads', 40),
+    ('split_threads', 50)
+) AS permission (label, sort);
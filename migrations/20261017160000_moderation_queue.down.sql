@@ -0,0 +1,5 @@
+-- Add down migration script here
+DELETE FROM permission_categories WHERE label = 'posting';
+DELETE FROM permissions WHERE label = 'approve_content';
+
+DROP INDEX IF EXISTS ugc_content_status_idx;
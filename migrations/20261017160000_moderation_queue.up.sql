@@ -0,0 +1,11 @@
+-- Add up migration script here
+CREATE INDEX ON ugc ( content_status ) WHERE content_status = 'in_queue'::CONTENT_STATUS;
+
+INSERT INTO permissions (category_id, label, sort)
+SELECT id, 'approve_content', 60 FROM permission_categories WHERE label = 'moderation';
+
+WITH category AS (
+    INSERT INTO permission_categories (label, sort) VALUES ('posting', 90) RETURNING id
+)
+INSERT INTO permissions (category_id, label, sort)
+SELECT category.id, 'require_approval', 10 FROM category;
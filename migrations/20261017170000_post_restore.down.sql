@@ -0,0 +1,2 @@
+-- Add down migration script here
+DELETE FROM permissions WHERE label IN ('undelete_posts', 'hard_delete_posts');
@@ -0,0 +1,8 @@
+-- Add up migration script here
+INSERT INTO permissions (category_id, label, sort)
+SELECT id, permission.label, permission.sort
+FROM permission_categories, (VALUES
+    ('undelete_posts', 70),
+    ('hard_delete_posts', 80)
+) AS permission (label, sort)
+WHERE permission_categories.label = 'moderation';
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE ugc_revisions DROP COLUMN IF EXISTS reverted_from_id;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE ugc_revisions ADD COLUMN reverted_from_id int NULL REFERENCES ugc_revisions ( id ) ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DELETE FROM permissions WHERE label = 'react_to_own_content';
+DROP TABLE IF EXISTS ugc_reactions;
+DROP TABLE IF EXISTS reaction_types;
@@ -0,0 +1,33 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS reaction_types
+(
+    id serial NOT NULL,
+    label text NOT NULL,
+    emoji text NOT NULL,
+    sort integer NOT NULL DEFAULT 0,
+    active boolean NOT NULL DEFAULT true,
+    PRIMARY KEY (id)
+);
+
+INSERT INTO reaction_types (label, emoji, sort) VALUES
+    ('Like', '👍', 10),
+    ('Love', '❤️', 20),
+    ('Funny', '😂', 30),
+    ('Wow', '😮', 40),
+    ('Sad', '😢', 50);
+
+CREATE TABLE IF NOT EXISTS ugc_reactions
+(
+    ugc_id int NOT NULL REFERENCES ugc ( id ) ON DELETE CASCADE,
+    user_id int NOT NULL REFERENCES users ( id ) ON DELETE CASCADE,
+    reaction_type_id int NOT NULL REFERENCES reaction_types ( id ) ON DELETE CASCADE,
+    author_id int NULL REFERENCES users ( id ) ON DELETE SET NULL,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (ugc_id, user_id)
+);
+
+CREATE INDEX ON ugc_reactions ( user_id );
+CREATE INDEX ON ugc_reactions ( author_id );
+
+INSERT INTO permissions (category_id, label, sort)
+SELECT id, 'react_to_own_content', 20 FROM permission_categories WHERE label = 'posting';
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS thread_tags;
+DROP TABLE IF EXISTS tags;
+ALTER TABLE threads DROP COLUMN IF EXISTS prefix_id;
+DROP TABLE IF EXISTS forum_thread_prefixes;
+DROP TABLE IF EXISTS thread_prefixes;
@@ -0,0 +1,41 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS thread_prefixes
+(
+    id serial NOT NULL,
+    label text NOT NULL,
+    color character varying(32) NOT NULL DEFAULT '#6a7480',
+    sort integer NOT NULL DEFAULT 0,
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE IF NOT EXISTS forum_thread_prefixes
+(
+    forum_id int NOT NULL REFERENCES forums ( id ) ON DELETE CASCADE,
+    thread_prefix_id int NOT NULL REFERENCES thread_prefixes ( id ) ON DELETE CASCADE,
+    PRIMARY KEY (forum_id, thread_prefix_id)
+);
+
+CREATE INDEX ON forum_thread_prefixes ( thread_prefix_id );
+
+ALTER TABLE threads ADD COLUMN prefix_id int NULL REFERENCES thread_prefixes ( id ) ON DELETE SET NULL;
+
+CREATE INDEX ON threads ( prefix_id );
+
+CREATE TABLE IF NOT EXISTS tags
+(
+    id serial NOT NULL,
+    name character varying(64) NOT NULL,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (id)
+);
+
+CREATE UNIQUE INDEX ON tags ( name );
+
+CREATE TABLE IF NOT EXISTS thread_tags
+(
+    thread_id int NOT NULL REFERENCES threads ( id ) ON DELETE CASCADE,
+    tag_id int NOT NULL REFERENCES tags ( id ) ON DELETE CASCADE,
+    PRIMARY KEY (thread_id, tag_id)
+);
+
+CREATE INDEX ON thread_tags ( tag_id );
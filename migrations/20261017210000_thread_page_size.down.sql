@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS threads_forum_id_created_at_idx;
+DROP INDEX IF EXISTS threads_forum_id_post_count_idx;
+DROP INDEX IF EXISTS threads_forum_id_view_count_idx;
+
+ALTER TABLE users DROP COLUMN IF EXISTS threads_per_page;
+ALTER TABLE forums DROP COLUMN IF EXISTS threads_per_page;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+ALTER TABLE forums ADD COLUMN threads_per_page int NULL;
+ALTER TABLE users ADD COLUMN threads_per_page int NULL;
+
+CREATE INDEX ON threads ( forum_id, created_at DESC );
+CREATE INDEX ON threads ( forum_id, post_count DESC );
+CREATE INDEX ON threads ( forum_id, view_count DESC );
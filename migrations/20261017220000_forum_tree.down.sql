@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS forums_parent_id_display_order_idx;
+
+ALTER TABLE forums DROP COLUMN IF EXISTS last_post_at;
+ALTER TABLE forums DROP COLUMN IF EXISTS post_count;
+ALTER TABLE forums DROP COLUMN IF EXISTS thread_count;
+ALTER TABLE forums DROP COLUMN IF EXISTS is_category;
+ALTER TABLE forums DROP COLUMN IF EXISTS display_order;
+ALTER TABLE forums DROP COLUMN IF EXISTS parent_id;
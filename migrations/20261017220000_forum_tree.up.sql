@@ -0,0 +1,34 @@
+-- Add up migration script here
+ALTER TABLE forums ADD COLUMN parent_id int NULL REFERENCES forums ( id ) ON DELETE SET NULL;
+ALTER TABLE forums ADD COLUMN display_order int NOT NULL DEFAULT 0;
+ALTER TABLE forums ADD COLUMN is_category boolean NOT NULL DEFAULT false;
+ALTER TABLE forums ADD COLUMN thread_count int NOT NULL DEFAULT 0;
+ALTER TABLE forums ADD COLUMN post_count int NOT NULL DEFAULT 0;
+ALTER TABLE forums ADD COLUMN last_post_at timestamp NULL;
+
+CREATE INDEX ON forums ( parent_id, display_order );
+
+-- Statistics only count threads whose first post is visible.
+UPDATE forums
+SET thread_count = stats.thread_count, post_count = stats.post_count
+FROM (
+    SELECT threads.forum_id, COUNT(*) AS thread_count, SUM(threads.post_count) AS post_count
+    FROM threads
+    INNER JOIN posts ON posts.id = threads.first_post_id
+    INNER JOIN ugc ON ugc.id = posts.ugc_id
+    WHERE ugc.content_status = 'visible'
+    GROUP BY threads.forum_id
+) AS stats
+WHERE forums.id = stats.forum_id;
+
+UPDATE forums
+SET last_thread_id = latest.id, last_post_id = latest.last_post_id, last_post_at = latest.last_post_at
+FROM (
+    SELECT DISTINCT ON (threads.forum_id) threads.forum_id, threads.id, threads.last_post_id, threads.last_post_at
+    FROM threads
+    INNER JOIN posts ON posts.id = threads.first_post_id
+    INNER JOIN ugc ON ugc.id = posts.ugc_id
+    WHERE ugc.content_status = 'visible'
+    ORDER BY threads.forum_id, threads.last_post_at DESC NULLS LAST, threads.id DESC
+) AS latest
+WHERE forums.id = latest.forum_id;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS threads_last_post_at_idx;
+DROP TABLE IF EXISTS forum_reads;
+DROP TABLE IF EXISTS thread_reads;
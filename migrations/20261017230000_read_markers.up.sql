@@ -0,0 +1,22 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS thread_reads
+(
+    user_id int NOT NULL REFERENCES users ( id ) ON DELETE CASCADE,
+    thread_id int NOT NULL REFERENCES threads ( id ) ON DELETE CASCADE,
+    read_at timestamp NOT NULL,
+    PRIMARY KEY (user_id, thread_id)
+);
+
+CREATE INDEX ON thread_reads ( read_at );
+
+CREATE TABLE IF NOT EXISTS forum_reads
+(
+    user_id int NOT NULL REFERENCES users ( id ) ON DELETE CASCADE,
+    forum_id int NOT NULL REFERENCES forums ( id ) ON DELETE CASCADE,
+    read_at timestamp NOT NULL,
+    PRIMARY KEY (user_id, forum_id)
+);
+
+CREATE INDEX ON forum_reads ( read_at );
+
+CREATE INDEX ON threads ( last_post_at );
@@ -0,0 +1,12 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS forum_watches;
+DROP TABLE IF EXISTS thread_watches;
+DROP INDEX IF EXISTS notifications_user_id_idx;
+
+-- Enum values cannot be dropped, so the type is replaced with one holding only the original values.
+DELETE FROM notifications WHERE notification_type <> 'mention';
+ALTER TYPE NOTIFICATION_TYPE RENAME TO NOTIFICATION_TYPE_OLD;
+CREATE TYPE NOTIFICATION_TYPE AS ENUM ('mention');
+ALTER TABLE notifications ALTER COLUMN notification_type TYPE NOTIFICATION_TYPE
+    USING notification_type::text::NOTIFICATION_TYPE;
+DROP TYPE NOTIFICATION_TYPE_OLD;
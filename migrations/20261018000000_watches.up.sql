@@ -0,0 +1,27 @@
+-- Add up migration script here
+ALTER TYPE NOTIFICATION_TYPE ADD VALUE IF NOT EXISTS 'quote';
+ALTER TYPE NOTIFICATION_TYPE ADD VALUE IF NOT EXISTS 'reaction';
+ALTER TYPE NOTIFICATION_TYPE ADD VALUE IF NOT EXISTS 'reply';
+ALTER TYPE NOTIFICATION_TYPE ADD VALUE IF NOT EXISTS 'thread';
+
+CREATE INDEX ON notifications ( user_id ) WHERE read_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS thread_watches
+(
+    user_id int NOT NULL REFERENCES users ( id ) ON DELETE CASCADE,
+    thread_id int NOT NULL REFERENCES threads ( id ) ON DELETE CASCADE,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (user_id, thread_id)
+);
+
+CREATE INDEX ON thread_watches ( thread_id );
+
+CREATE TABLE IF NOT EXISTS forum_watches
+(
+    user_id int NOT NULL REFERENCES users ( id ) ON DELETE CASCADE,
+    forum_id int NOT NULL REFERENCES forums ( id ) ON DELETE CASCADE,
+    created_at timestamp NOT NULL,
+    PRIMARY KEY (user_id, forum_id)
+);
+
+CREATE INDEX ON forum_watches ( forum_id );
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DELETE FROM attachment_thumbnails;
+
+ALTER TABLE attachment_thumbnails DROP CONSTRAINT attachment_thumbnails_pkey;
+ALTER TABLE attachment_thumbnails DROP COLUMN size;
+ALTER TABLE attachment_thumbnails ADD PRIMARY KEY (attachment_id, thumbnail_id);
+
+DROP TYPE IF EXISTS THUMBNAIL_SIZE;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+CREATE TYPE THUMBNAIL_SIZE AS ENUM ('xs', 's', 'm', 'l');
+
+-- Thumbnails were never generated before sizes were recorded.
+DELETE FROM attachment_thumbnails;
+
+ALTER TABLE attachment_thumbnails ADD COLUMN size THUMBNAIL_SIZE NOT NULL;
+ALTER TABLE attachment_thumbnails DROP CONSTRAINT attachment_thumbnails_pkey;
+ALTER TABLE attachment_thumbnails ADD PRIMARY KEY (attachment_id, size);
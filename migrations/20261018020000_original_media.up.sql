@@ -0,0 +1,3 @@
+-- Add up migration script here
+INSERT INTO permissions (category_id, label, sort)
+SELECT id, 'upload_original_media', 30 FROM permission_categories WHERE label = 'posting';
@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS upload_sessions
+(
+    id char(36) NOT NULL PRIMARY KEY,
+    user_id int REFERENCES users ( id ) ON DELETE CASCADE,
+    filename text NOT NULL,
+    mime text NOT NULL,
+    filesize bigint NOT NULL,
+    received bigint NOT NULL DEFAULT 0,
+    hash char(64),
+    created_at timestamp NOT NULL,
+    updated_at timestamp NOT NULL
+);
+
+CREATE INDEX ON upload_sessions ( updated_at );
@@ -0,0 +1,12 @@
+-- Add up migration script here
+CREATE TABLE IF NOT EXISTS ugc_revision_html_mentions
+(
+    ugc_revision_id int NOT NULL REFERENCES ugc_revision_html ( ugc_revision_id ) ON DELETE CASCADE,
+    name text NOT NULL,
+    PRIMARY KEY (ugc_revision_id, name)
+);
+
+CREATE INDEX ON ugc_revision_html_mentions ( name );
+
+-- HTML cached so far did not record the names it mentions.
+DELETE FROM ugc_revision_html;
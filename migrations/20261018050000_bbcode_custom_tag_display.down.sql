@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE bbcode_custom_tags DROP COLUMN IF EXISTS is_block;
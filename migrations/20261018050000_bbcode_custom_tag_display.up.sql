@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE bbcode_custom_tags ADD COLUMN is_block boolean NOT NULL DEFAULT false;
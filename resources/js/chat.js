@@ -469,6 +469,10 @@ document.addEventListener("DOMContentLoaded", function () {
             json.delete.forEach(message => messageDelete(message));
         }
 
+        if (json.hasOwnProperty('notifications')) {
+            json.notifications.forEach(notification => notificationPush(notification));
+        }
+
         if (json.hasOwnProperty('users')) {
             Object.entries(json.users).forEach(user => {
                 let [key, value] = user;
@@ -478,6 +482,13 @@ document.addEventListener("DOMContentLoaded", function () {
         }
     }
 
+    function notificationPush(notification) {
+        let link = document.createElement('a');
+        link.setAttribute('href', notification.url);
+        link.textContent = notification.message;
+        messagePush({ message: link.outerHTML }, null);
+    }
+
     function messagesDelete() {
         let messagesEl = document.getElementById('chat-messages');
         while (messagesEl.firstChild) {
@@ -1,8 +1,15 @@
+use crate::bbcode::{Attachments, Constructor};
 use crate::db::get_db_pool;
-use crate::filesystem::get_file_url_by_filename;
+use crate::filesystem::{get_file_url_by_filename, get_thumbnail_url_by_filename};
+use crate::orm::attachment_thumbnails::{self, ThumbnailSize};
 use crate::orm::{attachments, ugc_attachments};
 use chrono::Utc;
-use sea_orm::{entity::*, query::*, sea_query::Expr, FromQueryResult};
+use sea_orm::{
+    entity::*,
+    query::*,
+    sea_query::{Expr, Query, SimpleExpr},
+    FromQueryResult,
+};
 use std::collections::HashMap;
 
 /// Represents an attachments on UGC.
@@ -20,6 +27,9 @@ pub struct AttachmentForTemplate {
     pub file_height: Option<i32>,
     pub file_width: Option<i32>,
     pub mime: String,
+    // attachment_thumbnails
+    /// True if a large thumbnail or video poster frame was generated.
+    pub has_thumbnail: bool,
 }
 
 /// Enum of standarized attachment thumbnailing sizes.
@@ -32,14 +42,68 @@ pub enum AttachmentSize {
     Native,
 }
 
+impl AttachmentSize {
+    /// Returns the thumbnail used for this size, or None for the original file.
+    pub fn get_thumbnail_size(&self) -> Option<ThumbnailSize> {
+        match self {
+            AttachmentSize::Xs => Some(ThumbnailSize::Xs),
+            AttachmentSize::S => Some(ThumbnailSize::S),
+            AttachmentSize::M => Some(ThumbnailSize::M),
+            AttachmentSize::L => Some(ThumbnailSize::L),
+            AttachmentSize::Native => None,
+        }
+    }
+}
+
+/// Returns the largest width or height of a thumbnail.
+pub fn get_thumbnail_constraint(size: ThumbnailSize) -> i32 {
+    match size {
+        ThumbnailSize::Xs => 24,
+        ThumbnailSize::S => 48,
+        ThumbnailSize::M => 96,
+        ThumbnailSize::L => 144,
+    }
+}
+
+/// Scales dimensions so the longest side matches the constraint, keeping the aspect ratio.
+pub fn get_scaled_dimensions(dimensions: (i32, i32), constraint: i32) -> (i32, i32) {
+    let (width, height) = dimensions;
+    if width <= 0 || height <= 0 {
+        return (constraint, constraint);
+    }
+
+    let scale = |side: i32, longest: i32| ((side * constraint + longest / 2) / longest).max(1);
+    match width.cmp(&height) {
+        std::cmp::Ordering::Greater => (constraint, scale(height, width)),
+        std::cmp::Ordering::Less => (scale(width, height), constraint),
+        std::cmp::Ordering::Equal => (constraint, constraint),
+    }
+}
+
 impl AttachmentForTemplate {
     pub fn get_download_url(&self) -> String {
         get_file_url_by_filename(&self.hash, &self.ugc_filename)
     }
 
+    pub fn get_thumbnail_url(&self, size: ThumbnailSize) -> String {
+        get_thumbnail_url_by_filename(&self.hash, size, &self.ugc_filename)
+    }
+
     pub fn to_html(&self) -> String {
-        let url = self.get_download_url();
-        if let (Some(width), Some(height)) = (self.file_width, self.file_height) {
+        // Filenames are user input.
+        let url = Constructor::sanitize(&self.get_download_url());
+        if self.mime.starts_with("video/") && self.has_thumbnail {
+            format!(
+                "<video class=\"bbcode attachment\" src=\"{}\" poster=\"{}\" controls preload=\"metadata\"></video>",
+                url,
+                Constructor::sanitize(&self.get_thumbnail_url(ThumbnailSize::L))
+            )
+        } else if self.mime.starts_with("video/") {
+            format!(
+                "<video class=\"bbcode attachment\" src=\"{}\" controls preload=\"metadata\"></video>",
+                url
+            )
+        } else if let (Some(width), Some(height)) = (self.file_width, self.file_height) {
             format!(
                 "<img class=\"bbcode attachment\" src=\"{}\" width=\"{}px\" height=\"{}px\" />",
                 url, width, height
@@ -51,6 +115,44 @@ impl AttachmentForTemplate {
             )
         }
     }
+
+    /// Images are shown small and link to their full size.
+    /// Other files, and images which have no thumbnail, render as normal.
+    pub fn to_thumbnail_html(&self) -> String {
+        if self.mime.starts_with("image/") && self.has_thumbnail {
+            format!(
+                "<a class=\"bbcode attachment attachment--thumbnail\" href=\"{}\"><img src=\"{}\" alt=\"{}\" /></a>",
+                Constructor::sanitize(&self.get_download_url()),
+                Constructor::sanitize(&self.get_thumbnail_url(ThumbnailSize::L)),
+                Constructor::sanitize(&self.ugc_filename)
+            )
+        } else {
+            self.to_html()
+        }
+    }
+}
+
+/// Selects whether an attachment has the large thumbnail which is shown in UGC.
+fn get_has_thumbnail_expr() -> SimpleExpr {
+    Expr::cust(
+        "EXISTS (SELECT 1 FROM attachment_thumbnails WHERE attachment_thumbnails.attachment_id = attachments.id AND attachment_thumbnails.size = 'l')",
+    )
+}
+
+/// Prepares attachments for embedding into UGC with [attach].
+pub fn get_bbcode_attachments(attachments: &[AttachmentForTemplate]) -> Attachments {
+    let mut result = Attachments::default();
+
+    for attachment in attachments {
+        result.insert(
+            attachment.id,
+            attachment.ugc_filename.to_owned(),
+            attachment.to_thumbnail_html(),
+            attachment.to_html(),
+        );
+    }
+
+    result
 }
 
 pub async fn get_attachment_by_hash(hash: String) -> Option<attachments::Model> {
@@ -62,6 +164,32 @@ pub async fn get_attachment_by_hash(hash: String) -> Option<attachments::Model>
         .unwrap_or_default()
 }
 
+/// Returns the thumbnail of an attachment at a size.
+pub async fn get_thumbnail_by_hash(
+    hash: String,
+    size: ThumbnailSize,
+) -> Option<attachments::Model> {
+    attachments::Entity::find()
+        .join_rev(
+            JoinType::InnerJoin,
+            attachment_thumbnails::Relation::Attachments1.def(),
+        )
+        .filter(attachment_thumbnails::Column::Size.eq(size))
+        .filter(
+            attachment_thumbnails::Column::AttachmentId.in_subquery(
+                Query::select()
+                    .column(attachments::Column::Id)
+                    .from(attachments::Entity)
+                    .and_where(attachments::Column::Hash.eq(hash))
+                    .to_owned(),
+            ),
+        )
+        .one(get_db_pool())
+        .await
+        .map_err(|e| log::error!("get_thumbnail_by_hash: {}", e))
+        .unwrap_or_default()
+}
+
 // Returns attachments through their ugc_attachment.id.
 pub async fn get_attachments_by_ugc_attachment_id(ugc: Vec<i32>) -> Vec<AttachmentForTemplate> {
     if ugc.is_empty() {
@@ -81,6 +209,7 @@ pub async fn get_attachments_by_ugc_attachment_id(ugc: Vec<i32>) -> Vec<Attachme
         .column(attachments::Column::FileHeight)
         .column(attachments::Column::FileWidth)
         .column(attachments::Column::Mime)
+        .column_as(get_has_thumbnail_expr(), "has_thumbnail")
         .filter(ugc_attachments::Column::Id.is_in(ugc))
         .order_by_asc(ugc_attachments::Column::CreatedAt)
         .into_model::<AttachmentForTemplate>()
@@ -109,6 +238,7 @@ pub async fn get_attachments_for_ugc_by_id(
         .column(attachments::Column::FileHeight)
         .column(attachments::Column::FileWidth)
         .column(attachments::Column::Mime)
+        .column_as(get_has_thumbnail_expr(), "has_thumbnail")
         .filter(ugc_attachments::Column::UgcId.is_in(ugc))
         .order_by_asc(ugc_attachments::Column::CreatedAt)
         .into_model::<AttachmentForTemplate>()
@@ -129,27 +259,24 @@ pub async fn get_attachments_for_ugc_by_id(
     result
 }
 
-/// Constructs an HTML image tag at a specific dimension.
+/// Constructs an HTML image tag at a specific dimension, using the thumbnail for that size.
 pub fn get_avatar_html(filename: &str, dimensions: (i32, i32), size: AttachmentSize) -> String {
-    let constraint = match size {
-        AttachmentSize::Xs => 24,
-        AttachmentSize::S => 48,
-        AttachmentSize::M => 96,
-        AttachmentSize::L => 144,
-        AttachmentSize::Native => dimensions.0.to_owned().max(dimensions.1.to_owned()),
+    let (url, constraint) = match size.get_thumbnail_size() {
+        Some(size) => (
+            get_thumbnail_url_by_filename(filename, size, filename),
+            get_thumbnail_constraint(size),
+        ),
+        None => (
+            get_file_url_by_filename(filename, filename),
+            dimensions.0.max(dimensions.1),
+        ),
     };
 
-    let (x, y): (i32, i32) = match dimensions.0.cmp(&dimensions.1) {
-        std::cmp::Ordering::Greater => (constraint, constraint * (dimensions.1 / dimensions.0)),
-        std::cmp::Ordering::Less => (constraint * (dimensions.0 / dimensions.1), constraint),
-        std::cmp::Ordering::Equal => (constraint, constraint),
-    };
+    let (x, y) = get_scaled_dimensions(dimensions, constraint);
 
     format!(
         "<img src=\"{}\" class=\"avatar\" width=\"{}\" height=\"{}\" />",
-        get_file_url_by_filename(filename, filename),
-        x,
-        y
+        url, x, y
     )
 }
 
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+/// An attachment as it is embedded.
+struct Attachment {
+    filename: String,
+    thumbnail_html: String,
+    full_html: String,
+}
+
+/// Attachments which may be embedded with [attach], keyed by ugc_attachment id.
+/// HTML is rendered ahead of time by the owner of the attachments.
+#[derive(Default)]
+pub struct Attachments(HashMap<i32, Attachment>);
+
+impl Attachments {
+    pub fn insert(&mut self, id: i32, filename: String, thumbnail_html: String, full_html: String) {
+        self.0.insert(
+            id,
+            Attachment {
+                filename,
+                thumbnail_html,
+                full_html,
+            },
+        );
+    }
+
+    /// Returns the filename, which is used where HTML cannot be.
+    pub fn get_name(&self, id: i32) -> Option<&str> {
+        self.0.get(&id).map(|a| a.filename.as_str())
+    }
+
+    pub fn get_thumbnail_html(&self, id: i32) -> Option<&str> {
+        self.0.get(&id).map(|a| a.thumbnail_html.as_str())
+    }
+
+    pub fn get_full_html(&self, id: i32) -> Option<&str> {
+        self.0.get(&id).map(|a| a.full_html.as_str())
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns the version of HTML built by a Constructor of this version with these attachments.
+    /// Content is rendered again when its attachments change, such as one being linked after it was cached.
+    pub fn get_version(&self, constructor_version: &str) -> String {
+        if self.0.is_empty() {
+            return constructor_version.to_owned();
+        }
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(constructor_version.as_bytes());
+
+        let mut attachments: Vec<_> = self.0.iter().collect();
+        attachments.sort_unstable_by_key(|(id, _)| **id);
+        for (id, attachment) in attachments {
+            hasher.update(
+                format!(
+                    "attachment\0{}\0{}\0{}\0{}\0",
+                    id, attachment.filename, attachment.thumbnail_html, attachment.full_html
+                )
+                .as_bytes(),
+            );
+        }
+
+        hasher.finalize().to_hex().to_string()
+    }
+}
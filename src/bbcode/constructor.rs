@@ -1,13 +1,47 @@
-use super::{Element, Smilies, Tag};
+use super::{Attachments, CustomTags, Element, MediaProviders, Mentions, Smilies, Tag};
 use rctree::Node;
 use std::cell::RefMut;
 use std::collections::HashMap;
+use std::sync::Arc;
 
-/// Converts a Parser's AST into rendered HTML.
-#[derive(Default)]
+/// Increment whenever a change to the renderer changes the HTML it builds.
+/// Cached HTML from an older version is rendered again.
+pub const RENDERER_VERSION: u32 = 1;
+
+/// Default number of quotes which may nest inside each other before collapsing.
+pub const MAX_QUOTE_DEPTH: usize = 3;
+
+/// Converts a Parser's AST into rendered output, which is HTML unless another Renderer is used.
 pub struct Constructor {
     // TODO: Build string here, return in build().
-    pub smilies: Smilies,
+    /// Smilies loaded at startup, unless the Constructor was given its own.
+    pub smilies: Arc<Smilies>,
+    /// Quotes nested deeper than this collapse and do not render their contents.
+    pub max_quote_depth: usize,
+    /// If false, block spoilers are rendered as inline spoilers.
+    pub allow_block_spoilers: bool,
+    /// Media hosts which may be embedded.
+    pub media_providers: MediaProviders,
+    /// Attachments belonging to the content being built.
+    pub attachments: Attachments,
+    /// Users mentioned by name in the content being built.
+    pub mentions: Mentions,
+    /// Administrator defined tags, which are loaded at startup.
+    pub custom_tags: Arc<CustomTags>,
+}
+
+impl Default for Constructor {
+    fn default() -> Self {
+        Self {
+            smilies: super::get_smilies(),
+            max_quote_depth: MAX_QUOTE_DEPTH,
+            allow_block_spoilers: true,
+            media_providers: super::get_media_providers(),
+            attachments: Default::default(),
+            mentions: Default::default(),
+            custom_tags: super::get_custom_tags(),
+        }
+    }
 }
 
 impl Constructor {
@@ -17,27 +51,90 @@ impl Constructor {
         }
     }
 
-    pub fn build(&self, mut node: Node<Element>) -> String {
+    /// Returns a fingerprint of everything which changes the HTML this Constructor builds.
+    /// Attachments and mentions belong to the content being built and are not included.
+    /// See `Attachments::get_version`, and `forget_rendered_mentions` for mentions.
+    pub fn get_version(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&RENDERER_VERSION.to_le_bytes());
+        hasher.update(&self.max_quote_depth.to_le_bytes());
+        hasher.update(&[self.allow_block_spoilers as u8]);
+
+        // Settings are sorted, as their order does not change output.
+        let mut smilies: Vec<_> = self.smilies.iter().collect();
+        smilies.sort();
+        for (code, replace_with) in smilies {
+            hasher.update(format!("smilie\0{}\0{}\0", code, replace_with).as_bytes());
+        }
+
+        let mut providers: Vec<_> = self.media_providers.iter().map(|p| p.get_name()).collect();
+        providers.sort_unstable();
+        for provider in providers {
+            hasher.update(format!("media\0{}\0", provider).as_bytes());
+        }
+
+        let mut custom_tags: Vec<_> = self.custom_tags.iter().collect();
+        custom_tags.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, custom) in custom_tags {
+            hasher.update(
+                format!(
+                    "custom\0{}\0{}\0{}\0{}\0{}\0{}\0",
+                    name,
+                    custom.open_template,
+                    custom.close_template,
+                    custom
+                        .argument_regex
+                        .as_ref()
+                        .map(|regex| regex.as_str())
+                        .unwrap_or_default(),
+                    custom.allow_nesting,
+                    custom.is_block
+                )
+                .as_bytes(),
+            );
+        }
+
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Builds HTML from a Parser's AST.
+    pub fn build(&self, node: Node<Element>) -> String {
+        self.render(&Html, node)
+    }
+
+    /// Builds any output format from a Parser's AST.
+    /// Which tags may render is decided here, so every format breaks the same tags.
+    pub fn render<R: Renderer>(&self, renderer: &R, mut node: Node<Element>) -> String {
         let mut output: String = String::new();
 
+        // Custom tags which cannot render here are broken before building.
+        if !self.is_valid_custom_tag(&node) {
+            node.borrow_mut().set_broken();
+        }
+
         // If we have children, loop through them.
         if node.has_children() {
             let mut contents: String = String::new();
 
+            // Quotes nested too deeply discard their children.
+            if self.is_collapsed_quote(&node) {
+                contents.push('…');
+            }
             // Are we allowed to have children?
-            if node.borrow().can_parent() {
+            else if node.borrow().can_parent() {
                 // Build each child node and append the string to our output.
                 for child in node.children() {
                     // Sanity check on tag-in-tag logic.
                     let mut render = true;
                     // If we have a tag name, check if this tag can go into our parents.
-                    if let Some(tag) = child.borrow().get_tag_name() {
+                    if child.borrow().get_tag_name().is_some() {
+                        let child_el = child.borrow();
                         // Check first if this node can accept this tag.
-                        if node.borrow().can_parent_tag(tag) {
+                        if node.borrow().can_parent_tag(&child_el) {
                             // Then, check each parent upwards.
                             let mut some_parent = node.parent();
                             while let Some(parent) = some_parent {
-                                render = parent.borrow().can_parent_tag(tag);
+                                render = parent.borrow().can_parent_tag(&child_el);
                                 if !render {
                                     break;
                                 } else {
@@ -50,45 +147,191 @@ impl Constructor {
                     }
 
                     if render {
-                        contents.push_str(&self.build(child))
+                        contents.push_str(&self.render(renderer, child))
                     } else {
-                        contents.push_str(&Self::sanitize(child.borrow().get_raw()));
+                        contents.push_str(&renderer.literal(
+                            self,
+                            &node.borrow(),
+                            child.borrow().get_raw(),
+                        ));
                     }
                 }
             }
             // No, so our contents must be handled literally.
             else {
                 for child in node.children() {
-                    contents.push_str(&Self::sanitize(child.borrow().get_raw()));
+                    contents.push_str(&renderer.literal(
+                        self,
+                        &node.borrow(),
+                        child.borrow().get_raw(),
+                    ));
                 }
             }
 
-            let res = &self.element_contents(node.borrow_mut(), contents);
-            output.push_str(&self.element_open(node.borrow_mut()));
+            let res = &renderer.element_contents(self, &mut node, contents);
+            output.push_str(&renderer.element_open(self, node.borrow_mut()));
             output.push_str(res);
         }
         // If we do not have children, add our text.
         else {
-            let res = {
-                let el = node.borrow_mut();
-                &match el.get_contents() {
-                    Some(contents) => {
-                        self.element_contents(el, self.replace_emojis(Self::sanitize(contents)))
-                    }
-                    None => self.element_contents(el, String::new()),
+            let contents = node.borrow().get_contents();
+            let res = &match contents {
+                Some(contents) => {
+                    renderer.element_contents(self, &mut node, renderer.text(self, contents))
                 }
+                None => renderer.element_contents(self, &mut node, String::new()),
             };
 
-            output.push_str(&self.element_open(node.borrow_mut()));
+            output.push_str(&renderer.element_open(self, node.borrow_mut()));
             output.push_str(res);
         }
 
-        output.push_str(&self.element_close(node.borrow_mut()));
+        output.push_str(&renderer.element_close(self, node.borrow_mut()));
 
         output
     }
 
-    fn element_open(&self, el: RefMut<Element>) -> String {
+    /// Returns the raw text inside of a node, ignoring tags.
+    pub fn get_text(node: &Node<Element>) -> String {
+        node.descendants()
+            .filter_map(|n| n.borrow().get_contents().map(str::to_owned))
+            .collect()
+    }
+
+    /// Returns false if this element renders as raw input because of its tag or argument.
+    /// Embeds also depend on their contents, which are checked as they are filled.
+    pub fn is_renderable(&self, el: &Element) -> bool {
+        match el.get_tag_name() {
+            Some(_) if el.is_broken() => false,
+            Some(tag) => match Tag::get_by_name(tag) {
+                Tag::Invalid => self.custom_tags.get(tag).is_some(),
+                Tag::Color => Tag::get_color(el).is_some(),
+                Tag::InlineCode => !el.has_argument(),
+                Tag::InlineSpoiler | Tag::Spoiler => Tag::get_spoiler_title(el).is_some(),
+                Tag::List => Tag::get_list_style(el).is_some(),
+                Tag::Quote => Tag::get_quote_attribution(el).is_some(),
+                Tag::User => Tag::get_user_id(el).is_some(),
+                _ => true,
+            },
+            None => true,
+        }
+    }
+
+    /// Returns false if this node is a custom tag which cannot render.
+    fn is_valid_custom_tag(&self, node: &Node<Element>) -> bool {
+        let el = node.borrow();
+        let custom = match el.get_tag_name() {
+            Some(tag) => match Tag::get_by_name(tag) {
+                Tag::Invalid => match self.custom_tags.get(tag) {
+                    Some(custom) => custom,
+                    None => return true,
+                },
+                _ => return true,
+            },
+            None => return true,
+        };
+
+        // Ancestors include the node itself.
+        custom.is_valid_argument(el.get_argument())
+            && (custom.allow_nesting
+                || node
+                    .ancestors()
+                    .filter(|n| n.borrow().is_tag(el.get_tag_name().unwrap_or_default()))
+                    .count()
+                    == 1)
+    }
+
+    /// Returns true if this node is a quote nested beyond our maximum depth.
+    fn is_collapsed_quote(&self, node: &Node<Element>) -> bool {
+        // Ancestors include the node itself.
+        Tag::is_quote_tag(&node.borrow())
+            && node
+                .ancestors()
+                .filter(|n| Tag::is_quote_tag(&n.borrow()))
+                .count()
+                > self.max_quote_depth
+    }
+
+    /// Add emojis
+    pub fn replace_emojis(&self, input: String) -> String {
+        let mut result = input;
+        let mut hits: u8 = 0;
+        let mut hit_map: HashMap<u8, &String> = HashMap::with_capacity(self.smilies.count());
+
+        for (code, replace_with) in self.smilies.iter() {
+            if result.contains(code) {
+                hit_map.insert(hits, replace_with);
+                result = result.replace(code, &format!("\r{}", hits));
+                hits += 1;
+            }
+        }
+
+        for (hit, replace_with) in hit_map {
+            result = result.replace(&format!("\r{}", hit), replace_with);
+        }
+
+        result
+    }
+
+    /// Sanitizes a char for HTML.
+    pub fn sanitize(input: &str) -> String {
+        // Some insane person did an extremely detailed benchmark of this.
+        // https://lise-henry.github.io/articles/optimising_strings.html
+        let len = input.len();
+        let mut output: Vec<u8> = Vec::with_capacity(len * 4);
+
+        for c in input.bytes() {
+            // https://cheatsheetseries.owasp.org/cheatsheets/Cross_Site_Scripting_Prevention_Cheat_Sheet.html
+            match c {
+                b'<' => output.extend_from_slice(b"&lt;"),
+                b'>' => output.extend_from_slice(b"&gt;"),
+                b'&' => output.extend_from_slice(b"&amp;"),
+                b'\"' => output.extend_from_slice(b"&quot;"),
+                b'\'' => output.extend_from_slice(b"&#x27;"),
+                _ => output.push(c),
+            }
+        }
+
+        unsafe { String::from_utf8_unchecked(output) }
+    }
+}
+
+/// Output format of a Constructor.
+/// Renderers only decide how valid tags look. Tags which cannot render are output as raw input.
+pub trait Renderer {
+    /// Renders text which is not inside of a tag.
+    fn text(&self, con: &Constructor, contents: &str) -> String;
+
+    /// Renders raw input inside an element which does not parse it, like [code].
+    fn literal(&self, con: &Constructor, parent: &Element, raw: &str) -> String;
+
+    fn element_open(&self, con: &Constructor, el: RefMut<Element>) -> String;
+
+    /// Renders the contents of an element. This happens before the element is opened,
+    /// so elements which cannot use their contents are broken here.
+    fn element_contents(
+        &self,
+        con: &Constructor,
+        node: &mut Node<Element>,
+        contents: String,
+    ) -> String;
+
+    fn element_close(&self, con: &Constructor, el: RefMut<Element>) -> String;
+}
+
+/// Renders HTML.
+pub struct Html;
+
+impl Renderer for Html {
+    fn text(&self, con: &Constructor, contents: &str) -> String {
+        con.replace_emojis(Constructor::sanitize(contents))
+    }
+
+    fn literal(&self, _: &Constructor, _: &Element, raw: &str) -> String {
+        Constructor::sanitize(raw)
+    }
+
+    fn element_open(&self, con: &Constructor, el: RefMut<Element>) -> String {
         use super::tag::*;
 
         if let Some(tag) = el.get_tag_name() {
@@ -100,14 +343,31 @@ impl Constructor {
 
                     Tag::Bold => Tag::open_simple_tag("b"),
                     Tag::Color => Tag::open_color_tag(el),
+                    Tag::InlineSpoiler => Tag::open_ispoiler_tag(el),
                     Tag::Italics => Tag::open_simple_tag("i"),
                     Tag::Underline => Tag::open_simple_tag("u"),
                     Tag::Strikethrough => Tag::open_simple_tag("s"),
 
-                    Tag::Code => Tag::open_simple_tag("pre"),
+                    Tag::Code => Tag::open_code_tag(el),
+                    Tag::InlineCode => Tag::open_icode_tag(el),
 
+                    Tag::Attachment => Tag::open_attach_tag(el),
                     Tag::Image => Tag::open_img_tag(el),
                     Tag::Link => Tag::open_url_tag(el),
+                    Tag::Media => Tag::open_media_tag(el),
+
+                    Tag::Mention => Tag::open_mention_tag(el),
+                    Tag::User => Tag::open_user_tag(el),
+
+                    Tag::List => Tag::open_list_tag(el),
+                    Tag::ListItem => Tag::open_simple_tag("li"),
+                    Tag::Quote => Tag::open_quote_tag(el),
+                    Tag::Spoiler => Tag::open_spoiler_tag(el, !con.allow_block_spoilers),
+
+                    Tag::Invalid => match con.custom_tags.get(tag) {
+                        Some(custom) => custom.open(el.get_argument()),
+                        None => el.to_open_str(),
+                    },
 
                     _ => el.to_open_str(),
                 }
@@ -121,11 +381,28 @@ impl Constructor {
         }
     }
 
-    fn element_contents(&self, el: RefMut<Element>, contents: String) -> String {
+    fn element_contents(
+        &self,
+        con: &Constructor,
+        node: &mut Node<Element>,
+        contents: String,
+    ) -> String {
+        let el = node.borrow_mut();
+
         if let Some(tag) = el.get_tag_name() {
             match Tag::get_by_name(tag) {
+                Tag::Attachment => Tag::fill_attach_tag(el, contents, &con.attachments),
+                Tag::Code => {
+                    // Highlighting reads the raw input, which borrows the children.
+                    drop(el);
+                    let raw = Tag::get_raw_code(node);
+                    Tag::fill_code_tag(node.borrow_mut(), contents, &raw)
+                }
                 Tag::Image => Tag::fill_img_tag(el, contents),
-                Tag::Link => Tag::fill_url_tag(el, contents),
+                Tag::Link => Tag::fill_url_tag(el, contents, &con.media_providers),
+                Tag::Media => Tag::fill_media_tag(el, contents, &con.media_providers),
+                Tag::Mention => Tag::fill_mention_tag(el, &con.mentions),
+                Tag::User => Tag::fill_user_tag(el, contents),
                 _ => contents,
             }
         } else {
@@ -133,24 +410,34 @@ impl Constructor {
         }
     }
 
-    fn element_close(&self, el: RefMut<Element>) -> String {
+    fn element_close(&self, con: &Constructor, el: RefMut<Element>) -> String {
         // Only named elements close with output.
         if let Some(tag) = el.get_tag_name() {
             // Only unbroken tags render HTML.
             if !el.is_broken() {
                 match Tag::get_by_name(tag) {
-                    Tag::Invalid => el.to_close_str(),
+                    Tag::Invalid => match con.custom_tags.get(tag) {
+                        Some(custom) => custom.close(el.get_argument()),
+                        None => el.to_close_str(),
+                    },
 
                     Tag::Bold => Tag::close_simple_tag("b"),
                     Tag::Color => Tag::close_simple_tag("span"),
+                    Tag::InlineSpoiler => Tag::close_spoiler_tag(true),
                     Tag::Italics => Tag::close_simple_tag("i"),
                     Tag::Underline => Tag::close_simple_tag("u"),
                     Tag::Strikethrough => Tag::close_simple_tag("s"),
 
-                    Tag::Code => Tag::close_simple_tag("pre"),
+                    Tag::Code => Tag::close_code_tag(el),
+                    Tag::InlineCode => Tag::close_simple_tag("code"),
 
                     Tag::Link => Tag::close_simple_tag("a"),
 
+                    Tag::List => Tag::close_list_tag(el),
+                    Tag::ListItem => Tag::close_simple_tag("li"),
+                    Tag::Quote => Tag::close_quote_tag(),
+                    Tag::Spoiler => Tag::close_spoiler_tag(!con.allow_block_spoilers),
+
                     // Self-closing tags do not close.
                     _ => String::new(),
                 }
@@ -165,49 +452,6 @@ impl Constructor {
             String::new()
         }
     }
-
-    /// Add emojis
-    pub fn replace_emojis(&self, input: String) -> String {
-        let mut result = input;
-        let mut hits: u8 = 0;
-        let mut hit_map: HashMap<u8, &String> = HashMap::with_capacity(self.smilies.count());
-
-        for (code, replace_with) in self.smilies.iter() {
-            if result.contains(code) {
-                hit_map.insert(hits, replace_with);
-                result = result.replace(code, &format!("\r{}", hits));
-                hits += 1;
-            }
-        }
-
-        for (hit, replace_with) in hit_map {
-            result = result.replace(&format!("\r{}", hit), replace_with);
-        }
-
-        result
-    }
-
-    /// Sanitizes a char for HTML.
-    pub fn sanitize(input: &str) -> String {
-        // Some insane person did an extremely detailed benchmark of this.
-        // https://lise-henry.github.io/articles/optimising_strings.html
-        let len = input.len();
-        let mut output: Vec<u8> = Vec::with_capacity(len * 4);
-
-        for c in input.bytes() {
-            // https://cheatsheetseries.owasp.org/cheatsheets/Cross_Site_Scripting_Prevention_Cheat_Sheet.html
-            match c {
-                b'<' => output.extend_from_slice(b"&lt;"),
-                b'>' => output.extend_from_slice(b"&gt;"),
-                b'&' => output.extend_from_slice(b"&amp;"),
-                b'\"' => output.extend_from_slice(b"&quot;"),
-                b'\'' => output.extend_from_slice(b"&#x27;"),
-                _ => output.push(c),
-            }
-        }
-
-        unsafe { String::from_utf8_unchecked(output) }
-    }
 }
 
 mod tests {
@@ -238,6 +482,7 @@ mod tests {
         use super::{Constructor, Element, Smilies};
         use rctree::Node;
         use std::collections::HashMap;
+        use std::sync::Arc;
 
         let mut smilies: HashMap<String, String> = HashMap::default();
         smilies.insert(":c".to_string(), "☹️".to_string());
@@ -245,7 +490,8 @@ mod tests {
         smilies.insert("ookie".to_string(), "🤢".to_string());
 
         let con = Constructor {
-            smilies: Smilies::new_from_hashmap(&smilies),
+            smilies: Arc::new(Smilies::new_from_hashmap(&smilies)),
+            ..Default::default()
         };
 
         let mut ast = Node::new(Element::new_root());
@@ -255,6 +501,25 @@ mod tests {
         assert_eq!(out, "☹️ I want a 🍪!");
     }
 
+    #[test]
+    fn spoilers_disallowed() {
+        use super::Constructor;
+        use crate::bbcode::{tokenize, Parser};
+
+        let con = Constructor {
+            allow_block_spoilers: false,
+            ..Default::default()
+        };
+
+        let tokens = tokenize("[spoiler=Foo]Bar[/spoiler]").unwrap().1;
+        let ast = Parser::new_for_constructor(&con).parse(&tokens);
+
+        assert_eq!(
+            con.build(ast),
+            "<details class=\"bbCode tagIspoiler\"><summary>Spoiler: Foo</summary><span class=\"tagIspoiler-content\">Bar</span></details>"
+        );
+    }
+
     #[test]
     fn text_in_empty_nest() {
         use super::{Constructor, Element};
@@ -288,4 +553,28 @@ mod tests {
 
         assert_eq!(out, "Hello, world!");
     }
+
+    #[test]
+    fn version() {
+        use super::{Constructor, Smilies};
+        use std::sync::Arc;
+
+        let con = Constructor::new();
+        assert_eq!(con.get_version(), Constructor::new().get_version());
+
+        let with_smilies = Constructor {
+            smilies: Arc::new(Smilies::new_from_tuples(vec![(
+                ":c".to_string(),
+                "☹️".to_string(),
+            )])),
+            ..Default::default()
+        };
+        assert_ne!(con.get_version(), with_smilies.get_version());
+
+        let without_spoilers = Constructor {
+            allow_block_spoilers: false,
+            ..Default::default()
+        };
+        assert_ne!(con.get_version(), without_spoilers.get_version());
+    }
 }
@@ -0,0 +1,201 @@
+use super::{Constructor, ElementDisplay, Tag};
+use crate::orm::bbcode_custom_tags;
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use sea_orm::{entity::*, query::*, DatabaseConnection, DbErr};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+static CUSTOM_TAGS: OnceCell<Arc<CustomTags>> = OnceCell::new();
+
+/// Returns the custom tags loaded at startup, or none if they were never loaded.
+pub fn get_custom_tags() -> Arc<CustomTags> {
+    CUSTOM_TAGS.get().cloned().unwrap_or_default()
+}
+
+/// Loads active custom tags from the database for all future Constructors.
+/// Only the forum binary loads them. The XF chat binary renders custom tags as text.
+pub async fn init_custom_tags(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let models = bbcode_custom_tags::Entity::find()
+        .filter(bbcode_custom_tags::Column::Active.eq(true))
+        .all(db)
+        .await?;
+
+    let mut tags = CustomTags::default();
+    for model in models {
+        match CustomTag::new(
+            model.open_template,
+            model.close_template,
+            model.argument_regex.as_deref(),
+            model.allow_nesting,
+            model.is_block,
+        ) {
+            Ok(custom) => {
+                if !tags.insert(&model.tag, custom) {
+                    log::warn!(
+                        "Custom BbCode [{}] has an invalid or reserved name.",
+                        model.tag
+                    );
+                }
+            }
+            Err(err) => log::warn!("Custom BbCode [{}] has a bad regex: {}", model.tag, err),
+        }
+    }
+
+    log::info!("Loaded {} custom BbCode tags.", tags.count());
+
+    if CUSTOM_TAGS.set(Arc::new(tags)).is_err() {
+        log::warn!("Custom BbCode tags were already loaded.");
+    }
+
+    Ok(())
+}
+
+/// A simple replacement tag defined by an administrator.
+/// Templates are trusted HTML. The only substitution is `{option}`, which is sanitized.
+pub struct CustomTag {
+    pub open_template: String,
+    pub close_template: String,
+    /// If set, arguments must entirely match. If unset, arguments are not accepted.
+    pub argument_regex: Option<Regex>,
+    /// If false, this tag may not appear inside of itself.
+    pub allow_nesting: bool,
+    /// If true, this tag renders as a block and may not open inside of inline tags.
+    pub is_block: bool,
+}
+
+impl CustomTag {
+    pub fn new(
+        open_template: String,
+        close_template: String,
+        argument_regex: Option<&str>,
+        allow_nesting: bool,
+        is_block: bool,
+    ) -> Result<Self, regex::Error> {
+        Ok(Self {
+            open_template,
+            close_template,
+            argument_regex: match argument_regex {
+                Some(regex) if !regex.is_empty() => Some(Regex::new(&format!("^(?:{})$", regex))?),
+                _ => None,
+            },
+            allow_nesting,
+            is_block,
+        })
+    }
+
+    pub fn get_display(&self) -> ElementDisplay {
+        if self.is_block {
+            ElementDisplay::Block
+        } else {
+            ElementDisplay::Inline
+        }
+    }
+
+    /// Strips `=` and quotes from an argument.
+    fn get_option(arg: Option<&str>) -> Option<&str> {
+        let arg = arg?.strip_prefix('=')?.trim();
+        Some(match arg.strip_prefix('"') {
+            Some(quoted) => quoted.strip_suffix('"').unwrap_or(quoted),
+            None => arg,
+        })
+    }
+
+    pub fn is_valid_argument(&self, arg: Option<&str>) -> bool {
+        match arg {
+            Some(_) => match (&self.argument_regex, Self::get_option(arg)) {
+                (Some(regex), Some(option)) => regex.is_match(option),
+                _ => false,
+            },
+            None => true,
+        }
+    }
+
+    fn fill_template(template: &str, arg: Option<&str>) -> String {
+        template.replace(
+            "{option}",
+            &Constructor::sanitize(Self::get_option(arg).unwrap_or("")),
+        )
+    }
+
+    pub fn open(&self, arg: Option<&str>) -> String {
+        Self::fill_template(&self.open_template, arg)
+    }
+
+    pub fn close(&self, arg: Option<&str>) -> String {
+        Self::fill_template(&self.close_template, arg)
+    }
+}
+
+/// Registry of custom tags, keyed by lowercase name.
+#[derive(Default)]
+pub struct CustomTags(HashMap<String, CustomTag>);
+
+impl CustomTags {
+    /// Adds a tag. Returns false if the name cannot be used.
+    /// Built-in tags remain compiled in and may not be replaced.
+    pub fn insert(&mut self, name: &str, tag: CustomTag) -> bool {
+        if name.is_empty()
+            || !name.chars().all(|c| c.is_ascii_alphabetic())
+            || !matches!(Tag::get_by_name(name), Tag::Invalid)
+        {
+            return false;
+        }
+
+        self.0.insert(name.to_lowercase(), tag);
+        true
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomTag> {
+        self.0.get(&name.to_lowercase())
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, String, CustomTag> {
+        self.0.iter()
+    }
+}
+
+mod tests {
+    #[test]
+    fn argument() {
+        use super::CustomTag;
+
+        let tag = CustomTag::new(
+            "<span style=\"font-size: {option}px\">".to_string(),
+            "</span>".to_string(),
+            Some("[0-9]{1,2}"),
+            true,
+            false,
+        )
+        .unwrap();
+
+        assert!(tag.is_valid_argument(None));
+        assert!(tag.is_valid_argument(Some("=12")));
+        assert!(tag.is_valid_argument(Some("=\"12\"")));
+        assert!(!tag.is_valid_argument(Some("=123")));
+        assert!(!tag.is_valid_argument(Some("=12;color:red")));
+        assert!(!tag.is_valid_argument(Some(" size=12")));
+        assert_eq!(tag.open(Some("=12")), "<span style=\"font-size: 12px\">");
+
+        let tag = CustomTag::new("<i>".to_string(), "</i>".to_string(), None, true, false).unwrap();
+        assert!(!tag.is_valid_argument(Some("=12")));
+    }
+
+    #[test]
+    fn reserved() {
+        use super::{CustomTag, CustomTags};
+
+        let mut tags = CustomTags::default();
+        let new = || CustomTag::new(String::new(), String::new(), None, true, false).unwrap();
+
+        assert!(tags.insert("Highlight", new()));
+        assert!(!tags.insert("quote", new()));
+        assert!(!tags.insert("h1", new()));
+        assert!(tags.get("HIGHLIGHT").is_some());
+        assert_eq!(tags.count(), 1);
+    }
+}
@@ -39,6 +39,8 @@ pub struct Element<'str> {
     display: ElementDisplay,
     /// When parsing arguments, elements may break, which defaults rendering as text.
     broken: bool,
+    /// If true, this element is a custom tag and its display came from the tag's definition.
+    custom: bool,
     // If true, this element was explicitly defined and closed.
     explicit: bool,
 }
@@ -53,21 +55,32 @@ impl<'str> Element<'str> {
         };
 
         // Adjust display
-        el.display = match Tag::get_by_name(tag) {
-            Tag::Invalid => {
-                el.broken = true;
-                ElementDisplay::Inline
-            }
+        // Unknown tags are not broken yet, as they may be custom tags known to the Constructor.
+        el.display = Self::get_display_for_tag(tag);
+
+        el
+    }
+
+    /// Determines the display type of a tag by its name.
+    fn get_display_for_tag(tag: &str) -> ElementDisplay {
+        match Tag::get_by_name(tag) {
+            Tag::Invalid => ElementDisplay::Inline,
             Tag::Linebreak => ElementDisplay::Selfclosing,
             Tag::HorizontalRule => ElementDisplay::Selfclosing,
             Tag::Plain => ElementDisplay::Plain,
             Tag::Code => ElementDisplay::Preformatted,
+            Tag::InlineCode => ElementDisplay::Preformatted,
             Tag::Image => ElementDisplay::Plain,
+            Tag::Attachment => ElementDisplay::Plain,
             Tag::Link => ElementDisplay::Inline,
+            Tag::Media => ElementDisplay::Plain,
+            Tag::List => ElementDisplay::Block,
+            Tag::ListItem => ElementDisplay::Block,
+            Tag::Quote => ElementDisplay::Block,
+            Tag::Spoiler => ElementDisplay::Block,
+            Tag::InlineSpoiler => ElementDisplay::Inline,
             _ => ElementDisplay::Inline,
-        };
-
-        el
+        }
     }
 
     // Text-only element
@@ -88,6 +101,13 @@ impl<'str> Element<'str> {
                 display: ElementDisplay::Selfclosing,
                 ..Self::default()
             },
+            // Mentions are named "@", which no written tag can be.
+            Token::Mention(raw, name) => Self {
+                tag: Some("@"),
+                raw: Some(raw),
+                contents: Some(name),
+                ..Default::default()
+            },
             Token::Tag(raw, tag, arg) => Self::new_for_tag(raw, tag, *arg),
             Token::TagClose(raw, _) => Self::new_from_text(raw), // Closing tags are consumed unless they are unpaired.
             Token::Text(text) => Self::new_from_text(text),
@@ -140,6 +160,8 @@ impl<'str> Element<'str> {
         match self.display {
             ElementDisplay::Preformatted => false,
             ElementDisplay::Selfclosing => false,
+            // Lists may only contain items, so breaks between them are left as whitespace.
+            ElementDisplay::Block => !self.is_tag("list"),
             _ => true,
         }
     }
@@ -156,13 +178,32 @@ impl<'str> Element<'str> {
     }
 
     /// Exceptions list for tags.
-    pub fn can_parent_tag(&self, theirs: &'str str) -> bool {
+    pub fn can_parent_tag(&self, el: &Element) -> bool {
+        let theirs = match el.tag {
+            Some(theirs) => theirs,
+            None => return true,
+        };
+
+        // Block elements may never leak into inline elements, e.g. [b][quote][/quote][/b].
+        // Unknown elements, like [foo], render as text and are not restricted.
+        if let (ElementDisplay::Inline, ElementDisplay::Block) = (&self.display, &el.display) {
+            if !self.broken && !self.is_unknown_tag() {
+                return false;
+            }
+        }
+
         // A last resort for exceptional tags.
         // Almost all cases for parentage should be handled through ElementDisplay.
         match self.tag {
             Some(ours) => match Tag::get_by_name(ours) {
-                Tag::Link => match Tag::get_by_name(theirs) {
-                    Tag::Link => false,
+                // Links and mentions may not nest in each other.
+                Tag::Link | Tag::User => !matches!(
+                    Tag::get_by_name(theirs),
+                    Tag::Link | Tag::Mention | Tag::User
+                ),
+                // Items of a list which will not render are rendered as text.
+                Tag::List => match Tag::get_by_name(theirs) {
+                    Tag::ListItem => Tag::is_list_tag(self),
                     _ => true,
                 },
                 _ => true,
@@ -171,6 +212,11 @@ impl<'str> Element<'str> {
         }
     }
 
+    /// Returns true if this is a tag which is neither built in nor custom.
+    fn is_unknown_tag(&self) -> bool {
+        !self.custom && matches!(self.tag.map(Tag::get_by_name), Some(Tag::Invalid))
+    }
+
     pub fn clear_contents(&mut self) {
         self.contents = None;
     }
@@ -240,6 +286,16 @@ impl<'str> Element<'str> {
         self.explicit = true;
     }
 
+    /// Marks this element as a custom tag which displays as its definition says.
+    pub fn set_custom(&mut self, display: ElementDisplay) {
+        self.custom = true;
+        self.display = display;
+    }
+
+    pub fn set_display(&mut self, display: ElementDisplay) {
+        self.display = display;
+    }
+
     /// Unwinds element into an opening tag string.
     pub fn to_open_str(&self) -> String {
         self.raw.unwrap_or("").to_owned()
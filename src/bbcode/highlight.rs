@@ -0,0 +1,771 @@
+use super::Constructor;
+
+/// A language which [code] blocks may be highlighted as.
+/// Highlighting is a simple scan for comments, strings, numbers and keywords.
+/// Tokens are wrapped in classes, which are colored by the stylesheet.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    Bash,
+    C,
+    Cpp,
+    CSharp,
+    Css,
+    Go,
+    Java,
+    JavaScript,
+    Json,
+    Php,
+    Python,
+    Rust,
+    Sql,
+    TypeScript,
+}
+
+/// Syntax which the highlighter knows about for a language.
+struct Syntax {
+    keywords: &'static [&'static str],
+    literals: &'static [&'static str],
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Longer delimiters must come first, i.e. `"""` before `"`.
+    strings: &'static [&'static str],
+    /// If true, keywords match in any case, as in SQL.
+    ignore_case: bool,
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "char", "const", "continue", "default", "do", "double", "else",
+    "enum", "extern", "float", "for", "goto", "if", "inline", "int", "long", "register",
+    "restrict", "return", "short", "signed", "sizeof", "static", "struct", "switch", "typedef",
+    "union", "unsigned", "void", "volatile", "while",
+];
+
+const CPP_KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "explicit",
+    "extern",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "namespace",
+    "new",
+    "noexcept",
+    "operator",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "async",
+    "await",
+    "base",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "false",
+    "finally",
+    "float",
+    "for",
+    "foreach",
+    "get",
+    "if",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "out",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sealed",
+    "set",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "using",
+    "var",
+    "virtual",
+    "void",
+    "while",
+];
+
+const CSS_KEYWORDS: &[&str] = &["important", "inherit", "initial", "none", "unset"];
+
+const GO_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "type",
+    "var",
+];
+
+const JAVA_KEYWORDS: &[&str] = &[
+    "abstract",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "new",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "try",
+    "var",
+    "void",
+    "volatile",
+    "while",
+];
+
+const JAVASCRIPT_KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "let",
+    "new",
+    "of",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+const TYPESCRIPT_KEYWORDS: &[&str] = &[
+    "any",
+    "as",
+    "async",
+    "await",
+    "boolean",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "declare",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "keyof",
+    "let",
+    "namespace",
+    "never",
+    "new",
+    "number",
+    "of",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "return",
+    "static",
+    "string",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "try",
+    "type",
+    "typeof",
+    "unknown",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+
+const PHP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "array",
+    "as",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "echo",
+    "else",
+    "elseif",
+    "extends",
+    "final",
+    "finally",
+    "fn",
+    "for",
+    "foreach",
+    "function",
+    "if",
+    "implements",
+    "include",
+    "interface",
+    "match",
+    "namespace",
+    "new",
+    "private",
+    "protected",
+    "public",
+    "require",
+    "return",
+    "static",
+    "switch",
+    "throw",
+    "trait",
+    "try",
+    "use",
+    "while",
+];
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda",
+    "nonlocal", "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "type", "unsafe", "use",
+    "where", "while",
+];
+
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+    "local", "return", "then", "until", "while",
+];
+
+const SQL_KEYWORDS: &[&str] = &[
+    "add",
+    "all",
+    "alter",
+    "and",
+    "as",
+    "asc",
+    "between",
+    "by",
+    "case",
+    "check",
+    "column",
+    "constraint",
+    "create",
+    "cross",
+    "default",
+    "delete",
+    "desc",
+    "distinct",
+    "drop",
+    "else",
+    "end",
+    "exists",
+    "foreign",
+    "from",
+    "full",
+    "group",
+    "having",
+    "in",
+    "index",
+    "inner",
+    "insert",
+    "into",
+    "is",
+    "join",
+    "key",
+    "left",
+    "like",
+    "limit",
+    "not",
+    "offset",
+    "on",
+    "or",
+    "order",
+    "outer",
+    "primary",
+    "references",
+    "returning",
+    "right",
+    "select",
+    "set",
+    "table",
+    "then",
+    "union",
+    "unique",
+    "update",
+    "using",
+    "values",
+    "when",
+    "where",
+    "with",
+];
+
+impl Language {
+    /// Returns the language by its name or a common alias.
+    pub fn get_by_name(name: &str) -> Option<Self> {
+        match &*name.to_lowercase() {
+            "bash" | "sh" | "shell" | "zsh" => Some(Self::Bash),
+            "c" | "h" => Some(Self::C),
+            "cpp" | "c++" | "cc" | "hpp" => Some(Self::Cpp),
+            "csharp" | "c#" | "cs" => Some(Self::CSharp),
+            "css" | "scss" => Some(Self::Css),
+            "go" | "golang" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            "javascript" | "js" | "jsx" => Some(Self::JavaScript),
+            "json" => Some(Self::Json),
+            "php" => Some(Self::Php),
+            "python" | "py" => Some(Self::Python),
+            "rust" | "rs" => Some(Self::Rust),
+            "sql" | "mysql" | "postgres" | "postgresql" | "sqlite" => Some(Self::Sql),
+            "typescript" | "ts" | "tsx" => Some(Self::TypeScript),
+            _ => None,
+        }
+    }
+
+    /// Returns the canonical name, which is used in class names and Markdown fences.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::C => "c",
+            Self::Cpp => "cpp",
+            Self::CSharp => "csharp",
+            Self::Css => "css",
+            Self::Go => "go",
+            Self::Java => "java",
+            Self::JavaScript => "javascript",
+            Self::Json => "json",
+            Self::Php => "php",
+            Self::Python => "python",
+            Self::Rust => "rust",
+            Self::Sql => "sql",
+            Self::TypeScript => "typescript",
+        }
+    }
+
+    /// Returns the human-readable name of this language.
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Self::Bash => "Bash",
+            Self::C => "C",
+            Self::Cpp => "C++",
+            Self::CSharp => "C#",
+            Self::Css => "CSS",
+            Self::Go => "Go",
+            Self::Java => "Java",
+            Self::JavaScript => "JavaScript",
+            Self::Json => "JSON",
+            Self::Php => "PHP",
+            Self::Python => "Python",
+            Self::Rust => "Rust",
+            Self::Sql => "SQL",
+            Self::TypeScript => "TypeScript",
+        }
+    }
+
+    fn get_syntax(&self) -> Syntax {
+        const C_STYLE_COMMENT: Option<(&str, &str)> = Some(("/*", "*/"));
+        const C_STYLE_LITERALS: &[&str] = &["false", "null", "true"];
+
+        match self {
+            Self::Bash => Syntax {
+                keywords: SHELL_KEYWORDS,
+                literals: &["false", "true"],
+                line_comments: &["#"],
+                block_comment: None,
+                strings: &["\"", "'"],
+                ignore_case: false,
+            },
+            Self::C => Syntax {
+                keywords: C_KEYWORDS,
+                literals: &["NULL", "false", "true"],
+                line_comments: &["//", "#"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "'"],
+                ignore_case: false,
+            },
+            Self::Cpp => Syntax {
+                keywords: CPP_KEYWORDS,
+                literals: &["NULL", "false", "nullptr", "true"],
+                line_comments: &["//", "#"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "'"],
+                ignore_case: false,
+            },
+            Self::CSharp => Syntax {
+                keywords: CSHARP_KEYWORDS,
+                literals: C_STYLE_LITERALS,
+                line_comments: &["//"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "'"],
+                ignore_case: false,
+            },
+            Self::Css => Syntax {
+                keywords: CSS_KEYWORDS,
+                literals: &[],
+                line_comments: &[],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "'"],
+                ignore_case: true,
+            },
+            Self::Go => Syntax {
+                keywords: GO_KEYWORDS,
+                literals: &["false", "iota", "nil", "true"],
+                line_comments: &["//"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "`", "'"],
+                ignore_case: false,
+            },
+            Self::Java => Syntax {
+                keywords: JAVA_KEYWORDS,
+                literals: C_STYLE_LITERALS,
+                line_comments: &["//"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"\"\"", "\"", "'"],
+                ignore_case: false,
+            },
+            Self::JavaScript => Syntax {
+                keywords: JAVASCRIPT_KEYWORDS,
+                literals: &["false", "null", "true", "undefined"],
+                line_comments: &["//"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "'", "`"],
+                ignore_case: false,
+            },
+            Self::Json => Syntax {
+                keywords: &[],
+                literals: C_STYLE_LITERALS,
+                line_comments: &[],
+                block_comment: None,
+                strings: &["\""],
+                ignore_case: false,
+            },
+            Self::Php => Syntax {
+                keywords: PHP_KEYWORDS,
+                literals: C_STYLE_LITERALS,
+                line_comments: &["//", "#"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "'"],
+                ignore_case: true,
+            },
+            Self::Python => Syntax {
+                keywords: PYTHON_KEYWORDS,
+                literals: &["False", "None", "True"],
+                line_comments: &["#"],
+                block_comment: None,
+                strings: &["\"\"\"", "'''", "\"", "'"],
+                ignore_case: false,
+            },
+            // Single quotes are lifetimes as often as they are characters, so they are left alone.
+            Self::Rust => Syntax {
+                keywords: RUST_KEYWORDS,
+                literals: &["false", "None", "Some", "true"],
+                line_comments: &["//"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\""],
+                ignore_case: false,
+            },
+            Self::Sql => Syntax {
+                keywords: SQL_KEYWORDS,
+                literals: &["false", "null", "true"],
+                line_comments: &["--"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["'", "\""],
+                ignore_case: true,
+            },
+            Self::TypeScript => Syntax {
+                keywords: TYPESCRIPT_KEYWORDS,
+                literals: &["false", "null", "true", "undefined"],
+                line_comments: &["//"],
+                block_comment: C_STYLE_COMMENT,
+                strings: &["\"", "'", "`"],
+                ignore_case: false,
+            },
+        }
+    }
+
+    /// Renders raw code as sanitized HTML with highlighted tokens.
+    pub fn highlight(&self, code: &str) -> String {
+        let syntax = self.get_syntax();
+        let mut output = String::with_capacity(code.len() * 2);
+        let mut rest = code;
+
+        while let Some(c) = rest.chars().next() {
+            let (class, len) = if let Some(len) = syntax.match_comment(rest) {
+                (Some("comment"), len)
+            } else if let Some(len) = syntax.match_string(rest) {
+                (Some("string"), len)
+            } else if c.is_ascii_digit() {
+                (Some("number"), word_len(rest, true))
+            } else if c.is_alphabetic() || c == '_' || c == '$' {
+                let len = word_len(rest, false);
+                (syntax.get_word_class(&rest[..len]), len)
+            } else {
+                (None, c.len_utf8())
+            };
+
+            let token = Constructor::sanitize(&rest[..len]);
+            match class {
+                Some(class) => {
+                    output.push_str(&format!("<span class=\"hl-{}\">{}</span>", class, token))
+                }
+                None => output.push_str(&token),
+            }
+
+            rest = &rest[len..];
+        }
+
+        output
+    }
+}
+
+impl Syntax {
+    /// Returns the length of a comment at the start of the input.
+    fn match_comment(&self, input: &str) -> Option<usize> {
+        if self.line_comments.iter().any(|p| input.starts_with(p)) {
+            return Some(input.find(['\r', '\n']).unwrap_or(input.len()));
+        }
+
+        let (open, close) = self.block_comment?;
+        let body = input.strip_prefix(open)?;
+        Some(match body.find(close) {
+            Some(end) => open.len() + end + close.len(),
+            None => input.len(),
+        })
+    }
+
+    /// Returns the length of a string at the start of the input.
+    /// Unterminated strings run to the end of the input.
+    fn match_string(&self, input: &str) -> Option<usize> {
+        let quote = self.strings.iter().find(|q| input.starts_with(*q))?;
+        let body = &input[quote.len()..];
+        let mut escaped = false;
+
+        for (i, c) in body.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if body[i..].starts_with(quote) {
+                return Some(quote.len() + i + quote.len());
+            }
+        }
+
+        Some(input.len())
+    }
+
+    fn get_word_class(&self, word: &str) -> Option<&'static str> {
+        let matches = |list: &[&str]| {
+            if self.ignore_case {
+                list.iter().any(|k| k.eq_ignore_ascii_case(word))
+            } else {
+                list.contains(&word)
+            }
+        };
+
+        if matches(self.keywords) {
+            Some("keyword")
+        } else if matches(self.literals) {
+            Some("literal")
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the length of the identifier or number at the start of the input.
+/// Numbers may contain a decimal point.
+fn word_len(input: &str, number: bool) -> usize {
+    input
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || (number && c == '.')))
+        .unwrap_or(input.len())
+}
+
+mod tests {
+    #[test]
+    fn get_by_name() {
+        use super::Language;
+
+        assert_eq!(Language::get_by_name("Rust"), Some(Language::Rust));
+        assert_eq!(Language::get_by_name("rs"), Some(Language::Rust));
+        assert_eq!(Language::get_by_name("postgres"), Some(Language::Sql));
+        assert_eq!(Language::get_by_name("brainfuck"), None);
+    }
+
+    #[test]
+    fn highlight() {
+        use super::Language;
+
+        assert_eq!(
+            Language::Rust.highlight("let x = \"<b>\"; // 1"),
+            "<span class=\"hl-keyword\">let</span> x = <span class=\"hl-string\">&quot;&lt;b&gt;&quot;</span>; <span class=\"hl-comment\">// 1</span>"
+        );
+        assert_eq!(
+            Language::Sql.highlight("SELECT 1 FROM t"),
+            "<span class=\"hl-keyword\">SELECT</span> <span class=\"hl-number\">1</span> <span class=\"hl-keyword\">FROM</span> t"
+        );
+        assert_eq!(
+            Language::Python.highlight("'a\\'b' + None"),
+            "<span class=\"hl-string\">&#x27;a\\&#x27;b&#x27;</span> + <span class=\"hl-literal\">None</span>"
+        );
+        assert_eq!(
+            Language::C.highlight("/* open"),
+            "<span class=\"hl-comment\">/* open</span>"
+        );
+    }
+}
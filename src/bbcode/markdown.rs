@@ -0,0 +1,182 @@
+use super::plaintext::{get_list_item, get_mention, get_quote};
+use super::tag::{ListStyle, MediaTarget};
+use super::{Constructor, Element, Renderer, Tag};
+use rctree::Node;
+use std::cell::RefMut;
+
+/// Renders CommonMark, for bridges to services which do not speak BbCode.
+/// Underlines and colors have no Markdown and are dropped. Spoilers use the `||` extension.
+pub struct Markdown;
+
+impl Markdown {
+    /// Escapes punctuation which Markdown would format.
+    pub fn escape(input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+
+        for c in input.chars() {
+            if matches!(
+                c,
+                '\\' | '*' | '_' | '`' | '[' | ']' | '(' | ')' | '~' | '|' | '<' | '>' | '#'
+            ) {
+                output.push('\\');
+            }
+            output.push(c);
+        }
+
+        output
+    }
+
+    /// Writes a link destination, which may not break out of its parentheses.
+    fn destination(url: &url::Url) -> String {
+        url.as_str().replace('(', "%28").replace(')', "%29")
+    }
+}
+
+impl Renderer for Markdown {
+    fn text(&self, _: &Constructor, contents: &str) -> String {
+        Self::escape(contents)
+    }
+
+    fn literal(&self, _: &Constructor, parent: &Element, raw: &str) -> String {
+        match parent.get_tag_name().map(Tag::get_by_name) {
+            Some(Tag::Code) | Some(Tag::InlineCode) if !parent.is_broken() => raw.to_owned(),
+            _ => Self::escape(raw),
+        }
+    }
+
+    fn element_open(&self, _: &Constructor, el: RefMut<Element>) -> String {
+        match el.get_tag_name() {
+            Some(_) if el.is_broken() => Self::escape(&el.to_open_str()),
+            Some(tag) => match Tag::get_by_name(tag) {
+                Tag::HorizontalRule => String::from("\n\n---\n\n"),
+                Tag::Linebreak => String::from("  \n"),
+
+                Tag::Bold => String::from("**"),
+                Tag::InlineSpoiler => String::from("||"),
+                Tag::Italics => String::from("_"),
+                Tag::Strikethrough => String::from("~~"),
+
+                _ => String::new(),
+            },
+            None => String::new(),
+        }
+    }
+
+    fn element_contents(
+        &self,
+        con: &Constructor,
+        node: &mut Node<Element>,
+        contents: String,
+    ) -> String {
+        let tag = match node.borrow().get_tag_name() {
+            Some(tag) => tag,
+            None => return contents,
+        };
+
+        if !con.is_renderable(&node.borrow()) {
+            node.borrow_mut().set_broken();
+            return contents;
+        }
+
+        // Embeds read their raw text, as Markdown escapes would change it.
+        let output = match Tag::get_by_name(tag) {
+            Tag::Attachment => {
+                let el = node.borrow();
+                Tag::get_attachment(&el, &Constructor::get_text(node), &con.attachments)
+                    .and_then(|(id, _)| con.attachments.get_name(id))
+                    .map(Self::escape)
+            }
+            Tag::Code => {
+                // The fence must be longer than any run of backticks inside of it.
+                let fence = "`".repeat(longest_backticks(&contents).max(2) + 1);
+                Some(format!(
+                    "\n\n{}{}\n{}\n{}\n\n",
+                    fence,
+                    Tag::get_code_language(&node.borrow())
+                        .map(|language| language.get_name())
+                        .unwrap_or_default(),
+                    contents.trim_matches(['\r', '\n']),
+                    fence
+                ))
+            }
+            Tag::InlineCode => {
+                let fence = "`".repeat(longest_backticks(&contents) + 1);
+                // Code which starts or ends with a backtick is padded from the fence.
+                Some(if contents.starts_with('`') || contents.ends_with('`') {
+                    format!("{} {} {}", fence, contents, fence)
+                } else {
+                    format!("{}{}{}", fence, contents, fence)
+                })
+            }
+            Tag::Image => Tag::get_img_url(&Constructor::get_text(node))
+                .map(|url| format!("![]({})", Self::destination(&url))),
+            Tag::Link => {
+                let text = Constructor::get_text(node);
+                let el = node.borrow();
+                Tag::get_url_target(&el, &text).map(|url| {
+                    if !el.has_argument() || text.trim().is_empty() {
+                        format!("<{}>", url.as_str())
+                    } else {
+                        format!("[{}]({})", contents, Self::destination(&url))
+                    }
+                })
+            }
+            Tag::List => Some(format!("\n\n{}\n\n", contents.trim_matches('\n'))),
+            Tag::ListItem => Some(get_list_item(node, &contents, |style, n| match style {
+                ListStyle::Bullet => String::from("-"),
+                _ => format!("{}.", n),
+            })),
+            Tag::Media => Tag::get_media_target(
+                &node.borrow(),
+                &Constructor::get_text(node),
+                &con.media_providers,
+            )
+            .map(|target| match target {
+                MediaTarget::Embed(provider, id) => format!("<{}>", provider.get_page_url(&id)),
+                MediaTarget::Link(url) => format!("<{}>", url.as_str()),
+            }),
+            Tag::Mention => Some(get_mention(&node.borrow(), con, Self::escape)),
+            Tag::Quote => Some(format!("\n{}", get_quote(node, &contents, Self::escape))),
+            Tag::Spoiler => Tag::get_spoiler_title(&node.borrow()).map(|title| {
+                format!(
+                    "\n\n**{}**\n||{}||\n\n",
+                    Self::escape(&title),
+                    contents.trim_matches('\n')
+                )
+            }),
+            _ => Some(contents.to_owned()),
+        };
+
+        match output {
+            Some(output) => output,
+            None => {
+                node.borrow_mut().set_broken();
+                contents
+            }
+        }
+    }
+
+    fn element_close(&self, _: &Constructor, el: RefMut<Element>) -> String {
+        match el.get_tag_name() {
+            Some(_) if el.is_broken() => Self::escape(&el.to_close_str()),
+            Some(tag) => match Tag::get_by_name(tag) {
+                Tag::Bold => String::from("**"),
+                Tag::InlineSpoiler => String::from("||"),
+                Tag::Italics => String::from("_"),
+                Tag::Strikethrough => String::from("~~"),
+
+                _ => String::new(),
+            },
+            None => String::new(),
+        }
+    }
+}
+
+/// Returns the longest run of backticks, which a code fence must be longer than.
+fn longest_backticks(input: &str) -> usize {
+    input
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
@@ -0,0 +1,293 @@
+use super::Constructor;
+use once_cell::sync::OnceCell;
+use url::Url;
+
+static MEDIA_PROVIDERS: OnceCell<MediaProviders> = OnceCell::new();
+
+/// Returns the allowlist loaded at startup, or the default allowlist if it was never loaded.
+pub fn get_media_providers() -> MediaProviders {
+    MEDIA_PROVIDERS.get().cloned().unwrap_or_default()
+}
+
+/// Loads the allowlist for all future Constructors from MEDIA_PROVIDERS,
+/// a comma separated list of provider names. If it is not set, the default allowlist is used.
+pub fn init_media_providers() {
+    let providers = match std::env::var("MEDIA_PROVIDERS") {
+        Ok(list) => MediaProviders::new_from_list(&list),
+        Err(_) => MediaProviders::default(),
+    };
+
+    log::info!("Allowing {} media providers.", providers.count());
+
+    if MEDIA_PROVIDERS.set(providers).is_err() {
+        log::warn!("Media providers were already loaded.");
+    }
+}
+
+/// A known host of embeddable media.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MediaProvider {
+    /// Video and audio attachments hosted by us.
+    Attachment,
+    Vimeo,
+    YouTube,
+}
+
+impl MediaProvider {
+    pub fn get_by_name(name: &str) -> Option<Self> {
+        match &*name.to_lowercase() {
+            "attachment" => Some(Self::Attachment),
+            "vimeo" => Some(Self::Vimeo),
+            "youtube" => Some(Self::YouTube),
+            _ => None,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Attachment => "attachment",
+            Self::Vimeo => "vimeo",
+            Self::YouTube => "youtube",
+        }
+    }
+
+    /// Extracts a media id from a URL this provider hosts.
+    pub fn id_from_url(&self, url: &Url) -> Option<String> {
+        let host = url.host_str()?.trim_start_matches("www.");
+        let mut path = url.path_segments()?;
+
+        let id = match self {
+            // Our attachments are not linked to by URL.
+            Self::Attachment => None,
+            Self::Vimeo => match host {
+                "vimeo.com" => path.next().map(str::to_owned),
+                "player.vimeo.com" => match path.next() {
+                    Some("video") => path.next().map(str::to_owned),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Self::YouTube => match host {
+                "youtu.be" => path.next().map(str::to_owned),
+                "youtube.com" | "m.youtube.com" | "youtube-nocookie.com" => match path.next() {
+                    Some("watch") => url
+                        .query_pairs()
+                        .find(|(k, _)| k == "v")
+                        .map(|(_, v)| v.into_owned()),
+                    Some("embed") | Some("shorts") | Some("live") => path.next().map(str::to_owned),
+                    _ => None,
+                },
+                _ => None,
+            },
+        }?;
+
+        if self.is_valid_id(&id) {
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the id is something this provider could produce.
+    /// Ids are placed into URLs, so this must be strict.
+    pub fn is_valid_id(&self, id: &str) -> bool {
+        match self {
+            // BLAKE3 hash
+            Self::Attachment => id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit()),
+            Self::Vimeo => {
+                !id.is_empty() && id.len() <= 12 && id.chars().all(|c| c.is_ascii_digit())
+            }
+            Self::YouTube => {
+                id.len() == 11
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            }
+        }
+    }
+
+    /// Returns the address of the player for this media.
+    pub fn get_embed_url(&self, id: &str) -> String {
+        match self {
+            Self::Attachment => format!("/content/{}/media", id),
+            Self::Vimeo => format!("https://player.vimeo.com/video/{}?autoplay=1&dnt=1", id),
+            Self::YouTube => format!("https://www.youtube-nocookie.com/embed/{}?autoplay=1", id),
+        }
+    }
+
+    /// Returns the address where this media is watched, for output which cannot embed a player.
+    pub fn get_page_url(&self, id: &str) -> String {
+        match self {
+            Self::Attachment => self.get_embed_url(id),
+            Self::Vimeo => format!("https://vimeo.com/{}", id),
+            Self::YouTube => format!("https://www.youtube.com/watch?v={}", id),
+        }
+    }
+
+    /// Returns the human-readable name of this provider.
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Self::Attachment => "attachment",
+            Self::Vimeo => "Vimeo",
+            Self::YouTube => "YouTube",
+        }
+    }
+
+    /// Renders the media player. The id must be valid.
+    pub fn to_html(&self, id: &str) -> String {
+        match self {
+            // Our own media needs no privacy protection.
+            Self::Attachment => format!(
+                "<video class=\"bbCode tagMedia\" controls preload=\"metadata\" src=\"{}\"></video>",
+                self.get_embed_url(id)
+            ),
+            // Third party players are not contacted until the user clicks through.
+            // The srcdoc placeholder navigates the frame itself, so this works without JavaScript.
+            _ => {
+                let placeholder = format!(
+                    "<style>html,body{{height:100%;margin:0}}a{{display:flex;height:100%;align-items:center;justify-content:center;background:#000;color:#fff;font:1.2em sans-serif;text-decoration:none}}</style><a href=\"{}\">&#9654; Play {} video</a>",
+                    Constructor::sanitize(&self.get_embed_url(id)),
+                    self.get_label()
+                );
+
+                format!(
+                    "<iframe class=\"bbCode tagMedia tagMedia--{}\" srcdoc=\"{}\" allow=\"autoplay; encrypted-media; fullscreen; picture-in-picture\" allowfullscreen></iframe>",
+                    self.get_name(),
+                    Constructor::sanitize(&placeholder)
+                )
+            }
+        }
+    }
+}
+
+/// Allowlist of media providers which may be embedded.
+#[derive(Clone)]
+pub struct MediaProviders(Vec<MediaProvider>);
+
+impl Default for MediaProviders {
+    fn default() -> Self {
+        Self(vec![
+            MediaProvider::Attachment,
+            MediaProvider::Vimeo,
+            MediaProvider::YouTube,
+        ])
+    }
+}
+
+impl MediaProviders {
+    /// Builds an allowlist from provider names, ignoring any we do not know.
+    pub fn new_from_names(names: &[&str]) -> Self {
+        Self(
+            names
+                .iter()
+                .filter_map(|name| MediaProvider::get_by_name(name))
+                .collect(),
+        )
+    }
+
+    /// Builds an allowlist from a comma separated list of provider names, warning of any we do not know.
+    pub fn new_from_list(list: &str) -> Self {
+        let names: Vec<&str> = list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        for name in names.iter() {
+            if MediaProvider::get_by_name(name).is_none() {
+                log::warn!("Unknown media provider '{}' is not allowed.", name);
+            }
+        }
+
+        Self::new_from_names(&names)
+    }
+
+    /// Returns the provider if it is allowed.
+    pub fn get_by_name(&self, name: &str) -> Option<MediaProvider> {
+        MediaProvider::get_by_name(name).filter(|provider| self.0.contains(provider))
+    }
+
+    /// Returns the first allowed provider which recognizes this URL, and the media id.
+    pub fn get_by_url(&self, url: &Url) -> Option<(MediaProvider, String)> {
+        self.0
+            .iter()
+            .find_map(|provider| provider.id_from_url(url).map(|id| (*provider, id)))
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, MediaProvider> {
+        self.0.iter()
+    }
+}
+
+mod tests {
+    #[test]
+    fn id_from_url() {
+        use super::MediaProvider;
+        use url::Url;
+
+        let youtube = MediaProvider::YouTube;
+        for input in [
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtube.com/watch?t=10&v=dQw4w9WgXcQ",
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://youtu.be/dQw4w9WgXcQ",
+            "https://www.youtube.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        ] {
+            assert_eq!(
+                youtube.id_from_url(&Url::parse(input).unwrap()),
+                Some("dQw4w9WgXcQ".to_string())
+            );
+        }
+        for input in [
+            "https://www.youtube.com/",
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ\"",
+            "https://youtube.evil.com/watch?v=dQw4w9WgXcQ",
+            "https://vimeo.com/76979871",
+        ] {
+            assert_eq!(youtube.id_from_url(&Url::parse(input).unwrap()), None);
+        }
+
+        let vimeo = MediaProvider::Vimeo;
+        assert_eq!(
+            vimeo.id_from_url(&Url::parse("https://vimeo.com/76979871").unwrap()),
+            Some("76979871".to_string())
+        );
+        assert_eq!(
+            vimeo.id_from_url(&Url::parse("https://player.vimeo.com/video/76979871").unwrap()),
+            Some("76979871".to_string())
+        );
+        assert_eq!(
+            vimeo.id_from_url(&Url::parse("https://vimeo.com/channels/staffpicks").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn allowlist() {
+        use super::{MediaProvider, MediaProviders};
+
+        let providers = MediaProviders::new_from_names(&["youtube", "sneed"]);
+        assert_eq!(providers.count(), 1);
+        assert_eq!(
+            providers.get_by_name("YouTube"),
+            Some(MediaProvider::YouTube)
+        );
+        assert_eq!(providers.get_by_name("vimeo"), None);
+
+        let providers = MediaProviders::new_from_list(" Vimeo, attachment,,sneed ");
+        assert_eq!(providers.count(), 2);
+        assert_eq!(providers.get_by_name("vimeo"), Some(MediaProvider::Vimeo));
+        assert_eq!(
+            providers.get_by_name("attachment"),
+            Some(MediaProvider::Attachment)
+        );
+        assert_eq!(providers.get_by_name("youtube"), None);
+
+        assert_eq!(MediaProviders::new_from_list("").count(), 0);
+    }
+}
@@ -0,0 +1,130 @@
+use super::{Element, Tag};
+use rctree::Node;
+use std::collections::HashMap;
+
+/// A user as they are mentioned.
+struct MentionedUser {
+    id: i32,
+    name: String,
+}
+
+/// Users who may be mentioned with @name, keyed by the name they were mentioned by.
+/// Names are resolved ahead of time by the owner of the content, as renamed users still resolve.
+#[derive(Default)]
+pub struct Mentions(HashMap<String, MentionedUser>);
+
+impl Mentions {
+    /// Names are matched without case, so @foo mentions Foo.
+    pub fn insert(&mut self, mentioned_as: &str, id: i32, name: String) {
+        self.0
+            .insert(mentioned_as.to_lowercase(), MentionedUser { id, name });
+    }
+
+    /// Returns the id and current name of a user mentioned by name.
+    pub fn get(&self, mentioned_as: &str) -> Option<(i32, &str)> {
+        self.0
+            .get(&mentioned_as.to_lowercase())
+            .map(|user| (user.id, user.name.as_str()))
+    }
+
+    /// Returns the id of every user mentioned, once.
+    pub fn get_user_ids(&self) -> Vec<i32> {
+        let mut ids: Vec<i32> = self.0.values().map(|user| user.id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Users mentioned in BbCode, before they are resolved.
+#[derive(Debug, Default, PartialEq)]
+pub struct MentionTargets {
+    /// Names from @name, in lowercase.
+    pub names: Vec<String>,
+    /// Ids from [user=id].
+    pub user_ids: Vec<i32>,
+    /// Posts attributed by [quote="name, post: id"]. Quotes within quotes are not counted.
+    pub quoted_post_ids: Vec<i32>,
+    /// Users attributed by [quote="name, member: id"]. Quotes within quotes are not counted.
+    pub quoted_user_ids: Vec<i32>,
+}
+
+impl MentionTargets {
+    /// Finds mentions which would render.
+    /// Mentions inside of tags which are not parsed, like [code] and [plain], are not mentions.
+    pub fn from_ast(ast: &Node<Element>) -> Self {
+        let mut targets = Self::default();
+
+        for node in ast.descendants() {
+            let el = node.borrow();
+            let tag = match el.get_tag_name() {
+                Some(tag) => tag,
+                None => continue,
+            };
+
+            match Tag::get_by_name(tag) {
+                Tag::Mention if Self::renders(&node, &el) => {
+                    if let Some(name) = el.get_contents().map(str::to_lowercase) {
+                        if !targets.names.contains(&name) {
+                            targets.names.push(name);
+                        }
+                    }
+                }
+                Tag::User if Self::renders(&node, &el) => {
+                    if let Some(id) = Tag::get_user_id(&el) {
+                        if !targets.user_ids.contains(&id) {
+                            targets.user_ids.push(id);
+                        }
+                    }
+                }
+                Tag::Quote if Self::renders(&node, &el) && !Self::is_nested_quote(&node) => {
+                    if let Some(attribution) = Tag::get_quote_attribution(&el) {
+                        if let Some(post) = attribution.post {
+                            if !targets.quoted_post_ids.contains(&post) {
+                                targets.quoted_post_ids.push(post);
+                            }
+                        }
+                        if let Some(member) = attribution.member {
+                            if !targets.quoted_user_ids.contains(&member) {
+                                targets.quoted_user_ids.push(member);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        targets
+    }
+
+    /// Returns true if the node is inside of another quote.
+    fn is_nested_quote(node: &Node<Element>) -> bool {
+        node.ancestors().skip(1).any(|ancestor| {
+            let ancestor = ancestor.borrow();
+            matches!(
+                ancestor.get_tag_name().map(Tag::get_by_name),
+                Some(Tag::Quote)
+            )
+        })
+    }
+
+    /// Returns true if every parent of the node renders it as a tag.
+    fn renders(node: &Node<Element>, el: &Element) -> bool {
+        node.ancestors().skip(1).all(|ancestor| {
+            let ancestor = ancestor.borrow();
+            ancestor.can_parent() && ancestor.can_parent_tag(el)
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+            && self.user_ids.is_empty()
+            && self.quoted_post_ids.is_empty()
+            && self.quoted_user_ids.is_empty()
+    }
+}
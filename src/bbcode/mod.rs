@@ -1,17 +1,31 @@
 extern crate linkify;
 
+mod attachment;
 mod constructor;
+mod custom;
 mod element;
+mod highlight;
+mod markdown;
+mod media;
+mod mention;
 mod parser;
+mod plaintext;
 mod smilie;
 mod tag;
 mod token;
 mod tokenize;
 
-pub use constructor::Constructor;
+pub use attachment::Attachments;
+pub use constructor::{Constructor, Html, Renderer, RENDERER_VERSION};
+pub use custom::{get_custom_tags, init_custom_tags, CustomTag, CustomTags};
 pub use element::{Element, ElementDisplay};
+pub use highlight::Language;
+pub use markdown::Markdown;
+pub use media::{get_media_providers, init_media_providers, MediaProvider, MediaProviders};
+pub use mention::{MentionTargets, Mentions};
 pub use parser::Parser;
-pub use smilie::Smilies;
+pub use plaintext::PlainText;
+pub use smilie::{get_smilies, init_smilies, Smilies};
 pub use tag::Tag;
 pub use token::Token;
 pub use tokenize::tokenize;
@@ -19,23 +33,190 @@ pub use tokenize::tokenize;
 /// Generates a string of HTML from an &str of BbCode.
 #[no_mangle]
 pub fn parse(input: &str) -> String {
+    parse_with_constructor(input, &Constructor::new())
+}
+
+/// Generates a string of HTML from an &str of BbCode using a configured Constructor.
+pub fn parse_with_constructor(input: &str, constructor: &Constructor) -> String {
+    render_with_constructor(input, constructor, &Html)
+}
+
+/// Generates unformatted text from an &str of BbCode, for notifications and previews.
+pub fn to_plain_text(input: &str) -> String {
+    render_with_constructor(input, &Constructor::new(), &PlainText)
+        .trim()
+        .to_owned()
+}
+
+/// Generates Markdown from an &str of BbCode.
+pub fn to_markdown(input: &str) -> String {
+    render_with_constructor(input, &Constructor::new(), &Markdown)
+        .trim()
+        .to_owned()
+}
+
+/// Finds the users mentioned in an &str of BbCode, so they may be resolved before rendering.
+pub fn get_mentions(input: &str) -> MentionTargets {
+    let tokens: Vec<Token> = tokenize(input).expect("Failed to unwrap tokens.").1;
+    let ast = Parser::new().parse(&tokens);
+
+    MentionTargets::from_ast(&ast)
+}
+
+/// Generates any output format from an &str of BbCode using a configured Constructor.
+pub fn render_with_constructor<R: Renderer>(
+    input: &str,
+    constructor: &Constructor,
+    renderer: &R,
+) -> String {
     let tokens: Vec<Token> = tokenize(input).expect("Failed to unwrap tokens.").1;
 
     //println!("TOKENS: {:?}", tokens);
 
-    let mut parser = Parser::new();
+    let mut parser = Parser::new_for_constructor(constructor);
     let ast = parser.parse(&tokens);
 
     //for node in ast.descendants() {
     //    println!("{:?}", node);
     //}
 
-    let constructor = Constructor::new();
-    constructor.build(ast)
+    constructor.render(renderer, ast)
 }
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn attach() {
+        use super::{parse, parse_with_constructor, Attachments, Constructor};
+
+        let mut attachments = Attachments::default();
+        attachments.insert(
+            1,
+            "foo.png".to_string(),
+            "<thumb />".to_string(),
+            "<full />".to_string(),
+        );
+        let con = Constructor {
+            attachments,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "<thumb />",
+            parse_with_constructor("[attach]1[/attach]", &con)
+        );
+        assert_eq!("<thumb />", parse_with_constructor("[attach]1", &con));
+        assert_eq!(
+            "<full />",
+            parse_with_constructor("[attach=full]1[/attach]", &con)
+        );
+        assert_eq!(
+            "Foo <b><full /></b> Bar",
+            parse_with_constructor("Foo [b][attach=\"full\"]1[/attach][/b] Bar", &con)
+        );
+
+        // Attachments belonging to other content are broken.
+        assert_eq!(
+            "[attach]2[/attach]",
+            parse_with_constructor("[attach]2[/attach]", &con)
+        );
+        assert_eq!("[attach]1[/attach]", parse("[attach]1[/attach]"));
+        assert_eq!(
+            "[attach=foo]1[/attach]",
+            parse_with_constructor("[attach=foo]1[/attach]", &con)
+        );
+        assert_eq!(
+            "[attach]one[/attach]",
+            parse_with_constructor("[attach]one[/attach]", &con)
+        );
+    }
+
+    #[test]
+    fn code() {
+        use super::parse;
+
+        assert_eq!(
+            "<pre class=\"bbCode tagCode\" data-language=\"Rust\"><code class=\"language-rust\"><span class=\"hl-keyword\">let</span> x = <span class=\"hl-number\">1</span>;</code></pre>",
+            parse("[code=rust]\r\nlet x = 1;\r\n[/code]")
+        );
+        assert_eq!(
+            "<pre class=\"bbCode tagCode\" data-language=\"Rust\"><code class=\"language-rust\">a &lt; b</code></pre>",
+            parse("[code=rs]a < b[/code]")
+        );
+        assert_eq!(
+            "<pre>[b]Test[/b]</pre>",
+            parse("[code=klingon][b]Test[/b][/code]")
+        );
+        assert_eq!(
+            "Run <code class=\"bbCode tagIcode\">cargo [b]test[/b]</code>",
+            parse("Run [icode]cargo [b]test[/b][/icode]")
+        );
+        assert_eq!("[icode=x]Test[/icode]", parse("[icode=x]Test[/icode]"));
+    }
+
+    #[test]
+    fn custom() {
+        use super::{parse_with_constructor, Constructor, CustomTag, CustomTags};
+        use std::sync::Arc;
+
+        let mut custom_tags = CustomTags::default();
+        custom_tags.insert(
+            "size",
+            CustomTag::new(
+                "<span style=\"font-size: {option}px\">".to_string(),
+                "</span>".to_string(),
+                Some("[0-9]{1,2}"),
+                true,
+                false,
+            )
+            .unwrap(),
+        );
+        custom_tags.insert(
+            "box",
+            CustomTag::new(
+                "<div class=\"box\">".to_string(),
+                "</div>".to_string(),
+                None,
+                false,
+                true,
+            )
+            .unwrap(),
+        );
+        let con = Constructor {
+            custom_tags: Arc::new(custom_tags),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "<span style=\"font-size: 12px\"><b>&lt;Foo&gt;</b></span>",
+            parse_with_constructor("[size=12][b]<Foo>[/b][/size]", &con)
+        );
+        assert_eq!(
+            "[size=12;color:red]Foo[/size]",
+            parse_with_constructor("[size=12;color:red]Foo[/size]", &con)
+        );
+        assert_eq!(
+            "<div class=\"box\"><blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">Foo</div></blockquote></div>",
+            parse_with_constructor("[box][quote]Foo[/quote][/box]", &con)
+        );
+        assert_eq!(
+            "<span style=\"font-size: 12px\">[quote]Foo[/quote]</span>",
+            parse_with_constructor("[size=12][quote]Foo[/quote][/size]", &con)
+        );
+        assert_eq!(
+            "<b>[box]Foo[/box]</b>",
+            parse_with_constructor("[b][box]Foo[/box][/b]", &con)
+        );
+        assert_eq!(
+            "<div class=\"box\">[box]Foo[/box]</div>",
+            parse_with_constructor("[box][box]Foo[/box][/box]", &con)
+        );
+        assert_eq!(
+            "[box=1]Foo[/box]",
+            parse_with_constructor("[box=1]Foo[/box]", &con)
+        );
+    }
+
     #[test]
     fn img() {
         use super::parse;
@@ -174,6 +355,210 @@ mod tests {
         assert_eq!("[url]", parse("[url]"));
     }
 
+    #[test]
+    fn list() {
+        use super::parse;
+
+        assert_eq!(
+            "<ul class=\"bbCode tagList\"><li>Foo</li><li>Bar</li></ul>",
+            parse("[list][*]Foo[*]Bar[/list]")
+        );
+        assert_eq!(
+            "<ul class=\"bbCode tagList\">\n<li>Foo</li><li>Bar</li></ul>",
+            parse("[list]\n[*]Foo\n[*]Bar\n[/list]")
+        );
+        assert_eq!(
+            "<ol class=\"bbCode tagList\"><li>Foo</li><li>Bar</li></ol>",
+            parse("[list=1][*]Foo[/*][*]Bar[/list]")
+        );
+        assert_eq!(
+            "<ol class=\"bbCode tagList\" type=\"a\"><li>Foo</li></ol>",
+            parse("[list=a][*]Foo[/list]")
+        );
+        assert_eq!(
+            "<ul class=\"bbCode tagList\"><li><b>Foo</b></li><li>Bar</li></ul>",
+            parse("[list][*][b]Foo[*]Bar[/list]")
+        );
+        assert_eq!(
+            "<ul class=\"bbCode tagList\"><li>Foo<ol class=\"bbCode tagList\"><li>Bar</li></ol></li><li>Baz</li></ul>",
+            parse("[list][*]Foo[list=1][*]Bar[/list][*]Baz[/list]")
+        );
+        assert_eq!(
+            "<ul class=\"bbCode tagList\"><li>Foo</li></ul>",
+            parse("[list][*]Foo")
+        );
+        assert_eq!("[*]Foo", parse("[*]Foo"));
+        assert_eq!("[list=2][*]Foo[/list]", parse("[list=2][*]Foo[/list]"));
+        assert_eq!(
+            "[code][*]Foo[/code]",
+            parse("[plain][code][*]Foo[/code][/plain]")
+        );
+    }
+
+    #[test]
+    fn block_in_inline() {
+        use super::parse;
+
+        assert_eq!(
+            "<b>[list]<i>Foo</i>[/list]</b>",
+            parse("[b][list][i]Foo[/i][/list][/b]")
+        );
+        assert_eq!(
+            "<b>[quote]Foo[/quote]</b>",
+            parse("[b][quote]Foo[/quote][/b]")
+        );
+        assert_eq!(
+            "[foo]<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">Foo</div></blockquote>[/foo]",
+            parse("[foo][quote]Foo[/quote][/foo]")
+        );
+    }
+
+    #[test]
+    fn markdown() {
+        use super::to_markdown;
+
+        assert_eq!(
+            "Hello **world** \\*ok\\*",
+            to_markdown("Hello [b]world[/b] *ok*")
+        );
+        assert_eq!(
+            "Go to [Zombo\\_com](https://zombo.com/) or <https://zombo.com/x_y>",
+            to_markdown("Go to [url=https://zombo.com/]Zombo_com[/url] or https://zombo.com/x_y")
+        );
+        assert_eq!(
+            "> Foo said:\n> Hi",
+            to_markdown("[quote=\"Foo, post: 1\"]Hi[/quote]")
+        );
+        assert_eq!(
+            "1. One\n2. Two **2**  \n   - Nested",
+            to_markdown("[list=1][*]One\n[*]Two [b]2[/b]\n[list][*]Nested[/list][/list]")
+        );
+        assert_eq!("||Secret||", to_markdown("[ispoiler]Secret[/ispoiler]"));
+        assert_eq!(
+            "````\na *b* ```x```\n````",
+            to_markdown("[code]a *b* ```x```[/code]")
+        );
+        assert_eq!(
+            "```rust\nlet x = 1;\n```",
+            to_markdown("[code=rust]let x = 1;[/code]")
+        );
+        assert_eq!("Run ``a`b``", to_markdown("Run [icode]a`b[/icode]"));
+        assert_eq!(
+            "![](https://a.com/a.png)",
+            to_markdown("[img]https://a.com/a.png[/img]")
+        );
+        assert_eq!(
+            "<https://www.youtube.com/watch?v=dQw4w9WgXcQ>",
+            to_markdown("[media]https://youtu.be/dQw4w9WgXcQ[/media]")
+        );
+
+        // Broken tags must not form Markdown of their own.
+        assert_eq!(
+            "\\[foo\\]\\(javascript:x\\)",
+            to_markdown("[foo](javascript:x)")
+        );
+        assert_eq!(
+            "\\[url\\]javascript:alert\\(1\\)\\[/url\\]",
+            to_markdown("[url]javascript:alert(1)[/url]")
+        );
+    }
+
+    #[test]
+    fn media() {
+        use super::parse;
+
+        const YOUTUBE: &str = "<iframe class=\"bbCode tagMedia tagMedia--youtube\" srcdoc=\"&lt;style&gt;html,body{height:100%;margin:0}a{display:flex;height:100%;align-items:center;justify-content:center;background:#000;color:#fff;font:1.2em sans-serif;text-decoration:none}&lt;/style&gt;&lt;a href=&quot;https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?autoplay=1&quot;&gt;&amp;#9654; Play YouTube video&lt;/a&gt;\" allow=\"autoplay; encrypted-media; fullscreen; picture-in-picture\" allowfullscreen></iframe>";
+
+        assert_eq!(YOUTUBE, parse("[media=youtube]dQw4w9WgXcQ[/media]"));
+        assert_eq!(
+            YOUTUBE,
+            parse("[media=youtube]https://youtu.be/dQw4w9WgXcQ[/media]")
+        );
+        assert_eq!(
+            YOUTUBE,
+            parse("[media]https://www.youtube.com/watch?v=dQw4w9WgXcQ[/media]")
+        );
+        assert_eq!(
+            format!(
+                "{}<a class=\"bbCode tagUrl\" ref=\"nofollow\" href=\"https://youtu.be/dQw4w9WgXcQ\">https://youtu.be/dQw4w9WgXcQ</a>",
+                YOUTUBE
+            ),
+            parse("[url unfurl=\"true\"]https://youtu.be/dQw4w9WgXcQ[/url]")
+        );
+        assert_eq!(
+            "<a class=\"bbCode tagUrl\" ref=\"nofollow\" href=\"https://youtu.be/dQw4w9WgXcQ\">https://youtu.be/dQw4w9WgXcQ</a>",
+            parse("[url]https://youtu.be/dQw4w9WgXcQ[/url]")
+        );
+        assert_eq!(
+            "<video class=\"bbCode tagMedia\" controls preload=\"metadata\" src=\"/content/9e0834c0d3dd1f6a775b9af7523eff7b35e750afb8fcd2753eef06735e13c46f/media\"></video>",
+            parse("[media=attachment]9e0834c0d3dd1f6a775b9af7523eff7b35e750afb8fcd2753eef06735e13c46f[/media]")
+        );
+
+        // Unknown providers fall back to a link.
+        assert_eq!(
+            "<a class=\"bbCode tagUrl\" ref=\"nofollow\" href=\"https://zombo.com/video\">https://zombo.com/video</a>",
+            parse("[media]https://zombo.com/video[/media]")
+        );
+        assert_eq!(
+            "<a class=\"bbCode tagUrl\" ref=\"nofollow\" href=\"https://zombo.com/video\">https://zombo.com/video</a>",
+            parse("[media=zombo]https://zombo.com/video[/media]")
+        );
+        assert_eq!(
+            "[media=youtube]&quot;&gt;&lt;script&gt;[/media]",
+            parse("[media=youtube]\"><script>[/media]")
+        );
+        assert_eq!(
+            "[media=zombo]123[/media]",
+            parse("[media=zombo]123[/media]")
+        );
+        assert_eq!("[media][/media]", parse("[media][/media]"));
+    }
+
+    #[test]
+    fn mention() {
+        use super::{get_mentions, parse_with_constructor, to_plain_text, Constructor};
+
+        let mut con = Constructor::new();
+        con.mentions.insert("foo", 1, "Foo".to_string());
+        con.mentions.insert("foo bar", 2, "<Foo Bar>".to_string());
+
+        assert_eq!(
+            "Hi <a class=\"username mention\" href=\"/members/1/\">@Foo</a>",
+            parse_with_constructor("Hi @foo", &con)
+        );
+        assert_eq!(
+            "<a class=\"username mention\" href=\"/members/2/\">@&lt;Foo Bar&gt;</a>!",
+            parse_with_constructor("@\"Foo Bar\"!", &con)
+        );
+        assert_eq!("@nobody", parse_with_constructor("@nobody", &con));
+        assert_eq!(
+            "<a class=\"username mention\" href=\"/members/3/\"><b>Baz</b></a>",
+            parse_with_constructor("[user=3][b]Baz[/b][/user]", &con)
+        );
+        assert_eq!(
+            "[user=x]Baz[/user]",
+            parse_with_constructor("[user=x]Baz[/user]", &con)
+        );
+        assert_eq!(
+            "<pre>@foo</pre>",
+            parse_with_constructor("[code]@foo[/code]", &con)
+        );
+        assert_eq!("Hi Baz", to_plain_text("Hi [user=3]Baz[/user]"));
+
+        let mentions = get_mentions(
+            "@Foo @foo [user=3]Baz[/user] [code]@Code[/code] [plain]@Plain [user=4]x[/user][/plain] [url=https://a.com]@Link[/url]",
+        );
+        assert_eq!(mentions.names, vec!["foo".to_string()]);
+        assert_eq!(mentions.user_ids, vec![3]);
+
+        let quotes = get_mentions(
+            "[quote=\"Foo, post: 1, member: 2\"][quote=\"Bar, post: 3, member: 4\"]Hi[/quote][/quote] [quote=Baz]Hi[/quote] [code][quote=\"Qux, post: 5\"]Hi[/quote][/code]",
+        );
+        assert_eq!(quotes.quoted_post_ids, vec![1]);
+        assert_eq!(quotes.quoted_user_ids, vec![2]);
+        assert!(!quotes.is_empty());
+    }
+
     #[test]
     fn misc() {
         use super::parse;
@@ -196,6 +581,46 @@ mod tests {
         assert_eq!("[b]Foo[hr]bar[/b]", parse("[plain][b]Foo[hr]bar[/b]"));
     }
 
+    #[test]
+    fn plain_text() {
+        use super::to_plain_text;
+
+        assert_eq!("Hello world", to_plain_text("Hello [b]world[/b]"));
+        assert_eq!(
+            "Go to Zombo (https://zombo.com/) or https://zombo.com/",
+            to_plain_text(
+                "Go to [url=https://zombo.com/]Zombo[/url] or [url]https://zombo.com/[/url]"
+            )
+        );
+        assert_eq!(
+            "> Foo said:\n> Hi\n> there",
+            to_plain_text("[quote=Foo]Hi\nthere[/quote]")
+        );
+        assert_eq!(
+            "a. One\nb. Two",
+            to_plain_text("[list=a][*]One\n[*]Two[/list]")
+        );
+        assert_eq!(
+            "(Spoiler: Title) and (Spoiler)",
+            to_plain_text("[spoiler=Title]Secret[/spoiler] and [ispoiler]Secret[/ispoiler]")
+        );
+        assert_eq!("red", to_plain_text("[color=red]red[/color]"));
+
+        // Broken tags degrade as they do in HTML.
+        assert_eq!(
+            "[color=bad]x[/color]",
+            to_plain_text("[color=bad]x[/color]")
+        );
+        assert_eq!(
+            "[img]javascript:x[/img]",
+            to_plain_text("[img]javascript:x[/img]")
+        );
+        assert_eq!(
+            "[ispoiler=Foo]x[/ispoiler]",
+            to_plain_text("[ispoiler=Foo]x[/ispoiler]")
+        );
+    }
+
     #[test]
     fn pre() {
         use super::parse;
@@ -205,6 +630,88 @@ mod tests {
         assert_eq!("<pre>Foo\r\nbar&lt;/pre&gt;&lt;iframe&gt;</pre>", parse("[code]Foo\r\nbar</pre><iframe>[/code]"));
     }
 
+    #[test]
+    fn quote() {
+        use super::parse;
+
+        assert_eq!(
+            "<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">Test</div></blockquote>",
+            parse("[quote]Test[/quote]")
+        );
+        assert_eq!(
+            "<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-attribution\"><span class=\"username\">Foo</span> said:</div><div class=\"tagQuote-content\">Test</div></blockquote>",
+            parse("[quote=Foo]Test[/quote]")
+        );
+        assert_eq!(
+            "<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-attribution\"><a class=\"username\" href=\"/members/45/\">Foo</a> said: <a class=\"tagQuote-source\" href=\"/posts/123\" title=\"Go to post\">↑</a></div><div class=\"tagQuote-content\">Test</div></blockquote>",
+            parse("[quote=\"Foo, post: 123, member: 45\"]Test[/quote]")
+        );
+        assert_eq!(
+            "<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-attribution\"><span class=\"username\">Foo&#x27;s &amp; Bar</span> said:</div><div class=\"tagQuote-content\">Test</div></blockquote>",
+            parse("[quote=\"Foo's & Bar\"]Test[/quote]")
+        );
+        assert_eq!(
+            "<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\"><b>Test</b></div></blockquote>",
+            parse("[quote][b]Test[/quote]")
+        );
+        assert_eq!(
+            "[quote=\"Foo, post: abc\"]Test[/quote]",
+            parse("[quote=\"Foo, post: abc\"]Test[/quote]")
+        );
+        assert_eq!("[quote=]Test[/quote]", parse("[quote=]Test[/quote]"));
+    }
+
+    #[test]
+    fn quote_depth() {
+        use super::parse;
+
+        assert_eq!(
+            "<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">\
+            <blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">\
+            <blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">\
+            <blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-attribution\"><a class=\"username\" href=\"/members/1/\">Foo</a> said:</div>\
+            <div class=\"tagQuote-content\">…</div></blockquote>\
+            </div></blockquote></div></blockquote></div></blockquote>",
+            parse("[quote][quote][quote][quote=\"Foo, member: 1\"]Deep[/quote][/quote][/quote][/quote]")
+        );
+        assert_eq!(
+            "<blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">\
+            <blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">\
+            <blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-content\">\
+            <blockquote class=\"bbCode tagQuote\"><div class=\"tagQuote-attribution\"><span class=\"username\">Foo</span> said: \
+            <a class=\"tagQuote-source\" href=\"/posts/9\" title=\"Go to post\">↑</a></div><div class=\"tagQuote-content\">…\
+            </div></blockquote></div></blockquote></div></blockquote></div></blockquote>",
+            parse("[quote][quote][quote][quote=\"Foo, post: 9\"][quote]Deeper[/quote][/quote][/quote][/quote][/quote]")
+        );
+    }
+
+    #[test]
+    fn spoiler() {
+        use super::parse;
+
+        assert_eq!(
+            "<details class=\"bbCode tagSpoiler\"><summary>Spoiler</summary><div class=\"tagSpoiler-content\">Foo</div></details>",
+            parse("[spoiler]Foo[/spoiler]")
+        );
+        assert_eq!(
+            "<details class=\"bbCode tagSpoiler\"><summary>Spoiler: Foo &amp; Bar</summary><div class=\"tagSpoiler-content\">Baz</div></details>",
+            parse("[spoiler=\"Foo & Bar\"]Baz[/spoiler]")
+        );
+        assert_eq!(
+            "Foo <details class=\"bbCode tagIspoiler\"><summary>Spoiler</summary><span class=\"tagIspoiler-content\"><b>Bar</b></span></details> Baz",
+            parse("Foo [ispoiler][b]Bar[/b][/ispoiler] Baz")
+        );
+        assert_eq!(
+            "<b>[spoiler]Foo[/spoiler]</b>",
+            parse("[b][spoiler]Foo[/spoiler][/b]")
+        );
+        assert_eq!("[spoiler=]Foo[/spoiler]", parse("[spoiler=]Foo[/spoiler]"));
+        assert_eq!(
+            "[ispoiler=Foo]Bar[/ispoiler]",
+            parse("[ispoiler=Foo]Bar[/ispoiler]")
+        );
+    }
+
     #[test]
     fn sanitize() {
         use super::parse;
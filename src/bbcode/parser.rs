@@ -1,5 +1,6 @@
-use super::{Element, ElementDisplay, Token};
+use super::{Constructor, CustomTags, Element, ElementDisplay, Tag, Token};
 use rctree::Node;
+use std::sync::Arc;
 
 /// Struct for parsing BbCode Tokens into an Element tree.
 pub struct Parser<'str> {
@@ -7,6 +8,10 @@ pub struct Parser<'str> {
     root: Node<Element<'str>>,
     /// Current traversal node.
     node: Node<Element<'str>>,
+    /// Custom tags, which decide how their elements display.
+    custom_tags: Arc<CustomTags>,
+    /// If false, block spoilers render inline and may not contain block elements.
+    allow_block_spoilers: bool,
 }
 
 impl<'str> Default for Parser<'str> {
@@ -22,7 +27,21 @@ impl<'str> Parser<'str> {
         let root = Node::new(Element::new_root());
         let node = root.clone();
 
-        Self { root, node }
+        Self {
+            root,
+            node,
+            custom_tags: super::get_custom_tags(),
+            allow_block_spoilers: true,
+        }
+    }
+
+    /// Creates a Parser which displays elements the way a Constructor will render them.
+    pub fn new_for_constructor(constructor: &Constructor) -> Self {
+        Self {
+            custom_tags: constructor.custom_tags.clone(),
+            allow_block_spoilers: constructor.allow_block_spoilers,
+            ..Self::new()
+        }
     }
 
     pub fn parse(&mut self, tokens: &'str [Token]) -> Node<Element<'str>> {
@@ -32,7 +51,16 @@ impl<'str> Parser<'str> {
                     log::warn!("BbCode Lexer received Null instruction, which should not happen.");
                 }
                 Token::Linebreak(_) => self.add_linebreak(token),
-                Token::Tag(_, _, _) => self.open_tag(token, Element::new_from_token(token)),
+                Token::Mention(_, _) => self.add_mention(token),
+                Token::Tag(_, tag, _) => {
+                    let mut el = Element::new_from_token(token);
+                    if let Some(custom) = self.custom_tags.get(tag) {
+                        el.set_custom(custom.get_display());
+                    } else if !self.allow_block_spoilers && el.is_tag("spoiler") {
+                        el.set_display(ElementDisplay::Inline);
+                    }
+                    self.open_tag(token, el);
+                }
                 Token::TagClose(_, tag) => self.close_tag(token, tag),
                 Token::Text(text) => self.add_text(text),
                 Token::Url(url) => self.add_url(token, url),
@@ -67,6 +95,10 @@ impl<'str> Parser<'str> {
         }
     }
 
+    fn add_mention(&mut self, token: &'str Token) {
+        self.insert_element(Element::new_from_token(token));
+    }
+
     fn add_text(&mut self, text: &'str str) {
         if self.node.borrow().can_have_content() {
             self.insert_element(Element::new_from_text(text));
@@ -96,6 +128,15 @@ impl<'str> Parser<'str> {
                     self.insert_contents_as_node();
                 }
 
+                // List items do not keep the linebreak which precedes the next item.
+                if self.node.borrow().is_tag("*") {
+                    if let Some(mut last) = self.node.last_child() {
+                        if last.borrow().is_tag("br") {
+                            last.detach();
+                        }
+                    }
+                }
+
                 self.node = parent;
             }
             None => unreachable!(),
@@ -123,6 +164,10 @@ impl<'str> Parser<'str> {
                 if match el.get_display_type() {
                     // Inline tags may be closed by early termination of other tags.
                     ElementDisplay::Inline => true,
+                    // List items are closed by the end of their list.
+                    _ if el.is_tag("*") => {
+                        tag_matched || matches!(Tag::get_by_name(tag), Tag::List)
+                    }
                     // Other tags may never be closed by other tags.
                     _ => tag_matched,
                 } {
@@ -191,10 +236,57 @@ impl<'str> Parser<'str> {
         node
     }
 
+    /// Moves the current node up to the list a new item belongs in, closing any open item.
+    /// Returns false if there is no list for the item to go in.
+    fn close_to_list(&mut self) -> bool {
+        let mut closed_tags = 0;
+
+        let mut cursor = Some(self.node.clone());
+        while let Some(node) = cursor {
+            {
+                let el = node.borrow();
+
+                if el.is_tag("list") {
+                    break;
+                }
+
+                match el.get_display_type() {
+                    // Items and inline tags are implicitly closed by the next item.
+                    ElementDisplay::Inline => closed_tags += 1,
+                    _ if el.is_tag("*") => closed_tags += 1,
+                    // Items may not escape other block tags, including the root.
+                    _ => return false,
+                }
+            }
+
+            cursor = node.parent();
+        }
+
+        // Close the open item and anything left open inside of it.
+        while closed_tags > 0 {
+            self.close_open_tag(false);
+            closed_tags -= 1;
+        }
+
+        true
+    }
+
     /// Attempts to add element as child to current node and move current node to new element.
     fn open_tag(&mut self, token: &'str Token, el: Element<'str>) {
         // Can this tag parent any other element?
         if self.node.borrow().can_parent() {
+            // List items may only be opened inside of a list.
+            if el.is_tag("*") && !self.close_to_list() {
+                self.add_text(token.as_raw());
+                return;
+            }
+
+            // Block tags may not open inside of inline tags, so they are left as text.
+            if el.get_tag_name().is_some() && !self.node.borrow().can_parent_tag(&el) {
+                self.add_text(token.as_raw());
+                return;
+            }
+
             //  Can the new tag have any content at all?
             if el.can_have_content() {
                 // Insert the element and move our pointer.
@@ -371,4 +463,64 @@ mod tests {
         assert_eq!(ast.borrow().is_broken(), false);
         assert_eq!(parser.node, parser.root);
     }
+
+    #[test]
+    fn inline_spoiler_wont_parent_block() {
+        use super::{Parser, Token};
+
+        let tokens = [
+            Token::Tag("[ispoiler]", "ispoiler", None),
+            Token::Tag("[quote]", "quote", None),
+            Token::Text("Foo"),
+            Token::TagClose("[/quote]", "quote"),
+            Token::TagClose("[/ispoiler]", "ispoiler"),
+        ];
+
+        let mut parser = Parser::new();
+        let ast = parser.parse(&tokens);
+        let spoiler = ast.first_child().unwrap();
+
+        assert_eq!(spoiler.borrow().get_tag_name(), Some("ispoiler"));
+        assert_eq!(spoiler.children().count(), 3);
+        assert_eq!(
+            spoiler.first_child().unwrap().borrow().get_contents(),
+            Some("[quote]")
+        );
+        assert_eq!(parser.node, parser.root);
+    }
+
+    #[test]
+    fn disallowed_block_spoiler_wont_parent_block() {
+        use super::{Parser, Token};
+        use crate::bbcode::Constructor;
+
+        let tokens = [
+            Token::Tag("[spoiler]", "spoiler", None),
+            Token::Tag("[quote]", "quote", None),
+            Token::Text("Foo"),
+            Token::TagClose("[/quote]", "quote"),
+            Token::TagClose("[/spoiler]", "spoiler"),
+        ];
+
+        let con = Constructor {
+            allow_block_spoilers: false,
+            ..Default::default()
+        };
+        let ast = Parser::new_for_constructor(&con).parse(&tokens);
+        let spoiler = ast.first_child().unwrap();
+
+        assert_eq!(spoiler.borrow().get_tag_name(), Some("spoiler"));
+        assert_eq!(
+            spoiler.first_child().unwrap().borrow().get_contents(),
+            Some("[quote]")
+        );
+
+        // Block spoilers still parent blocks when they are allowed.
+        let ast = Parser::new().parse(&tokens);
+        let spoiler = ast.first_child().unwrap();
+        assert_eq!(
+            spoiler.first_child().unwrap().borrow().get_tag_name(),
+            Some("quote")
+        );
+    }
 }
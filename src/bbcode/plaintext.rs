@@ -0,0 +1,179 @@
+use super::tag::MediaTarget;
+use super::{Constructor, Element, Renderer, Tag};
+use rctree::Node;
+use std::cell::RefMut;
+
+/// Renders text without any formatting, for places which cannot display HTML.
+/// Spoilers are never revealed.
+pub struct PlainText;
+
+impl Renderer for PlainText {
+    fn text(&self, _: &Constructor, contents: &str) -> String {
+        contents.to_owned()
+    }
+
+    fn literal(&self, _: &Constructor, _: &Element, raw: &str) -> String {
+        raw.to_owned()
+    }
+
+    fn element_open(&self, _: &Constructor, el: RefMut<Element>) -> String {
+        match el.get_tag_name() {
+            Some(_) if el.is_broken() => el.to_open_str(),
+            Some(tag) => match Tag::get_by_name(tag) {
+                Tag::HorizontalRule | Tag::Linebreak => String::from("\n"),
+                _ => String::new(),
+            },
+            None => String::new(),
+        }
+    }
+
+    fn element_contents(
+        &self,
+        con: &Constructor,
+        node: &mut Node<Element>,
+        contents: String,
+    ) -> String {
+        let tag = match node.borrow().get_tag_name() {
+            Some(tag) => tag,
+            None => return contents,
+        };
+
+        if !con.is_renderable(&node.borrow()) {
+            node.borrow_mut().set_broken();
+            return contents;
+        }
+
+        let output = match Tag::get_by_name(tag) {
+            Tag::Attachment => {
+                let el = node.borrow();
+                Tag::get_attachment(&el, &contents, &con.attachments)
+                    .and_then(|(id, _)| con.attachments.get_name(id))
+                    .map(str::to_owned)
+            }
+            Tag::Image => Tag::get_img_url(&contents).map(String::from),
+            Tag::Link => {
+                let text = Constructor::get_text(node);
+                let el = node.borrow();
+                Tag::get_url_target(&el, &text).map(|url| {
+                    // Links which name themselves are only written once.
+                    if !el.has_argument() || text.trim().is_empty() || text.trim() == url.as_str() {
+                        url.to_string()
+                    } else {
+                        format!("{} ({})", contents, url.as_str())
+                    }
+                })
+            }
+            Tag::ListItem => Some(get_list_item(node, &contents, |style, n| {
+                style.text_marker(n)
+            })),
+            Tag::List => Some(format!("\n{}\n", contents.trim_matches('\n'))),
+            Tag::Media => Tag::get_media_target(&node.borrow(), &contents, &con.media_providers)
+                .map(|target| match target {
+                    MediaTarget::Embed(provider, id) => provider.get_page_url(&id),
+                    MediaTarget::Link(url) => url.to_string(),
+                }),
+            Tag::Mention => Some(get_mention(&node.borrow(), con, |name| name.to_owned())),
+            Tag::Quote => Some(get_quote(node, &contents, |name| name.to_owned())),
+            Tag::InlineSpoiler | Tag::Spoiler => {
+                Tag::get_spoiler_title(&node.borrow()).map(|title| format!("({})", title))
+            }
+            _ => Some(contents.to_owned()),
+        };
+
+        match output {
+            Some(output) => output,
+            None => {
+                node.borrow_mut().set_broken();
+                contents
+            }
+        }
+    }
+
+    fn element_close(&self, _: &Constructor, el: RefMut<Element>) -> String {
+        if el.is_broken() {
+            el.to_close_str()
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Writes @name with the current name of the user, or as it was written.
+pub(super) fn get_mention<F>(el: &Element, con: &Constructor, name_fn: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    match el.get_contents().and_then(|name| con.mentions.get(name)) {
+        Some((_, name)) => format!("@{}", name_fn(name)),
+        None => name_fn(el.get_raw()),
+    }
+}
+
+/// Writes a list item with its marker, indenting the lines which follow.
+/// The marker is given the list style and the position of the item, counting from 1.
+pub(super) fn get_list_item<F>(node: &Node<Element>, contents: &str, marker: F) -> String
+where
+    F: Fn(&super::tag::ListStyle, usize) -> String,
+{
+    let n = node
+        .preceding_siblings()
+        .filter(|sibling| sibling.borrow().is_tag("*"))
+        .count();
+    let style = node
+        .parent()
+        .and_then(|list| Tag::get_list_style(&list.borrow()))
+        .unwrap_or(super::tag::ListStyle::Bullet);
+    let marker = marker(&style, n);
+    let indent = " ".repeat(marker.chars().count() + 1);
+
+    // Blank lines would end the item.
+    let mut output = String::new();
+    for (i, line) in contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+    {
+        if i == 0 {
+            output.push_str(&marker);
+            output.push(' ');
+        } else {
+            output.push('\n');
+            output.push_str(&indent);
+        }
+        output.push_str(line);
+    }
+
+    if output.is_empty() {
+        output.push_str(&marker);
+    }
+
+    output.push('\n');
+    output
+}
+
+/// Writes a quote with each line prefixed by `> `, under its attribution.
+pub(super) fn get_quote<F>(node: &Node<Element>, contents: &str, name: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let mut output = String::from("\n");
+
+    if let Some(attribution) = Tag::get_quote_attribution(&node.borrow()) {
+        if let Some(author) = attribution.name {
+            output.push_str(&format!("> {} said:\n", name(author)));
+        }
+    }
+
+    for line in contents.trim_matches('\n').lines() {
+        if line.is_empty() {
+            output.push_str(">\n");
+        } else {
+            output.push_str("> ");
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    output.push('\n');
+    output
+}
@@ -1,4 +1,22 @@
+use once_cell::sync::OnceCell;
 use std::collections::HashMap;
+use std::sync::Arc;
+
+static SMILIES: OnceCell<Arc<Smilies>> = OnceCell::new();
+
+/// Returns the smilies loaded at startup, or none if they were never loaded.
+pub fn get_smilies() -> Arc<Smilies> {
+    SMILIES.get().cloned().unwrap_or_default()
+}
+
+/// Sets the smilies for all future Constructors.
+pub fn init_smilies(smilies: Smilies) {
+    log::info!("Loaded {} smilies.", smilies.count());
+
+    if SMILIES.set(Arc::new(smilies)).is_err() {
+        log::warn!("Smilies were already loaded.");
+    }
+}
 
 #[derive(Default)]
 pub struct Smilies(Vec<(String, String)>);
@@ -18,7 +36,8 @@ impl Smilies {
     }
 
     fn sort_tuples(mut smilies: Vec<(String, String)>) -> Vec<(String, String)> {
-        smilies.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+        // Longer codes are replaced first, so `cookie` is not eaten by `ookie`.
+        smilies.sort_by_key(|smilie| std::cmp::Reverse(smilie.0.len()));
         //smilies.sort_by(|a, b| a.0.chars().cmp(b.0.chars()));
         smilies
     }
@@ -0,0 +1,384 @@
+use super::super::Constructor;
+use super::Element;
+use crate::url::UrlToken;
+use std::cell::RefMut;
+
+/// Marker style supplied in a list argument.
+/// Example: \[list=1\]
+#[derive(Debug, PartialEq)]
+pub enum ListStyle {
+    Bullet,
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl ListStyle {
+    /// Returns the HTML element which renders this list.
+    pub fn html_tag(&self) -> &'static str {
+        match self {
+            Self::Bullet => "ul",
+            _ => "ol",
+        }
+    }
+
+    /// Returns the value of the `type` attribute, if the default is not used.
+    pub fn html_type(&self) -> Option<&'static str> {
+        match self {
+            Self::LowerAlpha => Some("a"),
+            Self::UpperAlpha => Some("A"),
+            Self::LowerRoman => Some("i"),
+            Self::UpperRoman => Some("I"),
+            _ => None,
+        }
+    }
+
+    /// Returns the marker of an item in text, counting from 1.
+    pub fn text_marker(&self, n: usize) -> String {
+        match self {
+            Self::Bullet => String::from("-"),
+            Self::Decimal => format!("{}.", n),
+            Self::LowerAlpha => format!("{}.", alpha_numeral(n).to_lowercase()),
+            Self::UpperAlpha => format!("{}.", alpha_numeral(n)),
+            Self::LowerRoman => format!("{}.", roman_numeral(n).to_lowercase()),
+            Self::UpperRoman => format!("{}.", roman_numeral(n)),
+        }
+    }
+}
+
+/// Attribution data supplied in a quote argument.
+/// Example: \[quote="name, post: 123, member: 45"\]
+#[derive(Debug, Default, PartialEq)]
+pub struct QuoteAttribution<'a> {
+    pub name: Option<&'a str>,
+    pub post: Option<i32>,
+    pub member: Option<i32>,
+}
+
+impl super::Tag {
+    pub fn open_list_tag(el: RefMut<Element>) -> String {
+        match Self::get_list_style(&el) {
+            Some(style) => match style.html_type() {
+                Some(kind) => format!(
+                    "<{} class=\"bbCode tagList\" type=\"{}\">",
+                    style.html_tag(),
+                    kind
+                ),
+                None => format!("<{} class=\"bbCode tagList\">", style.html_tag()),
+            },
+            None => Self::open_broken_tag(el),
+        }
+    }
+
+    /// Returns true if the element is a list which will render.
+    pub fn is_list_tag(el: &Element) -> bool {
+        match el.get_tag_name() {
+            Some(tag) => match Self::get_by_name(tag) {
+                Self::List => list_arg(el.get_argument()).is_some(),
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Returns the style of a list, or None if it will not render.
+    pub fn get_list_style(el: &Element) -> Option<ListStyle> {
+        list_arg(el.get_argument())
+    }
+
+    pub fn close_list_tag(el: RefMut<Element>) -> String {
+        match Self::get_list_style(&el) {
+            Some(style) => Self::close_simple_tag(style.html_tag()),
+            None => el.to_close_str(),
+        }
+    }
+
+    /// Opens a spoiler as a <details> element, which needs no JavaScript to toggle.
+    /// Block spoilers may be rendered inline where the constructor disallows them.
+    pub fn open_spoiler_tag(el: RefMut<Element>, inline: bool) -> String {
+        let title = match Self::get_spoiler_title(&el) {
+            Some(title) => Constructor::sanitize(&title),
+            None => return Self::open_broken_tag(el),
+        };
+
+        if inline {
+            format!(
+                "<details class=\"bbCode tagIspoiler\"><summary>{}</summary><span class=\"tagIspoiler-content\">",
+                title
+            )
+        } else {
+            format!(
+                "<details class=\"bbCode tagSpoiler\"><summary>{}</summary><div class=\"tagSpoiler-content\">",
+                title
+            )
+        }
+    }
+
+    /// Opens an inline spoiler, which takes no title.
+    pub fn open_ispoiler_tag(el: RefMut<Element>) -> String {
+        if el.has_argument() {
+            Self::open_broken_tag(el)
+        } else {
+            Self::open_spoiler_tag(el, true)
+        }
+    }
+
+    /// Returns the summary of a spoiler, or None if it will not render.
+    /// Inline spoilers take no title.
+    pub fn get_spoiler_title(el: &Element) -> Option<String> {
+        match el.get_argument() {
+            Some(_) if el.is_tag("ispoiler") => None,
+            Some(arg) => spoiler_arg(arg).map(|title| format!("Spoiler: {}", title)),
+            None => Some(String::from("Spoiler")),
+        }
+    }
+
+    pub fn close_spoiler_tag(inline: bool) -> String {
+        if inline {
+            String::from("</span></details>")
+        } else {
+            String::from("</div></details>")
+        }
+    }
+
+    pub fn open_quote_tag(el: RefMut<Element>) -> String {
+        let attribution = match Self::get_quote_attribution(&el) {
+            Some(attribution) => attribution,
+            None => return Self::open_broken_tag(el),
+        };
+
+        let mut output = String::from("<blockquote class=\"bbCode tagQuote\">");
+
+        if let Some(name) = attribution.name {
+            output.push_str("<div class=\"tagQuote-attribution\">");
+            output.push_str(
+                &UrlToken {
+                    id: attribution.member,
+                    name: Constructor::sanitize(name),
+                    base_url: crate::user::RESOURCE_URL,
+                    class: "username",
+                }
+                .to_string(),
+            );
+            output.push_str(" said:");
+
+            if let Some(post) = attribution.post {
+                // /posts/{id} is the post permalink, which resolves the thread page through get_url_for_pos.
+                output.push_str(&format!(
+                    " <a class=\"tagQuote-source\" href=\"/posts/{}\" title=\"Go to post\">↑</a>",
+                    post
+                ));
+            }
+
+            output.push_str("</div>");
+        }
+
+        output.push_str("<div class=\"tagQuote-content\">");
+        output
+    }
+
+    /// Returns the attribution of a quote, or None if it will not render.
+    pub fn get_quote_attribution<'a>(el: &Element<'a>) -> Option<QuoteAttribution<'a>> {
+        match el.get_argument() {
+            Some(arg) => quote_arg(arg),
+            None => Some(QuoteAttribution::default()),
+        }
+    }
+
+    /// Returns true if the element is a quote which will render.
+    pub fn is_quote_tag(el: &Element) -> bool {
+        match el.get_tag_name() {
+            Some(tag) => match Self::get_by_name(tag) {
+                Self::Quote => Self::get_quote_attribution(el).is_some(),
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    pub fn close_quote_tag() -> String {
+        String::from("</div></blockquote>")
+    }
+}
+
+//
+// Lexer logic
+//
+
+/// Parses `=1` style list arguments. No argument is a bulleted list.
+/// Returns None if the argument is malformed.
+pub fn list_arg(input: Option<&str>) -> Option<ListStyle> {
+    let input = match input {
+        Some(input) => input.strip_prefix('=')?.trim(),
+        None => return Some(ListStyle::Bullet),
+    };
+
+    match input.trim_matches('"') {
+        "1" => Some(ListStyle::Decimal),
+        "a" => Some(ListStyle::LowerAlpha),
+        "A" => Some(ListStyle::UpperAlpha),
+        "i" => Some(ListStyle::LowerRoman),
+        "I" => Some(ListStyle::UpperRoman),
+        _ => None,
+    }
+}
+
+/// Parses `=Title` and `="Title"` spoiler arguments.
+/// Returns None if the argument is malformed.
+pub fn spoiler_arg(input: &str) -> Option<&str> {
+    let input = input.strip_prefix('=')?.trim();
+    let input = match input.strip_prefix('"') {
+        Some(quoted) => quoted.strip_suffix('"')?.trim(),
+        None => input,
+    };
+
+    if input.is_empty() {
+        None
+    } else {
+        Some(input)
+    }
+}
+
+/// Parses `=name` and `="name, post: 123, member: 45"` quote arguments.
+/// Returns None if the argument is malformed.
+pub fn quote_arg(input: &str) -> Option<QuoteAttribution<'_>> {
+    let input = input.strip_prefix('=')?.trim();
+    let input = match input.strip_prefix('"') {
+        Some(quoted) => quoted.strip_suffix('"')?,
+        None => input,
+    };
+
+    let mut parts = input.split(',');
+    let name = parts.next()?.trim();
+
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut attribution = QuoteAttribution {
+        name: Some(name),
+        ..Default::default()
+    };
+
+    for part in parts {
+        let (key, value) = part.split_once(':')?;
+        let value = value.trim().parse::<i32>().ok()?;
+
+        match key.trim().to_lowercase().as_str() {
+            "post" => attribution.post = Some(value),
+            "member" => attribution.member = Some(value),
+            // Unknown attribution (i.e. XenForo's `user:`) is ignored.
+            _ => {}
+        }
+    }
+
+    Some(attribution)
+}
+
+/// Numbers items a through z, then aa, ab, and so on.
+fn alpha_numeral(mut n: usize) -> String {
+    let mut output = Vec::new();
+
+    while n > 0 {
+        n -= 1;
+        output.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+
+    output.reverse();
+    String::from_utf8(output).unwrap_or_default()
+}
+
+fn roman_numeral(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut output = String::new();
+
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            output.push_str(numeral);
+            n -= value;
+        }
+    }
+
+    output
+}
+
+mod tests {
+    #[test]
+    fn list_arg() {
+        use super::{list_arg, ListStyle};
+
+        assert_eq!(list_arg(None), Some(ListStyle::Bullet));
+        assert_eq!(list_arg(Some("=1")), Some(ListStyle::Decimal));
+        assert_eq!(list_arg(Some("=\"a\"")), Some(ListStyle::LowerAlpha));
+        assert_eq!(list_arg(Some("=I")), Some(ListStyle::UpperRoman));
+        assert_eq!(list_arg(Some("=")), None);
+        assert_eq!(list_arg(Some("=2")), None);
+        assert_eq!(list_arg(Some(" type=1")), None);
+    }
+
+    #[test]
+    fn text_marker() {
+        use super::ListStyle;
+
+        assert_eq!(ListStyle::Bullet.text_marker(3), "-");
+        assert_eq!(ListStyle::Decimal.text_marker(3), "3.");
+        assert_eq!(ListStyle::LowerAlpha.text_marker(3), "c.");
+        assert_eq!(ListStyle::UpperAlpha.text_marker(28), "AB.");
+        assert_eq!(ListStyle::LowerRoman.text_marker(4), "iv.");
+        assert_eq!(ListStyle::UpperRoman.text_marker(1994), "MCMXCIV.");
+    }
+
+    #[test]
+    fn spoiler_arg() {
+        use super::spoiler_arg;
+
+        assert_eq!(spoiler_arg(""), None);
+        assert_eq!(spoiler_arg("="), None);
+        assert_eq!(spoiler_arg("=\" \""), None);
+        assert_eq!(spoiler_arg(" title=Foo"), None);
+        assert_eq!(spoiler_arg("=Foo"), Some("Foo"));
+        assert_eq!(spoiler_arg("=\"Foo Bar\""), Some("Foo Bar"));
+    }
+
+    #[test]
+    fn quote_arg() {
+        use super::{quote_arg, QuoteAttribution};
+
+        assert_eq!(quote_arg(""), None);
+        assert_eq!(quote_arg("="), None);
+        assert_eq!(quote_arg("=\"\""), None);
+        assert_eq!(quote_arg("=\"Foo, post: abc\""), None);
+        assert_eq!(
+            quote_arg("=Foo"),
+            Some(QuoteAttribution {
+                name: Some("Foo"),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            quote_arg("=\"Foo Bar, post: 123, member: 45\""),
+            Some(QuoteAttribution {
+                name: Some("Foo Bar"),
+                post: Some(123),
+                member: Some(45),
+            })
+        );
+    }
+}
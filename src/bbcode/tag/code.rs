@@ -0,0 +1,63 @@
+use super::super::Language;
+use super::Element;
+use rctree::Node;
+use std::cell::RefMut;
+
+impl super::Tag {
+    /// Opens a code block. Blocks in a known language are highlighted, anything else is plain.
+    pub fn open_code_tag(el: RefMut<Element>) -> String {
+        match Self::get_code_language(&el) {
+            Some(language) => format!(
+                "<pre class=\"bbCode tagCode\" data-language=\"{}\"><code class=\"language-{}\">",
+                language.get_label(),
+                language.get_name()
+            ),
+            None => Self::open_simple_tag("pre"),
+        }
+    }
+
+    /// Highlights the raw contents of a code block.
+    /// Sanitized contents are kept if the block is plain.
+    pub fn fill_code_tag(el: RefMut<Element>, contents: String, raw: &str) -> String {
+        match Self::get_code_language(&el) {
+            Some(language) => language.highlight(raw.trim_matches(['\r', '\n'])),
+            None => contents,
+        }
+    }
+
+    /// Returns the language of a code block, if it is one we highlight.
+    /// Example: \[code=rust\]
+    pub fn get_code_language(el: &Element) -> Option<Language> {
+        el.get_argument()
+            .and_then(|arg| arg.strip_prefix('='))
+            .and_then(|arg| Language::get_by_name(arg.trim().trim_matches('"')))
+    }
+
+    pub fn close_code_tag(el: RefMut<Element>) -> String {
+        match Self::get_code_language(&el) {
+            Some(_) => String::from("</code></pre>"),
+            None => Self::close_simple_tag("pre"),
+        }
+    }
+
+    /// Returns the raw input inside of a code block, which is not parsed.
+    pub fn get_raw_code(node: &Node<Element>) -> String {
+        let el = node.borrow();
+        match el.get_contents() {
+            Some(contents) => contents.to_owned(),
+            None => node
+                .children()
+                .map(|child| child.borrow().get_raw().to_owned())
+                .collect(),
+        }
+    }
+
+    /// Opens inline code, which takes no argument.
+    pub fn open_icode_tag(el: RefMut<Element>) -> String {
+        if el.has_argument() {
+            Self::open_broken_tag(el)
+        } else {
+            String::from("<code class=\"bbCode tagIcode\">")
+        }
+    }
+}
@@ -1,26 +1,89 @@
+use super::super::{Attachments, MediaProvider, MediaProviders};
 use super::Element;
 use std::cell::RefMut;
 use url::Url;
 
+/// What a [media] tag resolves to.
+pub enum MediaTarget {
+    /// An allowed provider and a valid media id.
+    Embed(MediaProvider, String),
+    /// Any other web address, which is only linked to.
+    Link(Url),
+}
+
 impl super::Tag {
+    pub fn open_attach_tag(_: RefMut<Element>) -> String {
+        String::new()
+    }
+
+    /// Renders [attach]id[/attach] as a thumbnail and [attach=full]id[/attach] at full size.
+    pub fn fill_attach_tag(
+        mut el: RefMut<Element>,
+        contents: String,
+        attachments: &Attachments,
+    ) -> String {
+        let html = match Self::get_attachment(&el, &contents, attachments) {
+            Some((id, true)) => attachments.get_full_html(id),
+            Some((id, false)) => attachments.get_thumbnail_html(id),
+            None => None,
+        };
+
+        match html {
+            Some(html) => {
+                el.clear_contents();
+                html.to_owned()
+            }
+            None => {
+                el.set_broken();
+                contents
+            }
+        }
+    }
+
+    /// Returns the attachment id and if it is shown at full size.
+    /// Only attachments of the content being built may be embedded, anything else is broken.
+    pub fn get_attachment(
+        el: &Element,
+        contents: &str,
+        attachments: &Attachments,
+    ) -> Option<(i32, bool)> {
+        let full = match el.get_argument() {
+            Some(arg) => match arg.strip_prefix('=').map(|arg| arg.trim_matches('"')) {
+                Some("full") => true,
+                Some("thumb") => false,
+                _ => return None,
+            },
+            None => false,
+        };
+
+        contents
+            .trim()
+            .parse::<i32>()
+            .ok()
+            .filter(|id| attachments.get_name(*id).is_some())
+            .map(|id| (id, full))
+    }
+
     pub fn open_img_tag(_: RefMut<Element>) -> String {
         String::new()
     }
 
     pub fn fill_img_tag(mut el: RefMut<Element>, contents: String) -> String {
-        // Our URL comes from inside the tag.
-        if let Ok(url) = Url::parse(&contents) {
-            match url.scheme() {
-                "http" | "https" => {
-                    el.clear_contents();
-                    return format!("<img src=\"{}\" />", url.as_str());
-                }
-                _ => {}
+        match Self::get_img_url(&contents) {
+            Some(url) => {
+                el.clear_contents();
+                format!("<img src=\"{}\" />", url.as_str())
+            }
+            None => {
+                el.set_broken();
+                contents
             }
         }
+    }
 
-        el.set_broken();
-        contents
+    /// Returns the image address, which comes from inside the tag.
+    pub fn get_img_url(contents: &str) -> Option<Url> {
+        web_url(contents)
     }
 
     pub fn open_url_tag(el: RefMut<Element>) -> String {
@@ -31,29 +94,78 @@ impl super::Tag {
         }
     }
 
-    pub fn fill_url_tag(mut el: RefMut<Element>, contents: String) -> String {
-        let mut url: Option<Url> = None;
+    pub fn open_media_tag(_: RefMut<Element>) -> String {
+        String::new()
+    }
 
-        if let Some(arg) = el.get_argument() {
-            url = match url_arg(arg).transpose() {
-                Ok(url) => url,
-                Err(_) => {
-                    el.set_broken();
-                    return contents;
-                }
+    /// Renders [media=provider]id[/media] and [media]url[/media] for allowed providers.
+    /// Anything else falls back to a link, so we never frame a host we have not vetted.
+    pub fn fill_media_tag(
+        mut el: RefMut<Element>,
+        contents: String,
+        providers: &MediaProviders,
+    ) -> String {
+        match Self::get_media_target(&el, &contents, providers) {
+            Some(MediaTarget::Embed(provider, id)) => {
+                el.clear_contents();
+                provider.to_html(&id)
+            }
+            Some(MediaTarget::Link(url)) => {
+                el.clear_contents();
+                format!(
+                    "<a class=\"bbCode tagUrl\" ref=\"nofollow\" href=\"{}\">{}</a>",
+                    url.as_str(),
+                    contents
+                )
+            }
+            None => {
+                el.set_broken();
+                contents
             }
-            // TODO: Check for unfurl="true/false"
         }
+    }
 
-        if url.is_none() {
-            if let Ok(curl) = Url::parse(&contents) {
-                url = Some(curl)
-            }
+    pub fn get_media_target(
+        el: &Element,
+        contents: &str,
+        providers: &MediaProviders,
+    ) -> Option<MediaTarget> {
+        let id = contents.trim();
+        let url = web_url(id);
+
+        let media = match el.get_argument() {
+            Some(arg) => match arg.strip_prefix('=') {
+                Some(name) => providers
+                    .get_by_name(name.trim_matches('"'))
+                    .and_then(|provider| match &url {
+                        Some(url) => provider.id_from_url(url).map(|id| (provider, id)),
+                        None if provider.is_valid_id(id) => Some((provider, id.to_owned())),
+                        None => None,
+                    }),
+                None => None,
+            },
+            None => url.as_ref().and_then(|url| providers.get_by_url(url)),
+        };
+
+        match media {
+            Some((provider, id)) => Some(MediaTarget::Embed(provider, id)),
+            None => url.map(MediaTarget::Link),
         }
+    }
 
-        match url {
+    pub fn fill_url_tag(
+        mut el: RefMut<Element>,
+        contents: String,
+        providers: &MediaProviders,
+    ) -> String {
+        match Self::get_url_target(&el, &contents) {
             Some(url) => format!(
-                "<a class=\"bbCode tagUrl\" ref=\"nofollow\" href=\"{}\">{}",
+                "{}<a class=\"bbCode tagUrl\" ref=\"nofollow\" href=\"{}\">{}",
+                // Unfurled links to allowed providers get a player above the link.
+                match providers.get_by_url(&url) {
+                    Some((provider, id)) if Self::is_unfurled_url(&el) => provider.to_html(&id),
+                    _ => String::new(),
+                },
                 url.as_str(),
                 contents
             ),
@@ -64,6 +176,33 @@ impl super::Tag {
             }
         }
     }
+
+    /// Returns the link address from the argument, or from inside the tag.
+    pub fn get_url_target(el: &Element, contents: &str) -> Option<Url> {
+        match el.get_argument().and_then(url_arg) {
+            Some(Ok(url)) => Some(url),
+            Some(Err(_)) => None,
+            None => web_url(contents),
+        }
+    }
+
+    pub fn is_unfurled_url(el: &Element) -> bool {
+        el.get_argument().is_some_and(unfurl_arg)
+    }
+}
+
+/// Parses an address, which must be on the web.
+fn web_url(input: &str) -> Option<Url> {
+    Url::parse(input)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Returns true for XenForo's `[url unfurl="true"]`.
+fn unfurl_arg(input: &str) -> bool {
+    input
+        .split_whitespace()
+        .any(|part| matches!(part, "unfurl=\"true\"" | "unfurl=true"))
 }
 
 fn url_arg(input: &str) -> Option<Result<Url, &str>> {
@@ -9,16 +9,20 @@ use std::cell::RefMut;
 
 impl super::Tag {
     pub fn open_color_tag(el: RefMut<Element>) -> String {
-        if let Some(arg) = el.get_argument() {
-            if let Ok((_, color)) = color_from(arg) {
-                return format!(
-                    "<span class=\"bbCode tagColor\" style=\"color: {}\">",
-                    color
-                );
-            }
+        match Self::get_color(&el) {
+            Some(color) => format!(
+                "<span class=\"bbCode tagColor\" style=\"color: {}\">",
+                color
+            ),
+            None => Self::open_broken_tag(el),
         }
+    }
 
-        Self::open_broken_tag(el)
+    /// Returns the color of the text, or None if it will not render.
+    pub fn get_color<'a>(el: &Element<'a>) -> Option<&'a str> {
+        el.get_argument()
+            .and_then(|arg| color_from(arg).ok())
+            .map(|(_, color)| color)
     }
 }
 
@@ -1,7 +1,13 @@
 extern crate nom;
 
+mod block;
+mod code;
 mod embed;
 mod font;
+mod user;
+
+pub use block::ListStyle;
+pub use embed::MediaTarget;
 
 use super::Element;
 use std::{borrow::BorrowMut, cell::RefMut};
@@ -16,33 +22,57 @@ pub enum Tag {
     // Inline Tags
     Bold,
     Color,
+    InlineSpoiler,
     Italics,
     Underline,
     Strikethrough,
 
     // Formatting Tags
     Code,
+    InlineCode,
     Pre,
 
+    // Block Tags
+    List,
+    ListItem,
+    Quote,
+    Spoiler,
+
     // Embed Tags
+    Attachment,
     Image,
     Link,
+    Media,
+
+    // User Tags
+    Mention,
+    User,
 }
 
 impl Tag {
     pub fn get_by_name(tag: &str) -> Tag {
         match &*tag.to_lowercase() {
+            "*" => Tag::ListItem,
+            "@" => Tag::Mention,
+            "attach" => Tag::Attachment,
             "b" => Tag::Bold,
             "br" => Tag::Linebreak,
             "color" => Tag::Color,
             "code" => Tag::Code,
             "hr" => Tag::HorizontalRule,
             "i" => Tag::Italics,
+            "icode" => Tag::InlineCode,
             "img" => Tag::Image,
+            "ispoiler" => Tag::InlineSpoiler,
+            "list" => Tag::List,
+            "media" => Tag::Media,
             "plain" => Tag::Plain,
+            "quote" => Tag::Quote,
             "s" => Tag::Strikethrough,
+            "spoiler" => Tag::Spoiler,
             "u" => Tag::Underline,
             "url" => Tag::Link,
+            "user" => Tag::User,
             _ => Tag::Invalid,
         }
     }
@@ -0,0 +1,54 @@
+use super::super::{Constructor, Mentions};
+use super::Element;
+use crate::url::UrlToken;
+use std::cell::RefMut;
+
+impl super::Tag {
+    pub fn open_mention_tag(_: RefMut<Element>) -> String {
+        String::new()
+    }
+
+    /// Renders @name as a link to the user, if the name belongs to someone.
+    /// Anything else is left as it was written.
+    pub fn fill_mention_tag(el: RefMut<Element>, mentions: &Mentions) -> String {
+        match el.get_contents().and_then(|name| mentions.get(name)) {
+            Some((id, name)) => {
+                Self::get_user_link(id, format!("@{}", Constructor::sanitize(name)))
+            }
+            None => Constructor::sanitize(el.get_raw()),
+        }
+    }
+
+    pub fn open_user_tag(_: RefMut<Element>) -> String {
+        String::new()
+    }
+
+    /// Renders [user=id]name[/user] as a link to the user.
+    pub fn fill_user_tag(mut el: RefMut<Element>, contents: String) -> String {
+        match Self::get_user_id(&el) {
+            Some(id) => Self::get_user_link(id, contents),
+            None => {
+                el.set_broken();
+                contents
+            }
+        }
+    }
+
+    /// Returns the id of a [user=id] tag.
+    pub fn get_user_id(el: &Element) -> Option<i32> {
+        el.get_argument()
+            .and_then(|arg| arg.strip_prefix('='))
+            .and_then(|arg| arg.trim().trim_matches('"').parse::<i32>().ok())
+            .filter(|id| *id > 0)
+    }
+
+    fn get_user_link(id: i32, name: String) -> String {
+        UrlToken {
+            id: Some(id),
+            name,
+            base_url: crate::user::RESOURCE_URL,
+            class: "username mention",
+        }
+        .to_string()
+    }
+}
@@ -3,6 +3,7 @@
 pub enum Token<'a> {
     Null,
     Linebreak(&'a str),
+    Mention(&'a str, &'a str),
     Tag(&'a str, &'a str, Option<&'a str>),
     TagClose(&'a str, &'a str),
     Text(&'a str),
@@ -14,6 +15,7 @@ impl<'a> Token<'a> {
         match self {
             Self::Null => "",
             Self::Linebreak(raw) => raw,
+            Self::Mention(raw, _) => raw,
             Self::Tag(raw, _, _) => raw,
             Self::TagClose(raw, _) => raw,
             Self::Text(text) => text,
@@ -25,6 +27,7 @@ impl<'a> Token<'a> {
         match self {
             Self::Null => true,
             Self::Linebreak(_) => false,
+            Self::Mention(_, name) => name.is_empty(),
             Self::Tag(_, tag, arg) => tag.is_empty() && arg.is_none(),
             Self::TagClose(_, tag) => tag.is_empty(),
             Self::Text(text) => text.is_empty(),
@@ -59,6 +62,17 @@ mod tests {
         assert_eq!(inst.as_raw(), "\n\r");
     }
 
+    #[test]
+    fn reverse_mention() {
+        use super::Token;
+
+        let inst = Token::Mention("@Foo", "Foo");
+        assert_eq!(inst.as_raw(), "@Foo");
+
+        let inst = Token::Mention("@\"Foo Bar\"", "Foo Bar");
+        assert_eq!(inst.as_raw(), "@\"Foo Bar\"");
+    }
+
     #[test]
     fn reverse_tag() {
         use super::Token;
@@ -2,10 +2,11 @@ use super::Token;
 use nom::branch::{alt, permutation};
 use nom::bytes::complete::*;
 use nom::character::complete::*;
-use nom::combinator::{consumed, map, peek, recognize, rest};
+use nom::combinator::{all_consuming, consumed, map, peek, recognize, rest};
+use nom::error::{Error, ErrorKind};
 use nom::multi::{many0, many1};
-use nom::sequence::{delimited, tuple};
-use nom::IResult;
+use nom::sequence::{delimited, preceded, tuple};
+use nom::{Err, IResult};
 use url::Url;
 
 /// Tokenizer accepts string primitive and returns a Nom parser result.
@@ -21,6 +22,7 @@ fn parse(input: &str) -> IResult<&str, Token> {
         parse_tag_close,
         parse_tag_open,
         parse_url,
+        parse_mention,
         parse_text,
         parse_text_literally,
     ))(input)
@@ -34,11 +36,30 @@ fn parse_linebreak(input: &str) -> IResult<&str, Token> {
 /// Anticipates a closing tag, returns Token::TagClose.
 fn parse_tag_close(input: &str) -> IResult<&str, Token> {
     map(
-        consumed(delimited(tag("[/"), alpha1, tag("]"))),
+        consumed(delimited(tag("[/"), alt((alpha1, tag("*"))), tag("]"))),
         |(raw, tag): (&str, &str)| (Token::TagClose(raw, tag)),
     )(input)
 }
 
+/// Anticipates a mention of a user by name, returns Token::Mention.
+/// Names with spaces are quoted, as in @"Foo Bar".
+fn parse_mention(input: &str) -> IResult<&str, Token<'_>> {
+    map(
+        consumed(preceded(
+            char('@'),
+            alt((
+                delimited(
+                    char('"'),
+                    take_while1(|c: char| !"\"\r\n[]".contains(c)),
+                    char('"'),
+                ),
+                take_while1(is_mention_char),
+            )),
+        )),
+        |(raw, name): (&str, &str)| Token::Mention(raw, name),
+    )(input)
+}
+
 /// Anticipates an opening tag, returns Token::Tag.
 fn parse_tag_open(input: &str) -> IResult<&str, Token> {
     let (input, (raw, between)) = consumed(delimited(tag("["), tag_and_argument, tag("]")))(input)?;
@@ -91,17 +112,37 @@ fn parse_text_and_take_one_bracket(input: &str) -> IResult<&str, &str> {
 fn parse_text_until_terminator(input: &str) -> IResult<&str, &str> {
     alt((
         recognize(take_until1("http")),
+        take_until_mention,
         recognize(many1(none_of("\r\n["))),
     ))(input)
 }
 
+/// Returns text until the next @, unless it follows a word like in an email address.
+fn take_until_mention(input: &str) -> IResult<&str, &str> {
+    let mut previous = None;
+
+    for (i, c) in input.char_indices() {
+        if c == '@' && i > 0 && !previous.is_some_and(is_mention_char) {
+            return Ok((&input[i..], &input[..i]));
+        }
+        previous = Some(c);
+    }
+
+    Err(Err::Error(Error::new(input, ErrorKind::TakeUntil)))
+}
+
+fn is_mention_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
 fn parse_url(input: &str) -> IResult<&str, Token> {
     peek(tag("http"))(input)?;
     let (input, url) = recognize(many1(none_of(" \r\n[>,")))(input)?;
 
     match Url::parse(url) {
-        Ok(_) => Ok((input, Token::Url(url))),
-        Err(_) => Ok((input, Token::Text(url))),
+        // Only web addresses are linked, so `httpx:` is not.
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok((input, Token::Url(url))),
+        _ => Ok((input, Token::Text(url))),
     }
 }
 
@@ -121,6 +162,8 @@ fn token_from_argument(input: &str) -> IResult<&str, (&str, (&str, Option<&str>)
         ),
         // Matches `url` only
         map(alpha1, |tag: &str| (tag, (tag, None))),
+        // Matches `*` list items only
+        map(all_consuming(tag("*")), |tag: &str| (tag, (tag, None))),
     ))(input)
 
     //Token::Tag(tag, )
@@ -193,6 +236,27 @@ mod tests {
         assert_eq!(tokens[6], Token::Text(" link!"));
     }
 
+    #[test]
+    fn mentions() {
+        use super::{tokenize, Token};
+
+        let input = "Hi @Foo, @\"Foo Bar\" and foo@example.com!";
+        let tokens = tokenize(input).unwrap().1;
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[0], Token::Text("Hi "));
+        assert_eq!(tokens[1], Token::Mention("@Foo", "Foo"));
+        assert_eq!(tokens[2], Token::Text(", "));
+        assert_eq!(tokens[3], Token::Mention("@\"Foo Bar\"", "Foo Bar"));
+        assert_eq!(tokens[4], Token::Text(" and foo@example.com!"));
+
+        let input = "@ @@Foo";
+        let tokens = tokenize(input).unwrap().1;
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0], Token::Text("@ "));
+        assert_eq!(tokens[1], Token::Text("@"));
+        assert_eq!(tokens[2], Token::Mention("@Foo", "Foo"));
+    }
+
     #[test]
     fn tag_basic() {
         use super::{tokenize, Token};
@@ -320,6 +384,24 @@ mod tests {
         assert_eq!(tokens[2], Token::TagClose("[/tag]", "tag"));
     }
 
+    #[test]
+    fn tag_list_item() {
+        use super::{tokenize, Token};
+
+        let input = "[*]Item[/*]";
+        let tokens = tokenize(input).unwrap().1;
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0], Token::Tag("[*]", "*", None));
+        assert_eq!(tokens[1], Token::Text("Item"));
+        assert_eq!(tokens[2], Token::TagClose("[/*]", "*"));
+
+        let input = "[**]";
+        let tokens = tokenize(input).unwrap().1;
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0], Token::Text("[**]"));
+    }
+
     #[test]
     fn text() {
         use super::{tokenize, Token};
@@ -21,6 +21,26 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Permission System failed to initialize.");
 
+    ruforo::bbcode::init_custom_tags(get_db_pool())
+        .await
+        .expect("Custom BbCode tags failed to load.");
+    ruforo::bbcode::init_media_providers();
+
+    ruforo::reaction::init_reaction_types(get_db_pool())
+        .await
+        .expect("Reaction types failed to load.");
+
+    // Posts use the same smilies as chat, so cached HTML is rendered again when they change.
+    let layer = Arc::new(ruforo::web::chat::implement::default::Layer {
+        db: get_db_pool().to_owned(),
+    });
+    ruforo::bbcode::init_smilies(ruforo::web::chat::implement::load_smilies(layer.as_ref()).await);
+
+    // Maintenance tasks run instead of the server.
+    if let Some(task) = std::env::args().nth(1) {
+        return run_task(&task).await;
+    }
+
     let secret_key = match std::env::var("SECRET_KEY") {
         Ok(key) => Key::from(key.as_bytes()),
         Err(err) => {
@@ -34,12 +54,10 @@ async fn main() -> std::io::Result<()> {
         }
     };
 
-    let layer = Arc::new(ruforo::web::chat::implement::default::Layer {
-        db: get_db_pool().to_owned(),
-    });
     let chat = ruforo::web::chat::server::ChatServer::new(layer.clone())
         .await
         .start();
+    ruforo::notification::init_push(chat.clone().recipient());
 
     HttpServer::new(move || {
         let layer_data: Data<Arc<dyn ruforo::web::chat::implement::ChatLayer>> =
@@ -80,11 +98,57 @@ async fn main() -> std::io::Result<()> {
     .await
 }
 
+/// Runs a maintenance task from the command line.
+/// Example: `ruforo rebuild-ugc-html --all`
+async fn run_task(task: &str) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+
+    match task {
+        // Renders cached HTML for posts. Only stale HTML is rendered unless --all is passed.
+        "rebuild-ugc-html" => {
+            let stale_only = !std::env::args().any(|arg| arg == "--all");
+            let count = ruforo::ugc::rebuild_rendered_ugc(
+                get_db_pool(),
+                &mut ruforo::bbcode::Constructor::new(),
+                stale_only,
+            )
+            .await
+            .map_err(Error::other)?;
+
+            log::info!("Rendered HTML for {} UGC revisions.", count);
+            Ok(())
+        }
+        // Deletes read markers older than READ_MARKER_DAYS. Intended to be run on a schedule.
+        "prune-read-markers" => {
+            let count = ruforo::read_marker::prune_read_markers(get_db_pool())
+                .await
+                .map_err(Error::other)?;
+
+            log::info!("Pruned {} read markers.", count);
+            Ok(())
+        }
+        // Deletes uploads which have received nothing for UPLOAD_SESSION_HOURS. Intended to be run on a schedule.
+        "prune-uploads" => {
+            let count = ruforo::upload::prune_upload_sessions(get_db_pool())
+                .await
+                .map_err(Error::other)?;
+
+            log::info!("Pruned {} upload sessions.", count);
+            Ok(())
+        }
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown task '{}'.", task),
+        )),
+    }
+}
+
 /// Initialize third party crates we rely on but don't have control over.
 pub fn init_lib_mods() {
     // This should be calls to crates without any transformative work applied.
     dotenv::dotenv().expect("DotEnv failed to initialize.");
     env_logger::Builder::from_env(Env::default().default_filter_or("debug")).init();
+    #[cfg(feature = "ffmpeg")]
     ffmpeg_next::init().expect("FFMPEG failed to initialize.");
 }
 
@@ -97,4 +161,6 @@ pub fn init_our_mods() {
     ruforo::global::init();
     ruforo::session::init();
     ruforo::filesystem::init();
+    ruforo::storage::init();
+    ruforo::media::init();
 }
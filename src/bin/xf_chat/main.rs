@@ -49,6 +49,10 @@ async fn main() -> std::io::Result<()> {
     //        panic!("{:?}", err);
     //    }
     //};
+    // Custom BbCode tags are stored in our database, which this binary does not connect to.
+    // Chat messages here do not support them and render them as text.
+    ruforo::bbcode::init_media_providers();
+
     let layer = Arc::new(xf::XfLayer { db: mysql.clone() });
     let chat = ruforo::web::chat::server::ChatServer::new(layer.clone())
         .await
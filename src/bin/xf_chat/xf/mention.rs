@@ -0,0 +1,33 @@
+use super::orm::user;
+use ruforo::bbcode::Mentions;
+use sea_orm::entity::prelude::*;
+use sea_orm::DatabaseConnection;
+
+/// Resolves names mentioned in chat to XF users.
+/// XF compares usernames without case, like we do.
+pub async fn get_mentioned_users(db: &DatabaseConnection, names: Vec<String>) -> Mentions {
+    let mut mentions = Mentions::default();
+
+    if names.is_empty() {
+        return mentions;
+    }
+
+    match user::Entity::find()
+        .filter(user::Column::Username.is_in(names))
+        .all(db)
+        .await
+    {
+        Ok(users) => {
+            for user in users {
+                mentions.insert(
+                    &user.username,
+                    user.user_id as i32,
+                    user.username.to_owned(),
+                );
+            }
+        }
+        Err(err) => log::warn!("XF mention lookup failed: {:?}", err),
+    }
+
+    mentions
+}
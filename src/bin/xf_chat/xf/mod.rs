@@ -1,3 +1,4 @@
+pub mod mention;
 pub mod message;
 pub mod orm;
 pub mod permission;
@@ -36,6 +37,10 @@ impl implement::ChatLayer for XfLayer {
         message::edit_message(&self.db, id, author, message).await
     }
 
+    async fn get_mentioned_users(&self, names: Vec<String>) -> ruforo::bbcode::Mentions {
+        mention::get_mentioned_users(&self.db, names).await
+    }
+
     async fn get_message(&self, id: u32) -> Option<implement::Message> {
         message::get_message(&self.db, id).await
     }
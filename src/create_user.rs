@@ -37,7 +37,7 @@ async fn insert_new_user(
         password_cipher: Set(users::Cipher::Argon2id),
         ..Default::default() // all other attributes are `Unset`
     };
-    let res = users::Entity::insert(user).exec(db).await?;
+    let res = users::Entity::insert(user).exec(&txn).await?;
 
     let user_name_ins = user_names::ActiveModel {
         user_id: Set(res.last_insert_id),
@@ -55,8 +55,8 @@ async fn insert_new_user(
 
     // exec secondary inserts
     let (un_result, unh_result) = join!(
-        user_names::Entity::insert(user_name_ins).exec(db),
-        user_name_history::Entity::insert(user_name_history_ins).exec(db)
+        user_names::Entity::insert(user_name_ins).exec(&txn),
+        user_name_history::Entity::insert(user_name_history_ins).exec(&txn)
     );
 
     if un_result.is_err() {
@@ -65,8 +65,17 @@ async fn insert_new_user(
     if unh_result.is_err() {
         return Err(unh_result.unwrap_err());
     }
+
+    // Content which mentioned this name before it was taken renders it as a mention now.
+    crate::ugc::forget_rendered_mentions(&txn, name).await?;
     txn.commit().await?;
 
+    // Content rendered while the name was being taken may have cached it as unmentioned.
+    // The user exists now, so failing this is not a failed registration.
+    if let Err(err) = crate::ugc::forget_rendered_mentions(db, name).await {
+        log::error!("{}", err);
+    }
+
     Ok(res)
 }
 
@@ -1,9 +1,21 @@
+use crate::media::{MediaProbe, MediaStream, StreamKind};
 use ffmpeg_next::codec::context::Context;
-use ffmpeg_next::codec::id::Id::{AV1, OPUS, VORBIS, VP8, VP9};
+use ffmpeg_next::codec::id::Id::MJPEG;
 use ffmpeg_next::format::context::Input;
+use ffmpeg_next::format::Pixel;
 use ffmpeg_next::media::Type;
+use ffmpeg_next::software::scaling;
+use ffmpeg_next::util::frame::video::Video;
+use ffmpeg_next::{encoder, rescale, Packet};
 use std::path::Path;
 
+/// An encoded JPEG thumbnail.
+pub struct Thumbnail {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
 pub fn get_dimensions_from_input(ctx: &Input) -> Option<(u32, u32)> {
     let stream = ctx.streams().best(Type::Video)?;
     let context_decoder = Context::from_parameters(stream.parameters()).ok()?;
@@ -11,56 +23,38 @@ pub fn get_dimensions_from_input(ctx: &Input) -> Option<(u32, u32)> {
     Some((decoder.width(), decoder.height()))
 }
 
-pub fn get_extension_from_input(ctx: &Input) -> Option<String> {
-    let format = ctx.format();
-    log::error!("Name: {:#?}", format.name());
-    log::error!("Description: {:#?}", format.description());
-    log::error!("Extensions: {:#?}", format.extensions());
-    log::error!("MIME Types: {:#?}", format.mime_types());
+/// Describes the container and streams of an input so it can be validated.
+pub fn probe_input(ctx: &Input) -> MediaProbe {
+    let duration = ctx.duration();
 
-    for (k, v) in ctx.metadata().iter() {
-        log::error!("{}: {}", k, v);
-    }
-    for stream in ctx.streams() {
-        let codec = stream.parameters();
-        log::error!("\tmedium: {:?}", codec.medium());
-        log::error!("\tid: {:?}", codec.id());
-    }
+    MediaProbe {
+        format: ctx.format().name().to_owned(),
+        streams: ctx
+            .streams()
+            .map(|stream| {
+                let parameters = stream.parameters();
+                let kind = match parameters.medium() {
+                    Type::Video => StreamKind::Video,
+                    Type::Audio => StreamKind::Audio,
+                    _ => StreamKind::Other,
+                };
+                let dimensions = match kind {
+                    StreamKind::Video => Context::from_parameters(stream.parameters())
+                        .and_then(|context| context.decoder().video())
+                        .map(|decoder| (decoder.width(), decoder.height()))
+                        .ok(),
+                    _ => None,
+                };
 
-    match format.name() {
-        "matroska,webm" => {
-            log::info!("found mkv/webm");
-            let stream_count = ctx.streams().count();
-            if stream_count != 2 {
-                log::info!("stream_count: {:?}", stream_count);
-                return Some("mkv".to_owned());
-            }
-            let mut streams = ctx.streams();
-            let video_id = streams.next().unwrap().parameters().id(); // we already counted the streams, unwrapping should be fine
-            match video_id {
-                VP8 | VP9 | AV1 => {
-                    let audio_id = streams.next().unwrap().parameters().id();
-                    match audio_id {
-                        OPUS | VORBIS => {
-                            log::info!("validated webm");
-                            Some("webm".to_owned())
-                        }
-                        _ => {
-                            log::info!("audio_id: {:?}", audio_id);
-                            Some("mkv".to_owned())
-                        }
-                    }
-                }
-                _ => {
-                    log::info!("video_id: {:?}", video_id);
-                    Some("mkv".to_owned())
+                MediaStream {
+                    kind,
+                    codec: parameters.id().name().to_owned(),
+                    dimensions,
                 }
-            }
-        }
-        _ => {
-            log::error!("unhandled format");
-            None
-        }
+            })
+            .collect(),
+        // Duration is in AV_TIME_BASE units and is not set for still images.
+        duration: (duration > 0).then(|| duration as f64 * f64::from(rescale::TIME_BASE)),
     }
 }
 
@@ -79,3 +73,72 @@ pub fn open_with_ffmpeg<P: AsRef<Path>>(path: &P) -> Option<Input> {
         },
     }
 }
+
+/// Decodes a single frame to make thumbnails from.
+/// Images have only one frame. Videos use a frame from a little way in, as the first is often black.
+pub fn get_poster_frame(ctx: &mut Input) -> Option<Video> {
+    let stream = ctx.streams().best(Type::Video)?;
+    let stream_index = stream.index();
+    let context_decoder = Context::from_parameters(stream.parameters()).ok()?;
+    let mut decoder = context_decoder.decoder().video().ok()?;
+
+    // Duration is in AV_TIME_BASE units and is not set for still images.
+    let duration = ctx.duration();
+    if duration > 0 {
+        let target = duration / 10;
+        if let Err(e) = ctx.seek(target, ..target) {
+            log::debug!("get_poster_frame: failed to seek: {}", e);
+        }
+    }
+
+    let mut frame = Video::empty();
+    for (stream, packet) in ctx.packets() {
+        if stream.index() != stream_index || decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        if decoder.receive_frame(&mut frame).is_ok() {
+            return Some(frame);
+        }
+    }
+
+    decoder.send_eof().ok()?;
+    decoder.receive_frame(&mut frame).ok()?;
+    Some(frame)
+}
+
+/// Scales a frame to the given dimensions and encodes it as a JPEG.
+pub fn encode_thumbnail(frame: &Video, width: u32, height: u32) -> Option<Thumbnail> {
+    let mut scaler = scaling::Context::get(
+        frame.format(),
+        frame.width(),
+        frame.height(),
+        Pixel::YUVJ420P,
+        width,
+        height,
+        scaling::Flags::BICUBIC,
+    )
+    .ok()?;
+    let mut scaled = Video::empty();
+    scaler.run(frame, &mut scaled).ok()?;
+    scaled.set_pts(Some(0));
+
+    let codec = encoder::find(MJPEG)?;
+    let mut context_encoder = Context::new_with_codec(codec).encoder().video().ok()?;
+    context_encoder.set_width(width);
+    context_encoder.set_height(height);
+    context_encoder.set_format(Pixel::YUVJ420P);
+    context_encoder.set_time_base((1, 1));
+    let mut encoder = context_encoder.open().ok()?;
+
+    encoder.send_frame(&scaled).ok()?;
+    encoder.send_eof().ok()?;
+
+    let mut packet = Packet::empty();
+    encoder.receive_packet(&mut packet).ok()?;
+
+    Some(Thumbnail {
+        data: packet.data()?.to_vec(),
+        width,
+        height,
+    })
+}
@@ -1,7 +1,10 @@
 use crate::attachment::{get_attachment_by_hash, update_attachment_last_seen};
 use crate::db::get_db_pool;
+use crate::media::MediaError;
+use crate::middleware::ClientCtx;
+use crate::orm::attachment_thumbnails::ThumbnailSize;
 use crate::orm::attachments;
-use crate::s3::S3Bucket;
+use crate::storage::get_storage;
 use actix_multipart::{Field, Multipart};
 use actix_web::{error, post, web, Error, Responder};
 use chrono::Utc;
@@ -13,14 +16,13 @@ use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 static MIME_LOOKUP: OnceCell<HashMap<&'static str, &'static str>> = OnceCell::new();
 static EXT_LOOKUP: OnceCell<HashMap<&'static str, &'static str>> = OnceCell::new();
 static DIR_TMP: OnceCell<String> = OnceCell::new();
-static S3BUCKET: OnceCell<S3Bucket> = OnceCell::new();
 
 #[inline(always)]
 fn get_mime_lookup() -> &'static HashMap<&'static str, &'static str> {
@@ -31,13 +33,9 @@ fn get_ext_lookup() -> &'static HashMap<&'static str, &'static str> {
     unsafe { EXT_LOOKUP.get_unchecked() }
 }
 #[inline(always)]
-fn get_dir_tmp() -> &'static str {
+pub fn get_dir_tmp() -> &'static str {
     unsafe { DIR_TMP.get_unchecked() }
 }
-#[inline(always)]
-pub fn get_s3() -> &'static S3Bucket {
-    unsafe { S3BUCKET.get_unchecked() }
-}
 
 /// MUST be called ONCE before using functions in this module
 pub fn init() {
@@ -59,21 +57,6 @@ pub fn init() {
         )
         .unwrap();
 
-    if S3BUCKET
-        .set(S3Bucket::new(
-            rusoto_core::Region::Custom {
-                name: std::env::var("AWS_REGION_NAME").expect(".env missing AWS_REGION_NAME"),
-                endpoint: std::env::var("AWS_API_ENDPOINT")
-                    .expect(".env missing AWS_API_ENDPOINT."),
-            },
-            std::env::var("AWS_BUCKET_NAME").expect(".env missing AWS_BUCKET_NAME."),
-            std::env::var("AWS_PUBLIC_URL").expect(".env missing AWS_PUBLIC_URL."),
-        ))
-        .is_err()
-    {
-        panic!("S3BUCKET");
-    }
-
     let map: HashMap<&'static str, &'static str> = HashMap::from([
         ("aac", "aac"),
         ("apng", "apng"),
@@ -157,13 +140,32 @@ pub struct FileHashFormData {
 }
 
 pub struct UploadPayload {
-    data: Vec<u8>,
     filename: String,
+    filesize: u64,
     hash: blake3::Hash,
     tmp_path: PathBuf,
     mime: Mime,
 }
 
+impl UploadPayload {
+    /// Describes a file which has been saved to DIR_TMP in full.
+    pub fn new(
+        filename: String,
+        filesize: u64,
+        hash: blake3::Hash,
+        tmp_path: PathBuf,
+        mime: Mime,
+    ) -> Self {
+        Self {
+            filename,
+            filesize,
+            hash,
+            tmp_path,
+            mime,
+        }
+    }
+}
+
 #[derive(Debug, FromQueryResult, Serialize)]
 pub struct UploadResponse {
     pub id: i32,
@@ -196,17 +198,19 @@ pub async fn post_file_hash(form: web::Json<FileHashFormData>) -> Result<impl Re
 }
 
 #[post("/fs/upload-file")]
-pub async fn put_file(mut mutipart: Multipart) -> Result<impl Responder, Error> {
+pub async fn put_file(client: ClientCtx, mut mutipart: Multipart) -> Result<impl Responder, Error> {
     // see: https://users.rust-lang.org/t/file-upload-in-actix-web/64871/3
     let mut responses: Vec<UploadResponse> = Vec::new();
 
     // Iterate over multipart stream
     while let Ok(Some(mut field)) = mutipart.try_next().await {
-        match insert_field_as_attachment(&mut field).await {
+        match insert_field_as_attachment(&mut field, client.can_upload_original_media()).await {
             Ok(response) => match response {
                 Some(response) => responses.push(response),
                 None => log::debug!("Threw out field: (empty)"),
             },
+            // Rejected media is reported to the client.
+            Err(err) if err.as_error::<MediaError>().is_some() => return Err(err),
             Err(err) => log::debug!("Threw out field: {}", err),
         }
     }
@@ -338,12 +342,23 @@ pub fn get_file_url_by_filename(key: &str, filename: &str) -> String {
     format!("/content/{}/{}", &key[0..=63], filename)
 }
 
+#[inline(always)]
+pub fn get_thumbnail_url_by_filename(key: &str, size: ThumbnailSize, filename: &str) -> String {
+    format!(
+        "/thumbnails/{}/{}/{}",
+        size.to_value(),
+        &key[0..=63],
+        filename
+    )
+}
+
 // Direct way of converting an actix_multipart field into an upload response.
 pub async fn insert_field_as_attachment(
     field: &mut Field,
+    keep_metadata: bool,
 ) -> Result<Option<UploadResponse>, Error> {
     // Save the file to a temporary location and get payload data.
-    match save_field_as_temp_file(field).await? {
+    match save_field_as_temp_file(field, keep_metadata).await? {
         // Pass file through deduplication and receive a response..
         Some(payload) => match deduplicate_payload(&payload).await {
             Some(response) => Ok(Some(response)),
@@ -355,46 +370,63 @@ pub async fn insert_field_as_attachment(
 
 pub type PayloadConstraintFn = fn(&attachments::ActiveModel) -> Result<bool, Error>;
 
-/// Receives a request payload and inserts it into the database and the s3 bucket.
+/// Receives a request payload and inserts it into the database and storage.
 pub async fn insert_payload_as_attachment(
     payload: UploadPayload,
     constraints: Option<PayloadConstraintFn>,
 ) -> Result<Option<UploadResponse>, Error> {
-    return Err(actix_web::error::ErrorInternalServerError("sneed"))?;
     log::info!("Filename: {}", payload.filename);
     log::info!("BLAKE3: {}", payload.hash);
     log::info!("MIME: {}", payload.mime);
 
-    let dimensions: (Option<i32>, Option<i32>);
-    let extension: Option<String>;
+    // Probing reads the whole file, which may be a long video.
+    let (payload, probed) = web::block(move || {
+        let probed = probe_payload(&payload);
+        (payload, probed)
+    })
+    .await
+    .map_err(|e| {
+        log::error!("put_file: {}", e);
+        actix_web::error::ErrorInternalServerError("put_file: probing media")
+    })?;
 
-    /** removed so xf-chat can build 21-oct/2025
-    match crate::ffmpeg::open_with_ffmpeg(&payload.tmp_path) {
-        Some(ffmpeg) => {
-            dimensions = match crate::ffmpeg::get_dimensions_from_input(&ffmpeg) {
-                Some(xy) => (Some(xy.0 as i32), Some(xy.1 as i32)),
-                None => (None, None),
-            };
-            extension = match crate::ffmpeg::get_extension_from_input(&ffmpeg) {
-                Some(ffext) => Some(ffext),
-                None => get_extension(&payload.filename, &payload.mime),
-            };
-        }
-        None => {
-            dimensions = (None, None);
-            extension = get_extension(&payload.filename, &payload.mime);
+    let media = match probed {
+        Ok(media) => media,
+        Err(err) => {
+            log::info!("put_file: rejected media: {}", err);
+            if let Err(e) = std::fs::remove_file(&payload.tmp_path) {
+                log::error!("put_file: delete tmp file error: {}", e);
+            }
+            return Err(err.into());
         }
     };
-    */
-    let filesize: i64 = payload.data.len().try_into().map_err(|e| {
+
+    // Media is described by its contents, everything else by what the client claims.
+    let (dimensions, extension, mime) = match media {
+        Some((dimensions, extension, mime)) => (
+            match dimensions {
+                Some((width, height)) => (Some(width as i32), Some(height as i32)),
+                None => (None, None),
+            },
+            Some(extension.to_owned()),
+            mime.to_owned(),
+        ),
+        None => (
+            (None, None),
+            get_extension(&payload.filename, &payload.mime),
+            payload.mime.to_string(),
+        ),
+    };
+
+    let filesize: i64 = payload.filesize.try_into().map_err(|e| {
         log::error!(
-            "put_file: failed convert filesize from usize to i64, too big?: {}",
+            "put_file: failed convert filesize from u64 to i64, too big?: {}",
             e
         );
         actix_web::error::ErrorInternalServerError("put_file: file too large")
     })?;
 
-    let s3_filename = match extension {
+    let storage_filename = match extension {
         Some(extension) => format!("{}.{}", payload.hash, extension),
         None => payload.hash.to_string(),
     };
@@ -404,14 +436,14 @@ pub async fn insert_payload_as_attachment(
     let new_attachment = attachments::ActiveModel {
         // This is our canonical filename, not the user's filename.
         // User's filename belongs in ugc_attachments.
-        filename: Set(s3_filename.to_owned()),
+        filename: Set(storage_filename.to_owned()),
         hash: Set(hash.to_owned()),
         first_seen_at: Set(now),
         last_seen_at: Set(now),
         filesize: Set(filesize),
         file_width: Set(dimensions.0),
         file_height: Set(dimensions.1),
-        mime: Set(payload.mime.to_string()),
+        mime: Set(mime),
         meta: Set(sea_orm::query::JsonValue::Null),
         ..Default::default()
     };
@@ -438,30 +470,29 @@ pub async fn insert_payload_as_attachment(
             actix_web::error::ErrorInternalServerError("put_file: failed to store file")
         })?;
 
-    let bucket = get_s3();
-    let list = bucket.list_objects_v2(&s3_filename).await.map_err(|e| {
-        log::error!("put_file: failed to list_objects_v2: {}", e);
+    let storage = get_storage();
+    let exists = storage.exists(&storage_filename).await.map_err(|e| {
+        log::error!("put_file: failed to check storage: {}", e);
         actix_web::error::ErrorInternalServerError("put_file: failed to check if file exists")
     })?;
 
-    // Check for existing s3 data.
-    let count = list.key_count.ok_or_else(|| {
-        log::error!("put_file: key_count, I don't think this should ever happen");
-        actix_web::error::ErrorInternalServerError("put_file: failed to check if file exists")
-    })?;
-
-    // s3 key count should only ever be 0 or 1, otherwise there's something wrong with the prefix
-    if count == 0 {
-        // Insert the file data into s3.
-        bucket
-            .put_object(payload.data, &s3_filename)
+    if !exists {
+        // Insert the file data into storage.
+        storage
+            .put_file(&payload.tmp_path, &storage_filename)
             .await
             .map_err(|e| {
-                log::error!("put_file: failed to put_object: {}", e);
+                log::error!("put_file: failed to put_file: {}", e);
                 actix_web::error::ErrorInternalServerError("put_file: failed to store file")
             })?;
     } else {
-        log::info!("put_file: duplicate upload, skipping S3 put_object");
+        log::info!("put_file: duplicate upload, skipping put_file");
+    }
+
+    // Thumbnails are a convenience and never fail the upload.
+    #[cfg(feature = "ffmpeg")]
+    if let Err(e) = insert_thumbnails(res.last_insert_id, payload.tmp_path.to_owned()).await {
+        log::error!("put_file: failed to create thumbnails: {}", e);
     }
 
     // !!! WARNING !!! we delete a file, be mindful and don't fucking delete my porn folder
@@ -474,12 +505,176 @@ pub async fn insert_payload_as_attachment(
     Ok(Some(UploadResponse {
         id: res.last_insert_id,
         hash: hash.to_owned(),
-        filename: s3_filename.to_owned(),
+        filename: storage_filename.to_owned(),
     }))
 }
 
+/// Dimensions, extension and MIME of a media upload.
+type ProbedMedia = (Option<(u32, u32)>, &'static str, &'static str);
+
+/// Returns true if an upload claims to be media by its MIME or its extension.
+fn claims_media(payload: &UploadPayload) -> bool {
+    let is_media = |mime: &Mime| matches!(mime.type_(), mime::IMAGE | mime::VIDEO | mime::AUDIO);
+
+    is_media(&payload.mime)
+        || get_extension(&payload.filename, &payload.mime)
+            .map(|extension| is_media(&actix_files::file_extension_to_mime(&extension)))
+            .unwrap_or(false)
+}
+
+/// Probes an upload and checks media against the media policy.
+/// Files which are not media return None. Uploads claiming to be media must be readable as media.
+#[cfg(feature = "ffmpeg")]
+fn probe_payload(payload: &UploadPayload) -> Result<Option<ProbedMedia>, MediaError> {
+    use crate::ffmpeg::{open_with_ffmpeg, probe_input};
+    use crate::media::get_media_policy;
+
+    let is_media = claims_media(payload);
+
+    match open_with_ffmpeg(&payload.tmp_path).map(|ctx| probe_input(&ctx)) {
+        Some(probe) if is_media || probe.get_media_type().is_some() => {
+            let (extension, mime) = probe.validate(get_media_policy())?;
+            Ok(Some((probe.get_dimensions(), extension, mime)))
+        }
+        _ if is_media => Err(MediaError::Unreadable),
+        _ => Ok(None),
+    }
+}
+
+/// Without FFmpeg only JPEG, PNG and WebP images can be probed. Other media is refused.
+#[cfg(not(feature = "ffmpeg"))]
+fn probe_payload(payload: &UploadPayload) -> Result<Option<ProbedMedia>, MediaError> {
+    use crate::media::{get_media_policy, probe_image};
+
+    let is_media = claims_media(payload);
+
+    // Only images are read into memory.
+    let mut header = Vec::with_capacity(crate::metadata::HEADER_LEN);
+    File::open(&payload.tmp_path)
+        .and_then(|f| {
+            f.take(crate::metadata::HEADER_LEN as u64)
+                .read_to_end(&mut header)
+        })
+        .map_err(|_| MediaError::Unreadable)?;
+    let probe = if crate::metadata::may_have_metadata(&header) {
+        std::fs::read(&payload.tmp_path)
+            .ok()
+            .and_then(|data| probe_image(&data))
+    } else {
+        None
+    };
+
+    match probe {
+        Some(probe) => {
+            let (extension, mime) = probe.validate(get_media_policy())?;
+            Ok(Some((probe.get_dimensions(), extension, mime)))
+        }
+        None if is_media => Err(MediaError::Unprobed),
+        None => Ok(None),
+    }
+}
+
+/// Generates a thumbnail of each standard size from an image or video.
+#[cfg(feature = "ffmpeg")]
+fn create_thumbnails(path: &Path) -> Vec<(ThumbnailSize, crate::ffmpeg::Thumbnail)> {
+    use crate::attachment::{get_scaled_dimensions, get_thumbnail_constraint};
+    use crate::ffmpeg::{encode_thumbnail, get_poster_frame, open_with_ffmpeg};
+
+    let frame = match open_with_ffmpeg(&path).and_then(|mut ctx| get_poster_frame(&mut ctx)) {
+        Some(frame) => frame,
+        None => return Vec::new(),
+    };
+    let dimensions = (frame.width() as i32, frame.height() as i32);
+
+    ThumbnailSize::iter()
+        .filter_map(|size| {
+            // Never scale up, small files have small thumbnails.
+            let constraint = get_thumbnail_constraint(size).min(dimensions.0.max(dimensions.1));
+            let (width, height) = get_scaled_dimensions(dimensions, constraint);
+            encode_thumbnail(&frame, width as u32, height as u32).map(|thumbnail| (size, thumbnail))
+        })
+        .collect()
+}
+
+/// Stores thumbnails for an attachment in storage and records them.
+/// Thumbnails are attachments themselves and are deduplicated by their hash.
+#[cfg(feature = "ffmpeg")]
+async fn insert_thumbnails(attachment_id: i32, tmp_path: PathBuf) -> Result<(), Error> {
+    use crate::orm::attachment_thumbnails;
+
+    let thumbnails = web::block(move || create_thumbnails(&tmp_path))
+        .await
+        .map_err(|e| {
+            log::error!("insert_thumbnails: {}", e);
+            actix_web::error::ErrorInternalServerError("put_file: failed to create thumbnails")
+        })?;
+
+    for (size, thumbnail) in thumbnails {
+        let hash = blake3::hash(&thumbnail.data).to_string();
+        let thumbnail_id = match get_attachment_by_hash(hash.to_owned()).await {
+            Some(attachment) => attachment.id,
+            None => {
+                let filename = format!("{}.jpg", hash);
+                let now = Utc::now().naive_utc();
+                let new_attachment = attachments::ActiveModel {
+                    filename: Set(filename.to_owned()),
+                    hash: Set(hash),
+                    first_seen_at: Set(now),
+                    last_seen_at: Set(now),
+                    filesize: Set(thumbnail.data.len() as i64),
+                    file_width: Set(Some(thumbnail.width as i32)),
+                    file_height: Set(Some(thumbnail.height as i32)),
+                    mime: Set(mime::IMAGE_JPEG.to_string()),
+                    meta: Set(sea_orm::query::JsonValue::Null),
+                    ..Default::default()
+                };
+
+                // Store the data first so a thumbnail is never recorded without it.
+                get_storage()
+                    .put_object(thumbnail.data, &filename)
+                    .await
+                    .map_err(|e| {
+                        log::error!("insert_thumbnails: failed to put_object: {}", e);
+                        actix_web::error::ErrorInternalServerError(
+                            "put_file: failed to store thumbnail",
+                        )
+                    })?;
+
+                attachments::Entity::insert(new_attachment)
+                    .exec(get_db_pool())
+                    .await
+                    .map_err(|e| {
+                        log::error!("insert_thumbnails: failed to insert attachment: {}", e);
+                        actix_web::error::ErrorInternalServerError(
+                            "put_file: failed to store thumbnail",
+                        )
+                    })?
+                    .last_insert_id
+            }
+        };
+
+        attachment_thumbnails::ActiveModel {
+            attachment_id: Set(attachment_id),
+            size: Set(size),
+            thumbnail_id: Set(thumbnail_id),
+        }
+        .insert(get_db_pool())
+        .await
+        .map_err(|e| {
+            log::error!("insert_thumbnails: failed to insert thumbnail: {}", e);
+            actix_web::error::ErrorInternalServerError("put_file: failed to store thumbnail")
+        })?;
+    }
+
+    Ok(())
+}
+
 /// Accepts a multipart field, stores it on the disk, and returns indetifying information about it.
-pub async fn save_field_as_temp_file(field: &mut Field) -> Result<Option<UploadPayload>, Error> {
+/// Unless metadata is kept, images are re-encoded without it before they are hashed.
+pub async fn save_field_as_temp_file(
+    field: &mut Field,
+    keep_metadata: bool,
+) -> Result<Option<UploadPayload>, Error> {
     let content_type = field.content_disposition();
     let filename = content_type
         .get_filename()
@@ -525,7 +720,7 @@ pub async fn save_field_as_temp_file(field: &mut Field) -> Result<Option<UploadP
     });
 
     let mut hasher = blake3::Hasher::new();
-    let mut buf: Vec<u8> = Vec::with_capacity(1024); // TODO can we estimate a real size from the multipart?
+    let mut filesize: u64 = 0;
 
     let (f, filepath) = f
         .await
@@ -550,24 +745,79 @@ pub async fn save_field_as_temp_file(field: &mut Field) -> Result<Option<UploadP
         })?;
 
         hasher.update(&bytes);
-        buf.extend(bytes.to_owned());
+        filesize += bytes.len() as u64;
 
-        f = web::block(move || f.write_all(&bytes.clone()).map(|_| f))
+        f = web::block(move || f.write_all(&bytes).map(|_| f))
             .await
             .unwrap()?;
     }
 
-    if buf.is_empty() {
+    if filesize == 0 {
         log::debug!("save_field_as_temp_file: empty file, aborting");
         //std::fs::remove_file(filename); tmp file never created if there are no bytes saved
         return Ok(None);
     }
 
+    let mut hash = hasher.finalize();
+    if !keep_metadata {
+        // Deduplication must match the sanitized data, which is what we store.
+        drop(f);
+        match strip_temp_file(filepath.to_owned()).await {
+            Ok(Some(stripped)) => (hash, filesize) = stripped,
+            Ok(None) => {}
+            Err(err) => {
+                let _ = std::fs::remove_file(&filepath);
+                return Err(err);
+            }
+        }
+    }
+
     Ok(Some(UploadPayload {
-        data: buf,
         filename,
+        filesize,
         tmp_path: filepath, // Warning: This is deleted at the end of processing.
-        hash: hasher.finalize(),
+        hash,
         mime: field.content_type().to_owned(),
     }))
 }
+
+/// Rewrites an image saved to DIR_TMP without its metadata.
+/// Returns the new hash and size, or None if the file is not an image and was left as it was.
+/// Images which cannot be stripped are rejected rather than stored with their metadata.
+pub async fn strip_temp_file(path: PathBuf) -> Result<Option<(blake3::Hash, u64)>, Error> {
+    strip_temp_file_into(path.to_owned(), path).await
+}
+
+/// Writes an image saved to DIR_TMP to `dest` without its metadata, leaving the original as it was.
+/// Returns the new hash and size, or None if the file is not an image and nothing was written.
+pub async fn strip_temp_file_into(
+    path: PathBuf,
+    dest: PathBuf,
+) -> Result<Option<(blake3::Hash, u64)>, Error> {
+    web::block(move || {
+        // Only images are read back into memory.
+        let mut header = Vec::with_capacity(crate::metadata::HEADER_LEN);
+        File::open(&path)?
+            .take(crate::metadata::HEADER_LEN as u64)
+            .read_to_end(&mut header)?;
+        if !crate::metadata::may_have_metadata(&header) {
+            return Ok(Ok(None));
+        }
+
+        match crate::metadata::strip_metadata(&std::fs::read(&path)?) {
+            Some(stripped) => std::fs::write(&dest, &stripped)
+                .map(|_| Ok(Some((blake3::hash(&stripped), stripped.len() as u64)))),
+            None => Ok(Err(MediaError::Unstripped)),
+        }
+    })
+    .await
+    .map_err(|e| {
+        log::error!("put_file: {}", e);
+        actix_web::error::ErrorInternalServerError("put_file: saving data")
+    })?
+    .map_err(|e: std::io::Error| {
+        log::error!("put_file: failed to strip metadata: {}", e);
+        actix_web::error::ErrorInternalServerError("put_file: saving data")
+    })?
+    .map_err(Error::from)
+}
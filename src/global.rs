@@ -1,12 +1,57 @@
 use once_cell::sync::OnceCell;
 
 static SESSION_TIME: OnceCell<chrono::Duration> = OnceCell::new();
+static READ_MARKER_AGE: OnceCell<chrono::Duration> = OnceCell::new();
+static UPLOAD_SESSION_AGE: OnceCell<chrono::Duration> = OnceCell::new();
+static UPLOAD_MAX_SIZE: OnceCell<i64> = OnceCell::new();
+static UPLOAD_MAX_SESSIONS: OnceCell<u64> = OnceCell::new();
+
+/// Days of read tracking kept if READ_MARKER_DAYS is not set.
+const DEFAULT_READ_MARKER_DAYS: i64 = 30;
+/// Hours an unfinished upload is kept if UPLOAD_SESSION_HOURS is not set.
+const DEFAULT_UPLOAD_SESSION_HOURS: i64 = 24;
+/// Megabytes a resumable upload may declare if UPLOAD_MAX_MB is not set.
+const DEFAULT_UPLOAD_MAX_MB: i64 = 512;
+/// Unfinished uploads a user may have at once if UPLOAD_MAX_SESSIONS is not set.
+const DEFAULT_UPLOAD_MAX_SESSIONS: u64 = 4;
 
 #[inline(always)]
 pub fn get_session_time() -> &'static chrono::Duration {
     unsafe { SESSION_TIME.get_unchecked() }
 }
 
+/// Content older than this is always read, so read markers older than this can be pruned.
+pub fn get_read_marker_age() -> chrono::Duration {
+    READ_MARKER_AGE
+        .get()
+        .cloned()
+        .unwrap_or_else(|| chrono::Duration::days(DEFAULT_READ_MARKER_DAYS))
+}
+
+/// Unfinished uploads which have received nothing for this long are abandoned and can be pruned.
+pub fn get_upload_session_age() -> chrono::Duration {
+    UPLOAD_SESSION_AGE
+        .get()
+        .cloned()
+        .unwrap_or_else(|| chrono::Duration::hours(DEFAULT_UPLOAD_SESSION_HOURS))
+}
+
+/// Resumable uploads may not declare more bytes than this.
+pub fn get_upload_max_size() -> i64 {
+    UPLOAD_MAX_SIZE
+        .get()
+        .cloned()
+        .unwrap_or(DEFAULT_UPLOAD_MAX_MB * 1024 * 1024)
+}
+
+/// Users may not begin another resumable upload while they have this many unfinished.
+pub fn get_upload_max_sessions() -> u64 {
+    UPLOAD_MAX_SESSIONS
+        .get()
+        .cloned()
+        .unwrap_or(DEFAULT_UPLOAD_MAX_SESSIONS)
+}
+
 pub fn init() {
     // Init SESSION_TIME
     let time = std::env::var("SESSION_TIME").expect("SESSION_TIME MISSING from .env");
@@ -18,4 +63,54 @@ pub fn init() {
     }
     let time = chrono::Duration::minutes(time);
     SESSION_TIME.set(time).unwrap();
+
+    // Init READ_MARKER_AGE
+    let days = match std::env::var("READ_MARKER_DAYS") {
+        Ok(days) => days
+            .parse::<i64>()
+            .expect("READ_MARKER_DAYS cannot be parsed as an integer"),
+        Err(_) => DEFAULT_READ_MARKER_DAYS,
+    };
+    if days < 1 {
+        panic!("READ_MARKER_DAYS must be at least 1!");
+    }
+    READ_MARKER_AGE.set(chrono::Duration::days(days)).unwrap();
+
+    // Init UPLOAD_SESSION_AGE
+    let hours = match std::env::var("UPLOAD_SESSION_HOURS") {
+        Ok(hours) => hours
+            .parse::<i64>()
+            .expect("UPLOAD_SESSION_HOURS cannot be parsed as an integer"),
+        Err(_) => DEFAULT_UPLOAD_SESSION_HOURS,
+    };
+    if hours < 1 {
+        panic!("UPLOAD_SESSION_HOURS must be at least 1!");
+    }
+    UPLOAD_SESSION_AGE
+        .set(chrono::Duration::hours(hours))
+        .unwrap();
+
+    // Init UPLOAD_MAX_SIZE
+    let megabytes = match std::env::var("UPLOAD_MAX_MB") {
+        Ok(megabytes) => megabytes
+            .parse::<i64>()
+            .expect("UPLOAD_MAX_MB cannot be parsed as an integer"),
+        Err(_) => DEFAULT_UPLOAD_MAX_MB,
+    };
+    if megabytes < 1 {
+        panic!("UPLOAD_MAX_MB must be at least 1!");
+    }
+    UPLOAD_MAX_SIZE.set(megabytes * 1024 * 1024).unwrap();
+
+    // Init UPLOAD_MAX_SESSIONS
+    let sessions = match std::env::var("UPLOAD_MAX_SESSIONS") {
+        Ok(sessions) => sessions
+            .parse::<u64>()
+            .expect("UPLOAD_MAX_SESSIONS cannot be parsed as an integer"),
+        Err(_) => DEFAULT_UPLOAD_MAX_SESSIONS,
+    };
+    if sessions < 1 {
+        panic!("UPLOAD_MAX_SESSIONS must be at least 1!");
+    }
+    UPLOAD_MAX_SESSIONS.set(sessions).unwrap();
 }
@@ -6,16 +6,27 @@ pub mod auth_2fa;
 pub mod bbcode;
 pub mod create_user;
 pub mod db;
+#[cfg(feature = "ffmpeg")]
+pub mod ffmpeg;
 pub mod filesystem;
 pub mod global;
 pub mod group;
+pub mod media;
+pub mod mention;
+pub mod metadata;
 pub mod middleware;
+pub mod notification;
 pub mod orm;
 pub mod permission;
-pub mod s3;
+pub mod reaction;
+pub mod read_marker;
 pub mod session;
+pub mod storage;
+pub mod tag;
 pub mod template;
 pub mod ugc;
+pub mod upload;
 pub mod url;
 pub mod user;
+pub mod watch;
 pub mod web;
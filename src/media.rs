@@ -0,0 +1,458 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use once_cell::sync::OnceCell;
+use std::fmt;
+
+static MEDIA_POLICY: OnceCell<MediaPolicy> = OnceCell::new();
+
+/// Formats accepted if MEDIA_FORMATS is not set, by extension.
+const DEFAULT_FORMATS: &str = "apng,bmp,flac,gif,jpeg,m4a,mp3,mp4,ogg,ogv,png,weba,webm,webp";
+/// Codecs accepted if MEDIA_CODECS is not set, by FFmpeg codec name.
+const DEFAULT_CODECS: &str =
+    "aac,apng,av1,bmp,flac,gif,h264,mjpeg,mp3,opus,png,vorbis,vp8,vp9,webp";
+const DEFAULT_MAX_STREAMS: usize = 2;
+const DEFAULT_MAX_DURATION: f64 = 600.0;
+const DEFAULT_MAX_WIDTH: u32 = 8192;
+const DEFAULT_MAX_HEIGHT: u32 = 8192;
+
+/// Limits on what media may be uploaded.
+#[derive(Debug)]
+pub struct MediaPolicy {
+    /// Extensions of accepted formats.
+    pub formats: Vec<String>,
+    /// FFmpeg names of accepted codecs.
+    pub codecs: Vec<String>,
+    pub max_streams: usize,
+    /// Seconds.
+    pub max_duration: f64,
+    pub max_width: u32,
+    pub max_height: u32,
+}
+
+impl Default for MediaPolicy {
+    fn default() -> Self {
+        Self {
+            formats: parse_list(DEFAULT_FORMATS),
+            codecs: parse_list(DEFAULT_CODECS),
+            max_streams: DEFAULT_MAX_STREAMS,
+            max_duration: DEFAULT_MAX_DURATION,
+            max_width: DEFAULT_MAX_WIDTH,
+            max_height: DEFAULT_MAX_HEIGHT,
+        }
+    }
+}
+
+fn parse_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim().to_ascii_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_env<T: std::str::FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(value) => value
+            .parse::<T>()
+            .unwrap_or_else(|_| panic!("{} cannot be parsed", key)),
+        Err(_) => default,
+    }
+}
+
+/// Returns the media policy, or the default policy if it was never loaded.
+pub fn get_media_policy() -> &'static MediaPolicy {
+    MEDIA_POLICY.get_or_init(MediaPolicy::default)
+}
+
+/// Loads the media policy from the environment.
+pub fn init() {
+    let policy = MediaPolicy {
+        formats: parse_list(
+            &std::env::var("MEDIA_FORMATS").unwrap_or_else(|_| DEFAULT_FORMATS.to_owned()),
+        ),
+        codecs: parse_list(
+            &std::env::var("MEDIA_CODECS").unwrap_or_else(|_| DEFAULT_CODECS.to_owned()),
+        ),
+        max_streams: parse_env("MEDIA_MAX_STREAMS", DEFAULT_MAX_STREAMS),
+        max_duration: parse_env("MEDIA_MAX_DURATION", DEFAULT_MAX_DURATION),
+        max_width: parse_env("MEDIA_MAX_WIDTH", DEFAULT_MAX_WIDTH),
+        max_height: parse_env("MEDIA_MAX_HEIGHT", DEFAULT_MAX_HEIGHT),
+    };
+
+    if MEDIA_POLICY.set(policy).is_err() {
+        log::warn!("Media policy was already loaded.");
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Other,
+}
+
+/// A stream found by probing a file.
+#[derive(Debug)]
+pub struct MediaStream {
+    pub kind: StreamKind,
+    /// FFmpeg codec name, e.g. `vp9`.
+    pub codec: String,
+    /// Video streams only.
+    pub dimensions: Option<(u32, u32)>,
+}
+
+/// What FFmpeg found in a file.
+#[derive(Debug)]
+pub struct MediaProbe {
+    /// FFmpeg demuxer name, e.g. `matroska,webm`.
+    pub format: String,
+    pub streams: Vec<MediaStream>,
+    /// Seconds. Still images have none.
+    pub duration: Option<f64>,
+}
+
+impl MediaProbe {
+    /// Returns the dimensions of the first video stream.
+    pub fn get_dimensions(&self) -> Option<(u32, u32)> {
+        self.streams.iter().find_map(|stream| stream.dimensions)
+    }
+
+    fn has_video(&self) -> bool {
+        self.streams
+            .iter()
+            .any(|stream| stream.kind == StreamKind::Video)
+    }
+
+    /// WebM allows only VP8, VP9 or AV1 video with Opus or Vorbis audio.
+    fn is_webm(&self) -> bool {
+        self.streams.iter().all(|stream| match stream.kind {
+            StreamKind::Video => matches!(stream.codec.as_str(), "vp8" | "vp9" | "av1"),
+            StreamKind::Audio => matches!(stream.codec.as_str(), "opus" | "vorbis"),
+            StreamKind::Other => false,
+        })
+    }
+
+    /// Returns the real extension and MIME of a file from its contents.
+    pub fn get_media_type(&self) -> Option<(&'static str, &'static str)> {
+        Some(match self.format.as_str() {
+            "matroska,webm" if self.is_webm() && self.has_video() => ("webm", "video/webm"),
+            "matroska,webm" if self.is_webm() => ("weba", "audio/webm"),
+            "matroska,webm" if self.has_video() => ("mkv", "video/x-matroska"),
+            "matroska,webm" => ("mka", "audio/x-matroska"),
+            "mov,mp4,m4a,3gp,3g2,mj2" if self.has_video() => ("mp4", "video/mp4"),
+            "mov,mp4,m4a,3gp,3g2,mj2" => ("m4a", "audio/mp4"),
+            "ogg" if self.has_video() => ("ogv", "video/ogg"),
+            "ogg" => ("ogg", "audio/ogg"),
+            "aac" => ("aac", "audio/aac"),
+            "flac" => ("flac", "audio/flac"),
+            "mp3" => ("mp3", "audio/mpeg"),
+            "apng" => ("apng", "image/apng"),
+            "bmp_pipe" => ("bmp", "image/bmp"),
+            "gif" => ("gif", "image/gif"),
+            "jpeg_pipe" => ("jpeg", "image/jpeg"),
+            "png_pipe" => ("png", "image/png"),
+            "webp_pipe" => ("webp", "image/webp"),
+            _ => return None,
+        })
+    }
+
+    /// Checks the file against a policy and returns its real extension and MIME.
+    pub fn validate(
+        &self,
+        policy: &MediaPolicy,
+    ) -> Result<(&'static str, &'static str), MediaError> {
+        let (extension, mime) = self
+            .get_media_type()
+            .ok_or_else(|| MediaError::UnsupportedFormat(self.format.to_owned()))?;
+        if !policy.formats.iter().any(|format| format == extension) {
+            return Err(MediaError::UnsupportedFormat(extension.to_owned()));
+        }
+
+        if self.streams.len() > policy.max_streams {
+            return Err(MediaError::TooManyStreams {
+                count: self.streams.len(),
+                max: policy.max_streams,
+            });
+        }
+
+        for stream in self.streams.iter() {
+            if !policy.codecs.iter().any(|codec| codec == &stream.codec) {
+                return Err(MediaError::UnsupportedCodec(stream.codec.to_owned()));
+            }
+
+            if let Some((width, height)) = stream.dimensions {
+                if width > policy.max_width || height > policy.max_height {
+                    return Err(MediaError::TooLarge {
+                        width,
+                        height,
+                        max_width: policy.max_width,
+                        max_height: policy.max_height,
+                    });
+                }
+            }
+        }
+
+        if let Some(duration) = self.duration {
+            if duration > policy.max_duration {
+                return Err(MediaError::TooLong {
+                    duration,
+                    max: policy.max_duration,
+                });
+            }
+        }
+
+        Ok((extension, mime))
+    }
+}
+
+/// Probes a JPEG, PNG or WebP image without FFmpeg, describing it as FFmpeg would.
+/// Returns None for anything else.
+pub fn probe_image(data: &[u8]) -> Option<MediaProbe> {
+    use crate::metadata::is_animated_png;
+    use image::ImageFormat;
+
+    let image_format = image::guess_format(data).ok()?;
+    let (format, codec) = match image_format {
+        ImageFormat::Png if is_animated_png(data) => ("apng", "apng"),
+        ImageFormat::Png => ("png_pipe", "png"),
+        ImageFormat::Jpeg => ("jpeg_pipe", "mjpeg"),
+        ImageFormat::WebP => ("webp_pipe", "webp"),
+        _ => return None,
+    };
+
+    let dimensions = image::io::Reader::with_format(std::io::Cursor::new(data), image_format)
+        .into_dimensions()
+        .ok()?;
+
+    Some(MediaProbe {
+        format: format.to_owned(),
+        streams: vec![MediaStream {
+            kind: StreamKind::Video,
+            codec: codec.to_owned(),
+            dimensions: Some(dimensions),
+        }],
+        duration: None,
+    })
+}
+
+/// Reasons a media upload is rejected.
+#[derive(Debug)]
+pub enum MediaError {
+    /// The file claims to be media but could not be read as any.
+    Unreadable,
+    /// The file claims to be a kind of media which this build cannot read.
+    Unprobed,
+    /// The image's metadata could not be removed.
+    Unstripped,
+    UnsupportedFormat(String),
+    UnsupportedCodec(String),
+    TooManyStreams {
+        count: usize,
+        max: usize,
+    },
+    TooLong {
+        duration: f64,
+        max: f64,
+    },
+    TooLarge {
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+}
+
+impl MediaError {
+    /// Returns a stable identifier for clients.
+    pub fn get_code(&self) -> &'static str {
+        match self {
+            Self::Unreadable => "unreadable",
+            Self::Unprobed => "unprobed",
+            Self::Unstripped => "unstripped",
+            Self::UnsupportedFormat(_) => "unsupported_format",
+            Self::UnsupportedCodec(_) => "unsupported_codec",
+            Self::TooManyStreams { .. } => "too_many_streams",
+            Self::TooLong { .. } => "too_long",
+            Self::TooLarge { .. } => "too_large",
+        }
+    }
+}
+
+impl fmt::Display for MediaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable => write!(f, "The file could not be read as media."),
+            Self::Unprobed => write!(
+                f,
+                "Only JPEG, PNG and WebP images can be uploaded to this server."
+            ),
+            Self::Unstripped => write!(
+                f,
+                "The image is damaged and could not be stripped of its metadata."
+            ),
+            Self::UnsupportedFormat(format) => write!(f, "The format '{}' is not allowed.", format),
+            Self::UnsupportedCodec(codec) => write!(f, "The codec '{}' is not allowed.", codec),
+            Self::TooManyStreams { count, max } => write!(
+                f,
+                "The file has {} streams but at most {} are allowed.",
+                count, max
+            ),
+            Self::TooLong { duration, max } => write!(
+                f,
+                "The file is {:.0} seconds long but at most {:.0} are allowed.",
+                duration, max
+            ),
+            Self::TooLarge {
+                width,
+                height,
+                max_width,
+                max_height,
+            } => write!(
+                f,
+                "The file is {}x{} but at most {}x{} is allowed.",
+                width, height, max_width, max_height
+            ),
+        }
+    }
+}
+
+impl ResponseError for MediaError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNSUPPORTED_MEDIA_TYPE
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(serde_json::json!({
+            "error": self.get_code(),
+            "message": self.to_string(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(kind: StreamKind, codec: &str) -> MediaStream {
+        MediaStream {
+            kind,
+            codec: codec.to_owned(),
+            dimensions: match kind {
+                StreamKind::Video => Some((1280, 720)),
+                _ => None,
+            },
+        }
+    }
+
+    fn probe(format: &str, streams: Vec<MediaStream>) -> MediaProbe {
+        MediaProbe {
+            format: format.to_owned(),
+            streams,
+            duration: Some(30.0),
+        }
+    }
+
+    #[test]
+    fn media_type() {
+        let webm = probe(
+            "matroska,webm",
+            vec![
+                stream(StreamKind::Video, "vp9"),
+                stream(StreamKind::Audio, "opus"),
+            ],
+        );
+        assert_eq!(webm.get_media_type(), Some(("webm", "video/webm")));
+
+        let mkv = probe(
+            "matroska,webm",
+            vec![
+                stream(StreamKind::Video, "h264"),
+                stream(StreamKind::Audio, "opus"),
+            ],
+        );
+        assert_eq!(mkv.get_media_type(), Some(("mkv", "video/x-matroska")));
+
+        let m4a = probe(
+            "mov,mp4,m4a,3gp,3g2,mj2",
+            vec![stream(StreamKind::Audio, "aac")],
+        );
+        assert_eq!(m4a.get_media_type(), Some(("m4a", "audio/mp4")));
+
+        let unknown = probe("avi", vec![stream(StreamKind::Video, "h264")]);
+        assert_eq!(unknown.get_media_type(), None);
+    }
+
+    #[test]
+    fn validate() {
+        let policy = MediaPolicy::default();
+
+        let webm = probe(
+            "matroska,webm",
+            vec![
+                stream(StreamKind::Video, "vp9"),
+                stream(StreamKind::Audio, "opus"),
+            ],
+        );
+        assert_eq!(webm.validate(&policy).unwrap(), ("webm", "video/webm"));
+
+        // MKV is not allowed by default.
+        let mkv = probe(
+            "matroska,webm",
+            vec![
+                stream(StreamKind::Video, "h264"),
+                stream(StreamKind::Audio, "opus"),
+            ],
+        );
+        assert_eq!(
+            mkv.validate(&policy).unwrap_err().get_code(),
+            "unsupported_format"
+        );
+
+        let hevc = probe(
+            "mov,mp4,m4a,3gp,3g2,mj2",
+            vec![stream(StreamKind::Video, "hevc")],
+        );
+        assert_eq!(
+            hevc.validate(&policy).unwrap_err().get_code(),
+            "unsupported_codec"
+        );
+
+        let streams = probe(
+            "mov,mp4,m4a,3gp,3g2,mj2",
+            vec![
+                stream(StreamKind::Video, "h264"),
+                stream(StreamKind::Audio, "aac"),
+                stream(StreamKind::Audio, "aac"),
+            ],
+        );
+        assert_eq!(
+            streams.validate(&policy).unwrap_err().get_code(),
+            "too_many_streams"
+        );
+
+        let mut long = probe("mp3", vec![stream(StreamKind::Audio, "mp3")]);
+        long.duration = Some(policy.max_duration + 1.0);
+        assert_eq!(long.validate(&policy).unwrap_err().get_code(), "too_long");
+
+        let mut large = probe("png_pipe", vec![stream(StreamKind::Video, "png")]);
+        large.streams[0].dimensions = Some((policy.max_width + 1, 1));
+        assert_eq!(large.validate(&policy).unwrap_err().get_code(), "too_large");
+    }
+
+    #[test]
+    fn image_probe() {
+        use image::{DynamicImage, ImageOutputFormat, RgbImage};
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(3, 2))
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .unwrap();
+        let png = probe_image(&png.into_inner()).expect("PNG should be probed");
+        assert_eq!(png.get_dimensions(), Some((3, 2)));
+        assert_eq!(
+            png.validate(&MediaPolicy::default()).unwrap(),
+            ("png", "image/png")
+        );
+
+        assert!(probe_image(b"Hello, world!").is_none());
+    }
+}
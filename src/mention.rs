@@ -0,0 +1,239 @@
+use crate::bbcode::{get_mentions, MentionTargets, Mentions};
+use crate::notification::{create_notifications, NewNotification};
+use crate::orm::notifications::{self, NotificationType};
+use crate::orm::{posts, ugc_mentions, user_name_history, user_names};
+use chrono::Utc;
+use sea_orm::sea_query::{Expr, Func};
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr};
+
+/// Resolves names mentioned in BbCode to users.
+/// Names must be lowercase, as they are matched without case.
+/// Current names are preferred, but a user who was renamed is still found by an older name.
+pub async fn get_mentioned_users<C>(conn: &C, names: &[String]) -> Result<Mentions, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut mentions = Mentions::default();
+
+    if names.is_empty() {
+        return Ok(mentions);
+    }
+
+    for user_name in user_names::Entity::find()
+        .filter(
+            Expr::expr(Func::lower(Expr::col(user_names::Column::Name)))
+                .is_in(names.iter().map(String::as_str)),
+        )
+        .all(conn)
+        .await?
+    {
+        mentions.insert(
+            &user_name.name,
+            user_name.user_id,
+            user_name.name.to_owned(),
+        );
+    }
+
+    let unresolved: Vec<&str> = names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| mentions.get(name).is_none())
+        .collect();
+
+    if unresolved.is_empty() {
+        return Ok(mentions);
+    }
+
+    // The most recent owner of an old name is who it refers to.
+    let history = user_name_history::Entity::find()
+        .filter(
+            Expr::expr(Func::lower(Expr::col(user_name_history::Column::Name))).is_in(unresolved),
+        )
+        .order_by_desc(user_name_history::Column::ApprovedAt)
+        .all(conn)
+        .await?;
+
+    if history.is_empty() {
+        return Ok(mentions);
+    }
+
+    let current_names = user_names::Entity::find()
+        .filter(user_names::Column::UserId.is_in(history.iter().map(|h| h.user_id)))
+        .all(conn)
+        .await?;
+
+    for old_name in history {
+        if mentions.get(&old_name.name).is_some() {
+            continue;
+        }
+
+        if let Some(current) = current_names.iter().find(|n| n.user_id == old_name.user_id) {
+            mentions.insert(&old_name.name, current.user_id, current.name.to_owned());
+        }
+    }
+
+    Ok(mentions)
+}
+
+/// Records the users mentioned in UGC and notifies anyone who has not been mentioned in it before.
+/// Users whose posts are quoted are notified as well, unless they were also mentioned.
+/// Users do not mention themselves, and editing a post does not notify the same user twice.
+pub async fn record_mentions<C>(
+    conn: &C,
+    ugc_id: i32,
+    author_id: Option<i32>,
+    content: &str,
+) -> Result<Vec<NewNotification>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let targets = get_mentions(content);
+
+    if targets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut notifications = record_mentioned_users(conn, ugc_id, author_id, &targets).await?;
+    notifications.extend(notify_quoted_users(conn, ugc_id, author_id, &targets).await?);
+    Ok(notifications)
+}
+
+async fn record_mentioned_users<C>(
+    conn: &C,
+    ugc_id: i32,
+    author_id: Option<i32>,
+    targets: &MentionTargets,
+) -> Result<Vec<NewNotification>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut user_ids = get_mentioned_users(conn, &targets.names)
+        .await?
+        .get_user_ids();
+
+    // Only users who exist may be mentioned by id.
+    if !targets.user_ids.is_empty() {
+        user_ids.extend(
+            user_names::Entity::find()
+                .filter(user_names::Column::UserId.is_in(targets.user_ids.to_owned()))
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|user_name| user_name.user_id),
+        );
+    }
+
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    user_ids.retain(|id| Some(*id) != author_id);
+
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let recorded: Vec<i32> = ugc_mentions::Entity::find()
+        .filter(ugc_mentions::Column::UgcId.eq(ugc_id))
+        .filter(ugc_mentions::Column::UserId.is_in(user_ids.to_owned()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|mention| mention.user_id)
+        .collect();
+    user_ids.retain(|id| !recorded.contains(id));
+
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let now = Utc::now().naive_utc();
+    ugc_mentions::Entity::insert_many(user_ids.iter().map(|user_id| ugc_mentions::ActiveModel {
+        ugc_id: Set(ugc_id),
+        user_id: Set(*user_id),
+        created_at: Set(now),
+    }))
+    .exec(conn)
+    .await?;
+
+    create_notifications(
+        conn,
+        user_ids
+            .into_iter()
+            .map(|user_id| NewNotification {
+                user_id,
+                actor_id: author_id,
+                notification_type: NotificationType::Mention,
+                ugc_id: Some(ugc_id),
+            })
+            .collect(),
+    )
+    .await
+}
+
+/// Notifies the authors of quoted posts, and users named as the source of a quote.
+/// Anyone already notified of this content is skipped.
+async fn notify_quoted_users<C>(
+    conn: &C,
+    ugc_id: i32,
+    author_id: Option<i32>,
+    targets: &MentionTargets,
+) -> Result<Vec<NewNotification>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut user_ids: Vec<i32> = Vec::new();
+
+    if !targets.quoted_post_ids.is_empty() {
+        user_ids.extend(
+            posts::Entity::find()
+                .filter(posts::Column::Id.is_in(targets.quoted_post_ids.to_owned()))
+                .all(conn)
+                .await?
+                .into_iter()
+                .filter_map(|post| post.user_id),
+        );
+    }
+
+    // Only users who exist may be quoted by id.
+    if !targets.quoted_user_ids.is_empty() {
+        user_ids.extend(
+            user_names::Entity::find()
+                .filter(user_names::Column::UserId.is_in(targets.quoted_user_ids.to_owned()))
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|user_name| user_name.user_id),
+        );
+    }
+
+    user_ids.sort_unstable();
+    user_ids.dedup();
+    user_ids.retain(|id| Some(*id) != author_id);
+
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let notified: Vec<i32> = notifications::Entity::find()
+        .filter(notifications::Column::UgcId.eq(ugc_id))
+        .filter(notifications::Column::UserId.is_in(user_ids.to_owned()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|notification| notification.user_id)
+        .collect();
+    user_ids.retain(|id| !notified.contains(id));
+
+    create_notifications(
+        conn,
+        user_ids
+            .into_iter()
+            .map(|user_id| NewNotification {
+                user_id,
+                actor_id: author_id,
+                notification_type: NotificationType::Quote,
+                ugc_id: Some(ugc_id),
+            })
+            .collect(),
+    )
+    .await
+}
@@ -0,0 +1,313 @@
+use image::{DynamicImage, ImageFormat, ImageOutputFormat};
+use std::io::Cursor;
+
+/// Quality of re-encoded JPEG images, up to 100.
+const JPEG_QUALITY: u8 = 90;
+
+/// Bytes from the start of a file needed by may_have_metadata.
+pub const HEADER_LEN: usize = 16;
+
+/// Returns true if the start of a file is a JPEG, PNG or WebP image which strip_metadata accepts.
+pub fn may_have_metadata(header: &[u8]) -> bool {
+    matches!(
+        image::guess_format(header),
+        Ok(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)
+    )
+}
+
+/// Removes the metadata from a JPEG, PNG or WebP image.
+/// Images are re-encoded with their orientation applied, so EXIF, XMP, ICC profiles and comments are all lost.
+/// Animated images, and images which cannot be re-encoded, have their metadata chunks removed instead.
+/// Returns None for other files, and for images too malformed to be stripped, which must not be stored.
+pub fn strip_metadata(data: &[u8]) -> Option<Vec<u8>> {
+    let format = image::guess_format(data).ok()?;
+    let animated = match format {
+        ImageFormat::Jpeg => false,
+        ImageFormat::Png => is_animated_png(data),
+        ImageFormat::WebP => is_animated_webp(data),
+        _ => return None,
+    };
+
+    if !animated {
+        if let Some(encoded) = reencode(data, format) {
+            return Some(encoded);
+        }
+    }
+
+    match format {
+        ImageFormat::Jpeg => strip_jpeg_segments(data),
+        ImageFormat::Png => strip_png_chunks(data),
+        _ => strip_webp_chunks(data),
+    }
+}
+
+/// Decodes an image and encodes only its pixels in the same format.
+fn reencode(data: &[u8], format: ImageFormat) -> Option<Vec<u8>> {
+    let image = match image::load_from_memory_with_format(data, format) {
+        Ok(image) => apply_orientation(image, get_orientation(data)),
+        Err(e) => {
+            log::warn!("reencode: failed to decode image: {}", e);
+            return None;
+        }
+    };
+
+    let output_format = match format {
+        ImageFormat::Jpeg => ImageOutputFormat::Jpeg(JPEG_QUALITY),
+        format => format.into(),
+    };
+
+    let mut buf = Cursor::new(Vec::with_capacity(data.len()));
+    match image.write_to(&mut buf, output_format) {
+        Ok(_) => Some(buf.into_inner()),
+        Err(e) => {
+            log::warn!("reencode: failed to encode image: {}", e);
+            None
+        }
+    }
+}
+
+/// Copies a JPEG without its APP1 (EXIF and XMP), APP13 (IPTC) and comment segments.
+/// Anything after the end of the image is dropped. Returns None if the segments cannot be followed.
+fn strip_jpeg_segments(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(data.get(..2)?);
+
+    let mut offset = 2;
+    loop {
+        if *data.get(offset)? != 0xff {
+            return None;
+        }
+        // Markers may be padded with any number of fill bytes.
+        while *data.get(offset + 1)? == 0xff {
+            offset += 1;
+        }
+        let marker = data[offset + 1];
+        match marker {
+            // End of image.
+            0xd9 => {
+                out.extend_from_slice(&data[offset..offset + 2]);
+                return Some(out);
+            }
+            // Markers without a length.
+            0x01 | 0xd0..=0xd7 => {
+                out.extend_from_slice(&data[offset..offset + 2]);
+                offset += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        let length = u16::from_be_bytes([*data.get(offset + 2)?, *data.get(offset + 3)?]) as usize;
+        let end = offset + 2 + length;
+        let segment = data.get(offset..end)?;
+        if !matches!(marker, 0xe1 | 0xed | 0xfe) {
+            out.extend_from_slice(segment);
+        }
+        offset = end;
+
+        // Entropy coded data follows a scan up to the next marker which is not a restart or padding.
+        if marker == 0xda {
+            let start = offset;
+            while *data.get(offset)? != 0xff
+                || matches!(*data.get(offset + 1)?, 0x00 | 0xd0..=0xd7 | 0xff)
+            {
+                offset += 1;
+            }
+            out.extend_from_slice(&data[start..offset]);
+        }
+    }
+}
+
+/// Copies a PNG without its eXIf and text chunks. Returns None if the chunks cannot be followed.
+fn strip_png_chunks(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(data.get(..8)?);
+
+    // Each chunk is a 4 byte length, a 4 byte type, the data and a 4 byte CRC.
+    let mut offset = 8;
+    loop {
+        let length = u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let chunk_type = data.get(offset + 4..offset + 8)?;
+        let chunk = data.get(offset..offset.checked_add(12 + length)?)?;
+        if !matches!(chunk_type, b"eXIf" | b"tEXt" | b"iTXt" | b"zTXt") {
+            out.extend_from_slice(chunk);
+        }
+        if chunk_type == b"IEND" {
+            return Some(out);
+        }
+        offset += chunk.len();
+    }
+}
+
+/// Copies a WebP without its EXIF and XMP chunks. Returns None if the chunks cannot be followed.
+fn strip_webp_chunks(data: &[u8]) -> Option<Vec<u8>> {
+    let riff_end = (u32::from_le_bytes(data.get(4..8)?.try_into().ok()?) as usize)
+        .checked_add(8)?
+        .min(data.len());
+    let mut out = Vec::with_capacity(data.len());
+    out.extend_from_slice(data.get(..12)?);
+
+    // Each chunk is a 4 byte type, a 4 byte length and the data, padded to an even length.
+    let mut offset = 12;
+    while offset < riff_end {
+        let chunk_type = data.get(offset..offset + 4)?;
+        let length =
+            u32::from_le_bytes(data.get(offset + 4..offset + 8)?.try_into().ok()?) as usize;
+        let end = offset.checked_add(8 + length + length % 2)?.min(riff_end);
+        let chunk = data.get(offset..end)?;
+        match chunk_type {
+            b"EXIF" | b"XMP " => {}
+            // The extended header flags which metadata chunks are present.
+            b"VP8X" if chunk.len() > 8 => {
+                out.extend_from_slice(chunk);
+                let flags = out.len() - chunk.len() + 8;
+                out[flags] &= !0x0c;
+            }
+            _ => out.extend_from_slice(chunk),
+        }
+        offset = end;
+    }
+
+    let riff_size = u32::try_from(out.len() - 8).ok()?;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
+
+/// Returns the EXIF orientation of an image, which is 1 if it is upright or unknown.
+fn get_orientation(data: &[u8]) -> u32 {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(data))
+        .ok()
+        .and_then(|fields| {
+            fields
+                .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        })
+        .unwrap_or(1)
+}
+
+/// Transforms an image so it is upright without an EXIF orientation.
+fn apply_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// APNG images have an animation control chunk.
+pub(crate) fn is_animated_png(data: &[u8]) -> bool {
+    // Each chunk is a 4 byte length, a 4 byte type, the data and a 4 byte CRC.
+    let mut offset = 8;
+    while offset + 8 <= data.len() {
+        let length = u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]) as usize;
+        match &data[offset + 4..offset + 8] {
+            b"acTL" => return true,
+            b"IDAT" | b"IEND" => return false,
+            _ => offset += 12 + length,
+        }
+    }
+    false
+}
+
+/// Animated WebP images have an extended header with the animation flag set.
+fn is_animated_webp(data: &[u8]) -> bool {
+    data.len() > 20 && &data[12..16] == b"VP8X" && data[20] & 0x02 != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, RgbImage};
+
+    /// An APP1 segment with an EXIF orientation and a camera make.
+    fn exif_segment(orientation: u16) -> Vec<u8> {
+        let mut tiff: Vec<u8> = b"MM\0\x2a\0\0\0\x08".to_vec();
+        tiff.extend(2u16.to_be_bytes());
+        // Make, ASCII, 4 bytes inline.
+        tiff.extend([0x01, 0x0f, 0, 2, 0, 0, 0, 4]);
+        tiff.extend(b"GPS\0");
+        // Orientation, SHORT, 1 value inline.
+        tiff.extend([0x01, 0x12, 0, 3, 0, 0, 0, 1]);
+        tiff.extend(orientation.to_be_bytes());
+        tiff.extend([0, 0]);
+        tiff.extend([0, 0, 0, 0]);
+
+        let mut segment = vec![0xff, 0xe1];
+        segment.extend((tiff.len() as u16 + 8).to_be_bytes());
+        segment.extend(b"Exif\0\0");
+        segment.extend(tiff);
+        segment
+    }
+
+    fn jpeg_with_exif(orientation: u16) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(4, 2))
+            .write_to(&mut buf, ImageOutputFormat::Jpeg(JPEG_QUALITY))
+            .unwrap();
+        let jpeg = buf.into_inner();
+
+        // The segment goes directly after the start of image marker.
+        let mut data = jpeg[..2].to_vec();
+        data.extend(exif_segment(orientation));
+        data.extend(&jpeg[2..]);
+        data
+    }
+
+    #[test]
+    fn strip_jpeg() {
+        let data = jpeg_with_exif(6);
+        assert_eq!(get_orientation(&data), 6);
+
+        let stripped = strip_metadata(&data).expect("JPEG should be re-encoded");
+        assert!(!stripped.windows(4).any(|window| window == b"Exif"));
+        assert!(!stripped.windows(4).any(|window| window == b"GPS\0"));
+        assert_eq!(get_orientation(&stripped), 1);
+
+        // Rotated a quarter turn.
+        let image = image::load_from_memory(&stripped).unwrap();
+        assert_eq!(image.dimensions(), (2, 4));
+    }
+
+    #[test]
+    fn strip_ignores_other_files() {
+        assert_eq!(strip_metadata(b"Hello, world!"), None);
+    }
+
+    #[test]
+    fn strip_animated_png() {
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(1, 1))
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .unwrap();
+        let mut apng = png.into_inner();
+        // An acTL chunk and an eXIf chunk before the image data.
+        let mut chunks = b"\0\0\0\x08acTL\0\0\0\x01\0\0\0\0\0\0\0\0".to_vec();
+        let tiff = &exif_segment(1)[10..];
+        chunks.extend((tiff.len() as u32).to_be_bytes());
+        chunks.extend(b"eXIf");
+        chunks.extend(tiff);
+        chunks.extend([0, 0, 0, 0]);
+        apng.splice(33..33, chunks);
+        assert!(is_animated_png(&apng));
+
+        let stripped = strip_metadata(&apng).expect("APNG should have its chunks removed");
+        assert!(is_animated_png(&stripped));
+        assert!(!stripped.windows(4).any(|window| window == b"eXIf"));
+        assert!(!stripped.windows(4).any(|window| window == b"GPS\0"));
+        assert!(stripped.ends_with(b"IEND\xae\x42\x60\x82"));
+
+        // Chunks which cannot be followed are not stored at all.
+        assert_eq!(strip_metadata(&apng[..60]), None);
+    }
+}
@@ -21,6 +21,8 @@ pub struct ClientCtxInner {
     pub groups: Vec<i32>,
     /// Permission data.
     pub permissions: Data<PermissionData>,
+    /// Number of notifications the user has not read.
+    pub unread_notifications: usize,
     /// Randomly generated string for CSR.
     pub nonce: String,
     /// Time the request started for page load statistics.
@@ -35,6 +37,7 @@ impl Default for ClientCtxInner {
             groups: Vec::new(),
             // Only users.
             client: None,
+            unread_notifications: 0,
             // Generally left default.
             nonce: Self::nonce(),
             request_start: Instant::now(),
@@ -45,16 +48,27 @@ impl Default for ClientCtxInner {
 impl ClientCtxInner {
     pub async fn from_session(session: &Session, permissions: Data<PermissionData>) -> Self {
         use crate::group::get_group_ids_for_client;
+        use crate::notification::count_unread_notifications;
         use crate::session::authenticate_client_by_session;
 
         let db = get_db_pool();
         let client = authenticate_client_by_session(session).await;
         let groups = get_group_ids_for_client(db, &client).await;
+        let unread_notifications = match &client {
+            Some(user) => count_unread_notifications(db, user.id)
+                .await
+                .unwrap_or_else(|err| {
+                    log::error!("Unable to count unread notifications: {}", err);
+                    0
+                }),
+            None => 0,
+        };
 
         ClientCtxInner {
             client,
             groups,
             permissions,
+            unread_notifications,
             ..Default::default()
         }
     }
@@ -143,6 +157,11 @@ impl ClientCtx {
         }
     }
 
+    /// Returns how many notifications the user has not read.
+    pub fn get_unread_notification_count(&self) -> usize {
+        self.0.unread_notifications
+    }
+
     pub fn get_user(&self) -> Option<&Profile> {
         self.0.client.as_ref()
     }
@@ -155,8 +174,42 @@ impl ClientCtx {
         self.0.permissions.can(self, tag)
     }
 
-    pub fn can_post_in_thread(&self, _thread: &crate::orm::threads::Model) -> bool {
-        self.can_post_in_forum()
+    /// Locked threads only accept replies from those who can unlock them.
+    pub fn can_post_in_thread(&self, thread: &crate::orm::threads::Model) -> bool {
+        (!thread.is_locked || self.can_lock_thread(thread)) && self.can_post_in_forum()
+    }
+
+    pub fn can_lock_thread(&self, _thread: &crate::orm::threads::Model) -> bool {
+        self.can("lock_threads")
+    }
+
+    pub fn can_sticky_thread(&self, _thread: &crate::orm::threads::Model) -> bool {
+        self.can("sticky_threads")
+    }
+
+    pub fn can_move_thread(&self, _thread: &crate::orm::threads::Model) -> bool {
+        self.can("move_threads")
+    }
+
+    pub fn can_merge_thread(&self, _thread: &crate::orm::threads::Model) -> bool {
+        self.can("merge_threads")
+    }
+
+    pub fn can_split_thread(&self, _thread: &crate::orm::threads::Model) -> bool {
+        self.can("split_threads")
+    }
+
+    /// Returns true if any moderation tool is available for this thread.
+    pub fn can_moderate_thread(&self, thread: &crate::orm::threads::Model) -> bool {
+        self.can_lock_thread(thread)
+            || self.can_sticky_thread(thread)
+            || self.can_move_thread(thread)
+            || self.can_merge_thread(thread)
+            || self.can_split_thread(thread)
+    }
+
+    pub fn can_view_forum(&self, _forum: &crate::orm::forums::Model) -> bool {
+        true
     }
 
     pub fn can_post_in_forum(&self) -> bool {
@@ -167,14 +220,81 @@ impl ClientCtx {
         self.is_user() && self.get_id() == post.user_id
     }
 
+    pub fn can_undelete_post(&self, _post: &crate::web::post::PostForTemplate) -> bool {
+        self.can("undelete_posts")
+    }
+
+    /// Hard deletion removes the post and its UGC entirely.
+    pub fn can_hard_delete_post(&self, _post: &crate::web::post::PostForTemplate) -> bool {
+        self.can("hard_delete_posts")
+    }
+
+    /// Users may react to content, but only to their own if permitted.
+    pub fn can_react_to(&self, author_id: &Option<i32>) -> bool {
+        self.is_user() && (self.get_id() != *author_id || self.can("react_to_own_content"))
+    }
+
+    /// Uploads keep their metadata, such as EXIF location data, only if permitted.
+    pub fn can_upload_original_media(&self) -> bool {
+        self.can("upload_original_media")
+    }
+
     pub fn can_update_post(&self, post: &crate::web::post::PostForTemplate) -> bool {
         self.is_user() && self.get_id() == post.user_id
     }
 
     pub fn can_read_post(&self, post: &crate::web::post::PostForTemplate) -> bool {
+        if !self.can_read_content(post.user_id, post.content_status) {
+            return false;
+        }
+
         // TODO: In XenForo, users cannot view their own deleted posts.
         // This should be a moderator setting. Maybe a 'can view own deleted posts' option.
-        post.deleted_at.is_none() || self.get_id() == post.user_id
+        post.deleted_at.is_none()
+            || self.get_id() == post.user_id
+            || self.can_undelete_post(post)
+            || self.can_hard_delete_post(post)
+    }
+
+    /// Threads share the status of their first post.
+    pub fn can_read_thread(
+        &self,
+        thread: &crate::orm::threads::Model,
+        status: crate::ugc::ContentStatus,
+    ) -> bool {
+        self.can_read_content(thread.user_id, status)
+    }
+
+    /// Queued content is seen by its author and moderators. Hidden content only by moderators.
+    pub fn can_read_content(
+        &self,
+        user_id: Option<i32>,
+        status: crate::ugc::ContentStatus,
+    ) -> bool {
+        use crate::ugc::ContentStatus;
+
+        match status {
+            ContentStatus::Visible => true,
+            ContentStatus::InQueue if self.is_user() && self.get_id() == user_id => true,
+            _ => self.can_approve_content(),
+        }
+    }
+
+    /// Moderators may view, approve and reject content in the moderation queue.
+    pub fn can_approve_content(&self) -> bool {
+        self.can("approve_content")
+    }
+
+    /// Returns the status new content from this client is created with.
+    /// Groups with require_approval have their content held for a moderator.
+    pub fn get_new_content_status(&self) -> crate::ugc::ContentStatus {
+        use crate::ugc::ContentStatus;
+
+        if self.can("require_approval") && !self.can_approve_content() {
+            ContentStatus::InQueue
+        } else {
+            ContentStatus::Visible
+        }
     }
 
     pub fn get_nonce(&self) -> &String {
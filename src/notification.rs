@@ -0,0 +1,477 @@
+use crate::orm::notifications::{self, NotificationType};
+use crate::orm::{posts, threads, user_names};
+use crate::web::chat::message::Notify;
+use actix::Recipient;
+use chrono::{NaiveDateTime, Utc};
+use once_cell::sync::OnceCell;
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{entity::*, query::*, ActiveEnum, ConnectionTrait, DbErr, FromQueryResult};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Notifications listed on each page of the notification center.
+pub const NOTIFICATIONS_PER_PAGE: i32 = 30;
+
+static PUSH_RECIPIENT: OnceCell<Recipient<Notify>> = OnceCell::new();
+
+/// Sends new notifications to users with an open chat connection.
+/// Without a recipient, notifications are only stored.
+pub fn init_push(recipient: Recipient<Notify>) {
+    if PUSH_RECIPIENT.set(recipient).is_err() {
+        log::warn!("Notification push recipient was already set.");
+    }
+}
+
+/// An event which a user is to be notified of.
+pub struct NewNotification {
+    pub user_id: i32,
+    /// The user who caused the event, if any.
+    pub actor_id: Option<i32>,
+    pub notification_type: NotificationType,
+    /// The content the event happened in, if any.
+    pub ugc_id: Option<i32>,
+}
+
+/// A notification as it is sent live to chat connections.
+#[derive(Serialize)]
+struct PushNotification {
+    notification_type: String,
+    message: String,
+    url: &'static str,
+}
+
+#[derive(Serialize)]
+struct PushNotifications {
+    notifications: Vec<PushNotification>,
+}
+
+/// A notification with the names of what it refers to.
+#[derive(Debug, FromQueryResult)]
+pub struct NotificationForTemplate {
+    pub id: i32,
+    pub notification_type: NotificationType,
+    pub created_at: NaiveDateTime,
+    pub read_at: Option<NaiveDateTime>,
+    pub actor_name: Option<String>,
+    pub thread_id: Option<i32>,
+    pub thread_title: Option<String>,
+    pub position: Option<i32>,
+}
+
+impl NotificationForTemplate {
+    pub fn get_message(&self) -> String {
+        get_notification_message(
+            &self.notification_type,
+            self.actor_name.as_deref(),
+            self.thread_title.as_deref(),
+        )
+    }
+
+    /// Returns where the notification leads, if its content is a post.
+    pub fn get_url(&self) -> Option<String> {
+        match (self.thread_id, self.position) {
+            (Some(thread_id), Some(position)) => {
+                Some(crate::web::thread::get_url_for_pos(thread_id, position))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_unread(&self) -> bool {
+        self.read_at.is_none()
+    }
+}
+
+/// Describes a notification in a sentence.
+/// TODO: l10n
+pub fn get_notification_message(
+    notification_type: &NotificationType,
+    actor_name: Option<&str>,
+    thread_title: Option<&str>,
+) -> String {
+    let actor_name = actor_name.unwrap_or("Someone");
+    let mut message = match notification_type {
+        NotificationType::Mention => format!("{} mentioned you", actor_name),
+        NotificationType::Quote => format!("{} quoted your post", actor_name),
+        NotificationType::Reaction => format!("{} reacted to your content", actor_name),
+        NotificationType::Reply => format!("{} replied", actor_name),
+        NotificationType::Thread => format!("{} posted a new thread", actor_name),
+    };
+
+    if let Some(thread_title) = thread_title {
+        message.push_str(" in ");
+        message.push_str(thread_title);
+    }
+
+    message.push('.');
+    message
+}
+
+/// Notifies users of events. Returns the notifications so they can be pushed once committed.
+pub async fn create_notifications<C>(
+    conn: &C,
+    notifications: Vec<NewNotification>,
+) -> Result<Vec<NewNotification>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    if notifications.is_empty() {
+        return Ok(notifications);
+    }
+
+    let now = Utc::now().naive_utc();
+    notifications::Entity::insert_many(notifications.iter().map(|notification| {
+        notifications::ActiveModel {
+            user_id: Set(notification.user_id),
+            actor_id: Set(notification.actor_id),
+            notification_type: Set(notification.notification_type.to_owned()),
+            ugc_id: Set(notification.ugc_id),
+            created_at: Set(now),
+            read_at: Set(None),
+            ..Default::default()
+        }
+    }))
+    .exec(conn)
+    .await?;
+
+    Ok(notifications)
+}
+
+/// Sends notifications to the chat connections of the users they are for.
+/// Call this once the notifications are committed, so nobody is sent one which was rolled back.
+pub async fn push_notifications<C>(conn: &C, notifications: &[NewNotification]) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let recipient = match PUSH_RECIPIENT.get() {
+        Some(recipient) if !notifications.is_empty() => recipient,
+        _ => return Ok(()),
+    };
+
+    #[derive(Debug, FromQueryResult)]
+    struct ThreadTitle {
+        ugc_id: i32,
+        title: String,
+    }
+
+    let actor_names: HashMap<i32, String> = user_names::Entity::find()
+        .filter(user_names::Column::UserId.is_in(notifications.iter().filter_map(|n| n.actor_id)))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|user_name| (user_name.user_id, user_name.name))
+        .collect();
+
+    let thread_titles: HashMap<i32, String> = posts::Entity::find()
+        .select_only()
+        .column(posts::Column::UgcId)
+        .inner_join(threads::Entity)
+        .column(threads::Column::Title)
+        .filter(posts::Column::UgcId.is_in(notifications.iter().filter_map(|n| n.ugc_id)))
+        .into_model::<ThreadTitle>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|thread| (thread.ugc_id, thread.title))
+        .collect();
+
+    let mut by_user: HashMap<i32, Vec<PushNotification>> = HashMap::new();
+    for notification in notifications {
+        by_user
+            .entry(notification.user_id)
+            .or_default()
+            .push(PushNotification {
+                notification_type: notification.notification_type.to_value(),
+                message: get_notification_message(
+                    &notification.notification_type,
+                    notification
+                        .actor_id
+                        .and_then(|id| actor_names.get(&id))
+                        .map(String::as_str),
+                    notification
+                        .ugc_id
+                        .and_then(|id| thread_titles.get(&id))
+                        .map(String::as_str),
+                ),
+                url: "/account/notifications",
+            });
+    }
+
+    for (user_id, notifications) in by_user {
+        match serde_json::to_string(&PushNotifications { notifications }) {
+            Ok(message) => recipient.do_send(Notify {
+                user_id: user_id as u32,
+                message,
+            }),
+            Err(err) => log::error!("push_notifications: {}", err),
+        }
+    }
+
+    Ok(())
+}
+
+/// Notifies the users watching a thread of a reply.
+/// Users already notified of this content, or of a reply in this thread they have not read, are skipped.
+pub async fn notify_thread_watchers<C>(
+    conn: &C,
+    thread_id: i32,
+    ugc_id: i32,
+    author_id: Option<i32>,
+) -> Result<Vec<NewNotification>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut user_ids = crate::watch::get_thread_watchers(conn, thread_id).await?;
+    user_ids.retain(|id| Some(*id) != author_id);
+
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let notified: Vec<i32> = notifications::Entity::find()
+        .filter(notifications::Column::UserId.is_in(user_ids.to_owned()))
+        .filter(
+            Condition::any()
+                .add(notifications::Column::UgcId.eq(ugc_id))
+                .add(
+                    Condition::all()
+                        .add(notifications::Column::NotificationType.eq(NotificationType::Reply))
+                        .add(notifications::Column::ReadAt.is_null())
+                        .add(
+                            notifications::Column::UgcId.in_subquery(
+                                Query::select()
+                                    .column(posts::Column::UgcId)
+                                    .from(posts::Entity)
+                                    .and_where(posts::Column::ThreadId.eq(thread_id))
+                                    .to_owned(),
+                            ),
+                        ),
+                ),
+        )
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|notification| notification.user_id)
+        .collect();
+    user_ids.retain(|id| !notified.contains(id));
+
+    create_notifications(
+        conn,
+        user_ids
+            .into_iter()
+            .map(|user_id| NewNotification {
+                user_id,
+                actor_id: author_id,
+                notification_type: NotificationType::Reply,
+                ugc_id: Some(ugc_id),
+            })
+            .collect(),
+    )
+    .await
+}
+
+/// Notifies the users watching a forum of a new thread, by the content of its first post.
+/// Users already notified of this content are skipped.
+pub async fn notify_forum_watchers<C>(
+    conn: &C,
+    forum_id: i32,
+    ugc_id: i32,
+    author_id: Option<i32>,
+) -> Result<Vec<NewNotification>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut user_ids = crate::watch::get_forum_watchers(conn, forum_id).await?;
+    user_ids.retain(|id| Some(*id) != author_id);
+
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let notified: Vec<i32> = notifications::Entity::find()
+        .filter(notifications::Column::UserId.is_in(user_ids.to_owned()))
+        .filter(notifications::Column::UgcId.eq(ugc_id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|notification| notification.user_id)
+        .collect();
+    user_ids.retain(|id| !notified.contains(id));
+
+    create_notifications(
+        conn,
+        user_ids
+            .into_iter()
+            .map(|user_id| NewNotification {
+                user_id,
+                actor_id: author_id,
+                notification_type: NotificationType::Thread,
+                ugc_id: Some(ugc_id),
+            })
+            .collect(),
+    )
+    .await
+}
+
+/// Notifies an author of a reaction to their content.
+/// Reacting to your own content, or reacting again after removing a reaction, notifies nobody.
+pub async fn notify_reaction<C>(
+    conn: &C,
+    ugc_id: i32,
+    author_id: Option<i32>,
+    user_id: i32,
+) -> Result<Vec<NewNotification>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let author_id = match author_id {
+        Some(author_id) if author_id != user_id => author_id,
+        _ => return Ok(Vec::new()),
+    };
+
+    let notified = notifications::Entity::find()
+        .filter(notifications::Column::UserId.eq(author_id))
+        .filter(notifications::Column::ActorId.eq(user_id))
+        .filter(notifications::Column::NotificationType.eq(NotificationType::Reaction))
+        .filter(notifications::Column::UgcId.eq(ugc_id))
+        .one(conn)
+        .await?
+        .is_some();
+
+    if notified {
+        return Ok(Vec::new());
+    }
+
+    create_notifications(
+        conn,
+        vec![NewNotification {
+            user_id: author_id,
+            actor_id: Some(user_id),
+            notification_type: NotificationType::Reaction,
+            ugc_id: Some(ugc_id),
+        }],
+    )
+    .await
+}
+
+/// Returns how many notifications a user has.
+pub async fn count_notifications<C>(conn: &C, user_id: i32) -> Result<usize, DbErr>
+where
+    C: ConnectionTrait,
+{
+    notifications::Entity::find()
+        .filter(notifications::Column::UserId.eq(user_id))
+        .count(conn)
+        .await
+}
+
+/// Returns how many notifications a user has not read.
+pub async fn count_unread_notifications<C>(conn: &C, user_id: i32) -> Result<usize, DbErr>
+where
+    C: ConnectionTrait,
+{
+    notifications::Entity::find()
+        .filter(notifications::Column::UserId.eq(user_id))
+        .filter(notifications::Column::ReadAt.is_null())
+        .count(conn)
+        .await
+}
+
+/// Returns a select for a user's notifications and the names of what they refer to.
+fn find_notifications_for_template(user_id: i32) -> Select<notifications::Entity> {
+    notifications::Entity::find()
+        .join(
+            JoinType::LeftJoin,
+            notifications::Entity::belongs_to(user_names::Entity)
+                .from(notifications::Column::ActorId)
+                .to(user_names::Column::UserId)
+                .into(),
+        )
+        .column_as(user_names::Column::Name, "actor_name")
+        .join(
+            JoinType::LeftJoin,
+            notifications::Entity::belongs_to(posts::Entity)
+                .from(notifications::Column::UgcId)
+                .to(posts::Column::UgcId)
+                .into(),
+        )
+        .column_as(posts::Column::ThreadId, "thread_id")
+        .column_as(posts::Column::Position, "position")
+        .join(JoinType::LeftJoin, posts::Relation::Threads.def())
+        .column_as(threads::Column::Title, "thread_title")
+        .filter(notifications::Column::UserId.eq(user_id))
+}
+
+/// Returns a page of a user's notifications, newest first.
+pub async fn get_notifications_for_template<C>(
+    conn: &C,
+    user_id: i32,
+    page: i32,
+) -> Result<Vec<NotificationForTemplate>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    find_notifications_for_template(user_id)
+        .order_by_desc(notifications::Column::CreatedAt)
+        .order_by_desc(notifications::Column::Id)
+        .limit(NOTIFICATIONS_PER_PAGE as u64)
+        .offset(((page - 1) * NOTIFICATIONS_PER_PAGE) as u64)
+        .into_model::<NotificationForTemplate>()
+        .all(conn)
+        .await
+}
+
+/// Returns one of a user's notifications, marking it read.
+pub async fn read_notification<C>(
+    conn: &C,
+    user_id: i32,
+    id: i32,
+) -> Result<Option<NotificationForTemplate>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    notifications::Entity::update_many()
+        .col_expr(
+            notifications::Column::ReadAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(notifications::Column::Id.eq(id))
+        .filter(notifications::Column::UserId.eq(user_id))
+        .filter(notifications::Column::ReadAt.is_null())
+        .exec(conn)
+        .await?;
+
+    find_notifications_for_template(user_id)
+        .filter(notifications::Column::Id.eq(id))
+        .into_model::<NotificationForTemplate>()
+        .one(conn)
+        .await
+}
+
+/// Marks a user's notifications read. Only those about this content are marked, if any is given.
+pub async fn mark_notifications_read<C>(
+    conn: &C,
+    user_id: i32,
+    ugc_ids: Option<Vec<i32>>,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let query = notifications::Entity::update_many()
+        .col_expr(
+            notifications::Column::ReadAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(notifications::Column::UserId.eq(user_id))
+        .filter(notifications::Column::ReadAt.is_null());
+
+    match ugc_ids {
+        Some(ugc_ids) if ugc_ids.is_empty() => return Ok(()),
+        Some(ugc_ids) => query.filter(notifications::Column::UgcId.is_in(ugc_ids)),
+        None => query,
+    }
+    .exec(conn)
+    .await?;
+
+    Ok(())
+}
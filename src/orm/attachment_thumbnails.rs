@@ -8,9 +8,32 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub attachment_id: i32,
     #[sea_orm(primary_key, auto_increment = false)]
+    pub size: ThumbnailSize,
     pub thumbnail_id: i32,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "thumbnail_size")]
+pub enum ThumbnailSize {
+    #[sea_orm(string_value = "xs")]
+    Xs,
+    #[sea_orm(string_value = "s")]
+    S,
+    #[sea_orm(string_value = "m")]
+    M,
+    #[sea_orm(string_value = "l")]
+    L,
+}
+
+/// Required to use the enum in a primary key. Keys are never generated by the database.
+impl sea_orm::TryFromU64 for ThumbnailSize {
+    fn try_from_u64(_: u64) -> Result<Self, DbErr> {
+        Err(DbErr::Exec(
+            "ThumbnailSize cannot be converted from u64".to_owned(),
+        ))
+    }
+}
+
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
@@ -13,6 +13,13 @@ pub struct Model {
     pub description: Option<String>,
     pub last_post_id: Option<i32>,
     pub last_thread_id: Option<i32>,
+    pub threads_per_page: Option<i32>,
+    pub parent_id: Option<i32>,
+    pub display_order: i32,
+    pub is_category: bool,
+    pub thread_count: i32,
+    pub post_count: i32,
+    pub last_post_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -33,6 +40,14 @@ pub enum Relation {
         on_delete = "SetNull"
     )]
     Threads,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Parent,
 }
 
 impl Related<super::posts::Entity> for Entity {
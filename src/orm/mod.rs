@@ -4,23 +4,39 @@ pub mod prelude;
 
 pub mod attachment_thumbnails;
 pub mod attachments;
+pub mod bbcode_custom_tags;
 pub mod chat_messages;
 pub mod chat_rooms;
 pub mod forum_permissions;
+pub mod forum_reads;
+pub mod forum_watches;
+pub mod forum_thread_prefixes;
 pub mod forums;
 pub mod groups;
 pub mod ip;
+pub mod notifications;
 pub mod permission_categories;
 pub mod permission_collections;
 pub mod permission_values;
 pub mod permissions;
 pub mod posts;
+pub mod reaction_types;
 pub mod sessions;
+pub mod tags;
+pub mod thread_prefixes;
+pub mod thread_reads;
+pub mod thread_watches;
+pub mod thread_tags;
 pub mod threads;
 pub mod ugc;
 pub mod ugc_attachments;
 pub mod ugc_deletions;
+pub mod ugc_mentions;
+pub mod ugc_reactions;
+pub mod ugc_revision_html;
+pub mod ugc_revision_html_mentions;
 pub mod ugc_revisions;
+pub mod upload_sessions;
 pub mod user_2fa;
 pub mod user_avatars;
 pub mod user_groups;
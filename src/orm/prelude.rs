@@ -2,23 +2,39 @@
 
 pub use super::attachment_thumbnails::Entity as AttachmentThumbnails;
 pub use super::attachments::Entity as Attachments;
+pub use super::bbcode_custom_tags::Entity as BbcodeCustomTags;
 pub use super::chat_messages::Entity as ChatMessages;
 pub use super::chat_rooms::Entity as ChatRooms;
 pub use super::forum_permissions::Entity as ForumPermissions;
+pub use super::forum_reads::Entity as ForumReads;
+pub use super::forum_watches::Entity as ForumWatches;
+pub use super::forum_thread_prefixes::Entity as ForumThreadPrefixes;
 pub use super::forums::Entity as Forums;
 pub use super::groups::Entity as Groups;
 pub use super::ip::Entity as Ip;
+pub use super::notifications::Entity as Notifications;
 pub use super::permission_categories::Entity as PermissionCategories;
 pub use super::permission_collections::Entity as PermissionCollections;
 pub use super::permission_values::Entity as PermissionValues;
 pub use super::permissions::Entity as Permissions;
 pub use super::posts::Entity as Posts;
+pub use super::reaction_types::Entity as ReactionTypes;
 pub use super::sessions::Entity as Sessions;
+pub use super::tags::Entity as Tags;
+pub use super::thread_prefixes::Entity as ThreadPrefixes;
+pub use super::thread_reads::Entity as ThreadReads;
+pub use super::thread_watches::Entity as ThreadWatches;
+pub use super::thread_tags::Entity as ThreadTags;
 pub use super::threads::Entity as Threads;
 pub use super::ugc::Entity as Ugc;
 pub use super::ugc_attachments::Entity as UgcAttachments;
 pub use super::ugc_deletions::Entity as UgcDeletions;
+pub use super::ugc_mentions::Entity as UgcMentions;
+pub use super::ugc_reactions::Entity as UgcReactions;
+pub use super::ugc_revision_html::Entity as UgcRevisionHtml;
+pub use super::ugc_revision_html_mentions::Entity as UgcRevisionHtmlMentions;
 pub use super::ugc_revisions::Entity as UgcRevisions;
+pub use super::upload_sessions::Entity as UploadSessions;
 pub use super::user_2fa::Entity as User2fa;
 pub use super::user_groups::Entity as UserGroups;
 pub use super::user_name_history::Entity as UserNameHistory;
@@ -19,6 +19,9 @@ pub struct Model {
     pub first_post_id: Option<i32>,
     pub last_post_id: Option<i32>,
     pub last_post_at: Option<DateTime>,
+    pub is_locked: bool,
+    pub is_sticky: bool,
+    pub prefix_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -57,6 +60,14 @@ pub enum Relation {
     Forum,
     #[sea_orm(has_many = "super::posts::Entity")]
     Posts,
+    #[sea_orm(
+        belongs_to = "super::thread_prefixes::Entity",
+        from = "Column::PrefixId",
+        to = "super::thread_prefixes::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Prefix,
     #[sea_orm(
         belongs_to = "super::user_names::Entity",
         from = "Column::UserId",
@@ -79,6 +90,12 @@ impl Related<super::forums::Entity> for Entity {
     }
 }
 
+impl Related<super::thread_prefixes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Prefix.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
@@ -8,6 +8,7 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub ugc_revision_id: Option<i32>,
+    pub content_status: crate::ugc::ContentStatus,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,215 @@
+use crate::notification::{notify_reaction, push_notifications};
+use crate::orm::{reaction_types, ugc_reactions};
+use chrono::prelude::Utc;
+use once_cell::sync::OnceCell;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    entity::*, query::*, ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+static REACTION_TYPES: OnceCell<Arc<Vec<reaction_types::Model>>> = OnceCell::new();
+
+/// Returns the reaction types loaded at startup, in order, or none if they were never loaded.
+pub fn get_reaction_types() -> Arc<Vec<reaction_types::Model>> {
+    REACTION_TYPES.get().cloned().unwrap_or_default()
+}
+
+/// Loads active reaction types from the database.
+pub async fn init_reaction_types(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let types = reaction_types::Entity::find()
+        .filter(reaction_types::Column::Active.eq(true))
+        .order_by_asc(reaction_types::Column::Sort)
+        .order_by_asc(reaction_types::Column::Id)
+        .all(db)
+        .await?;
+
+    log::info!("Loaded {} reaction types.", types.len());
+
+    if REACTION_TYPES.set(Arc::new(types)).is_err() {
+        log::warn!("Reaction types were already loaded.");
+    }
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct ReactionFormData {
+    pub reaction: i32,
+}
+
+/// Returns true if the reaction type exists and is active.
+pub fn is_reaction_type(reaction_type_id: i32) -> bool {
+    get_reaction_types()
+        .iter()
+        .any(|reaction_type| reaction_type.id == reaction_type_id)
+}
+
+/// Reacts to UGC. Each user has one reaction per UGC.
+/// Reacting the same way twice removes the reaction. Returns the reaction which remains, if any.
+/// A new reaction notifies the author, so this is not to be called inside a transaction.
+/// Each step is a single statement, so concurrent reactions by the same user cannot conflict.
+pub async fn toggle_reaction<C>(
+    conn: &C,
+    ugc_id: i32,
+    user_id: i32,
+    author_id: Option<i32>,
+    reaction_type_id: i32,
+) -> Result<Option<i32>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    // Reacting the same way again removes the reaction.
+    let removed = ugc_reactions::Entity::delete_many()
+        .filter(ugc_reactions::Column::UgcId.eq(ugc_id))
+        .filter(ugc_reactions::Column::UserId.eq(user_id))
+        .filter(ugc_reactions::Column::ReactionTypeId.eq(reaction_type_id))
+        .exec(conn)
+        .await?;
+    if removed.rows_affected > 0 {
+        return Ok(None);
+    }
+
+    let now = Utc::now().naive_utc();
+    let inserted = conn
+        .execute(Statement::from_sql_and_values(
+            conn.get_database_backend(),
+            r#"INSERT INTO ugc_reactions (ugc_id, user_id, reaction_type_id, author_id, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (ugc_id, user_id) DO NOTHING"#,
+            vec![
+                ugc_id.into(),
+                user_id.into(),
+                reaction_type_id.into(),
+                author_id.into(),
+                now.into(),
+            ],
+        ))
+        .await?;
+
+    if inserted.rows_affected() > 0 {
+        let notifications = notify_reaction(conn, ugc_id, author_id, user_id).await?;
+        push_notifications(conn, &notifications).await?;
+        return Ok(Some(reaction_type_id));
+    }
+
+    // A different reaction exists and is replaced.
+    ugc_reactions::Entity::update_many()
+        .col_expr(
+            ugc_reactions::Column::ReactionTypeId,
+            Expr::value(reaction_type_id),
+        )
+        .col_expr(ugc_reactions::Column::CreatedAt, Expr::value(now))
+        .filter(ugc_reactions::Column::UgcId.eq(ugc_id))
+        .filter(ugc_reactions::Column::UserId.eq(user_id))
+        .filter(ugc_reactions::Column::ReactionTypeId.ne(reaction_type_id))
+        .exec(conn)
+        .await?;
+
+    Ok(Some(reaction_type_id))
+}
+
+/// Returns how many reactions a user has received on their content.
+pub async fn get_received_reaction_count<C>(conn: &C, user_id: i32) -> Result<usize, DbErr>
+where
+    C: ConnectionTrait,
+{
+    ugc_reactions::Entity::find()
+        .filter(ugc_reactions::Column::AuthorId.eq(user_id))
+        .count(conn)
+        .await
+}
+
+/// Reaction totals for a set of UGC, and which reactions the client chose.
+#[derive(Default)]
+pub struct UgcReactions {
+    types: Arc<Vec<reaction_types::Model>>,
+    /// Totals by ugc_id, then by reaction_type_id.
+    counts: HashMap<i32, HashMap<i32, i64>>,
+    /// The client's reaction by ugc_id.
+    chosen: HashMap<i32, i32>,
+}
+
+impl UgcReactions {
+    pub async fn load<C>(conn: &C, ugc_ids: Vec<i32>, user_id: Option<i32>) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        #[derive(Debug, FromQueryResult)]
+        struct ReactionCount {
+            ugc_id: i32,
+            reaction_type_id: i32,
+            count: i64,
+        }
+
+        let mut reactions = Self {
+            types: get_reaction_types(),
+            ..Default::default()
+        };
+
+        if ugc_ids.is_empty() {
+            return Ok(reactions);
+        }
+
+        let counts = ugc_reactions::Entity::find()
+            .select_only()
+            .column(ugc_reactions::Column::UgcId)
+            .column(ugc_reactions::Column::ReactionTypeId)
+            .column_as(ugc_reactions::Column::UserId.count(), "count")
+            .filter(ugc_reactions::Column::UgcId.is_in(ugc_ids.to_owned()))
+            .group_by(ugc_reactions::Column::UgcId)
+            .group_by(ugc_reactions::Column::ReactionTypeId)
+            .into_model::<ReactionCount>()
+            .all(conn)
+            .await?;
+
+        for count in counts {
+            reactions
+                .counts
+                .entry(count.ugc_id)
+                .or_default()
+                .insert(count.reaction_type_id, count.count);
+        }
+
+        if let Some(user_id) = user_id {
+            reactions.chosen = ugc_reactions::Entity::find()
+                .filter(ugc_reactions::Column::UgcId.is_in(ugc_ids))
+                .filter(ugc_reactions::Column::UserId.eq(user_id))
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|reaction| (reaction.ugc_id, reaction.reaction_type_id))
+                .collect();
+        }
+
+        Ok(reactions)
+    }
+
+    /// Returns every reaction type which may be chosen.
+    pub fn get_types(&self) -> &[reaction_types::Model] {
+        &self.types
+    }
+
+    /// Returns each reaction type used on this UGC with its total, in order.
+    pub fn get_summary(&self, ugc_id: &i32) -> Vec<(&reaction_types::Model, i64)> {
+        match self.counts.get(ugc_id) {
+            Some(counts) => self
+                .types
+                .iter()
+                .filter_map(|reaction_type| {
+                    counts
+                        .get(&reaction_type.id)
+                        .map(|count| (reaction_type, *count))
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns true if the client reacted to this UGC with this reaction type.
+    pub fn is_chosen(&self, ugc_id: &i32, reaction_type_id: &i32) -> bool {
+        self.chosen.get(ugc_id) == Some(reaction_type_id)
+    }
+}
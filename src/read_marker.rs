@@ -0,0 +1,220 @@
+use crate::orm::{forum_reads, thread_reads};
+use chrono::{prelude::Utc, NaiveDateTime};
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, FromQueryResult, Statement};
+use std::collections::{HashMap, HashSet};
+
+/// Returns the time before which everything is considered read.
+pub fn get_read_cutoff() -> NaiveDateTime {
+    Utc::now().naive_utc() - crate::global::get_read_marker_age()
+}
+
+/// Marks a thread as read up to a point. Markers never move backwards.
+pub async fn mark_thread_read<C>(
+    conn: &C,
+    user_id: i32,
+    thread_id: i32,
+    read_at: NaiveDateTime,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    conn.execute(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        r#"INSERT INTO thread_reads (user_id, thread_id, read_at) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, thread_id)
+        DO UPDATE SET read_at = GREATEST(thread_reads.read_at, EXCLUDED.read_at)"#,
+        vec![user_id.into(), thread_id.into(), read_at.into()],
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Marks every thread in a forum as read up to a point. Markers never move backwards.
+pub async fn mark_forum_read<C>(
+    conn: &C,
+    user_id: i32,
+    forum_id: i32,
+    read_at: NaiveDateTime,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    conn.execute(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        r#"INSERT INTO forum_reads (user_id, forum_id, read_at) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, forum_id)
+        DO UPDATE SET read_at = GREATEST(forum_reads.read_at, EXCLUDED.read_at)"#,
+        vec![user_id.into(), forum_id.into(), read_at.into()],
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Returns when a user last read a thread, accounting for forum markers and the cutoff.
+pub async fn get_thread_read_at<C>(
+    conn: &C,
+    user_id: i32,
+    thread_id: i32,
+    forum_id: i32,
+) -> Result<NaiveDateTime, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let thread_read = thread_reads::Entity::find_by_id((user_id, thread_id)).one(conn);
+    let forum_read = forum_reads::Entity::find_by_id((user_id, forum_id)).one(conn);
+    let (thread_read, forum_read) = futures::try_join!(thread_read, forum_read)?;
+
+    Ok([
+        thread_read.map(|read| read.read_at),
+        forum_read.map(|read| read.read_at),
+    ]
+    .into_iter()
+    .flatten()
+    .fold(get_read_cutoff(), std::cmp::max))
+}
+
+/// Returns which of these forums have a visible thread the user has not read.
+pub async fn get_unread_forum_ids<C>(
+    conn: &C,
+    user_id: i32,
+    forum_ids: &[i32],
+) -> Result<HashSet<i32>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    #[derive(Debug, FromQueryResult)]
+    struct UnreadForum {
+        forum_id: i32,
+    }
+
+    if forum_ids.is_empty() {
+        return Ok(Default::default());
+    }
+
+    // Forum ids follow the user id and cutoff.
+    let placeholders = (0..forum_ids.len())
+        .map(|i| format!("${}", i + 3))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut values = vec![user_id.into(), get_read_cutoff().into()];
+    values.extend(forum_ids.iter().map(|id| (*id).into()));
+
+    Ok(UnreadForum::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        &format!(
+            r#"SELECT DISTINCT threads.forum_id
+        FROM threads
+        INNER JOIN posts ON posts.id = threads.first_post_id
+        INNER JOIN ugc ON ugc.id = posts.ugc_id
+        LEFT JOIN thread_reads ON thread_reads.thread_id = threads.id AND thread_reads.user_id = $1
+        LEFT JOIN forum_reads ON forum_reads.forum_id = threads.forum_id AND forum_reads.user_id = $1
+        WHERE threads.forum_id IN ({})
+            AND threads.last_post_at > $2
+            AND ugc.content_status = 'visible'
+            AND (thread_reads.read_at IS NULL OR thread_reads.read_at < threads.last_post_at)
+            AND (forum_reads.read_at IS NULL OR forum_reads.read_at < threads.last_post_at)"#,
+            placeholders
+        ),
+        values,
+    ))
+    .all(conn)
+    .await?
+    .into_iter()
+    .map(|forum| forum.forum_id)
+    .collect())
+}
+
+/// Deletes read markers older than the cutoff, which no longer affect what is unread.
+/// Returns how many markers were deleted.
+pub async fn prune_read_markers<C>(conn: &C) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let cutoff = get_read_cutoff();
+    let threads = thread_reads::Entity::delete_many()
+        .filter(thread_reads::Column::ReadAt.lt(cutoff))
+        .exec(conn)
+        .await?;
+    let forums = forum_reads::Entity::delete_many()
+        .filter(forum_reads::Column::ReadAt.lt(cutoff))
+        .exec(conn)
+        .await?;
+
+    Ok(threads.rows_affected + forums.rows_affected)
+}
+
+/// Read markers for a user and a set of threads. Guests have no markers and nothing is unread.
+#[derive(Default)]
+pub struct ReadMarkers {
+    /// None for guests.
+    cutoff: Option<NaiveDateTime>,
+    /// Read time by thread_id.
+    threads: HashMap<i32, NaiveDateTime>,
+    /// Read time by forum_id.
+    forums: HashMap<i32, NaiveDateTime>,
+}
+
+impl ReadMarkers {
+    pub async fn load<C>(
+        conn: &C,
+        user_id: Option<i32>,
+        thread_ids: Vec<i32>,
+    ) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => return Ok(Self::default()),
+        };
+
+        let mut markers = Self {
+            cutoff: Some(get_read_cutoff()),
+            ..Default::default()
+        };
+
+        if !thread_ids.is_empty() {
+            markers.threads = thread_reads::Entity::find()
+                .filter(thread_reads::Column::UserId.eq(user_id))
+                .filter(thread_reads::Column::ThreadId.is_in(thread_ids))
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|read| (read.thread_id, read.read_at))
+                .collect();
+        }
+
+        markers.forums = forum_reads::Entity::find()
+            .filter(forum_reads::Column::UserId.eq(user_id))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|read| (read.forum_id, read.read_at))
+            .collect();
+
+        Ok(markers)
+    }
+
+    /// Returns true if the thread has a post newer than the user's markers.
+    pub fn is_thread_unread(
+        &self,
+        thread_id: &i32,
+        forum_id: &i32,
+        last_post_at: &NaiveDateTime,
+    ) -> bool {
+        let is_read_by = |read_at: Option<&NaiveDateTime>| {
+            read_at.is_some_and(|read_at| read_at >= last_post_at)
+        };
+
+        match self.cutoff {
+            Some(cutoff) => {
+                *last_post_at > cutoff
+                    && !is_read_by(self.threads.get(thread_id))
+                    && !is_read_by(self.forums.get(forum_id))
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use super::{get_object_key, Storage, StorageError, StorageObject};
+use chrono::{DateTime, Utc};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+/// Stores objects in a directory on the disk with the same layout as the s3 bucket.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: String) -> LocalStorage {
+        log::info!("Initializing local storage in {}.", root);
+
+        let root = PathBuf::from(root);
+        if !root.exists() {
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .create(&root)
+                .expect("failed to create DIR_STORAGE");
+        }
+
+        LocalStorage { root }
+    }
+
+    fn get_path(&self, filename: &str) -> Result<PathBuf, StorageError> {
+        Ok(self.root.join(get_object_key(filename)?))
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for LocalStorage {
+    async fn exists(&self, filename: &str) -> Result<bool, StorageError> {
+        match tokio::fs::metadata(self.get_path(filename)?).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn get_object(
+        &self,
+        filename: &str,
+        range: Option<String>,
+    ) -> Result<StorageObject, StorageError> {
+        let path = self.get_path(filename)?;
+        let mut file = File::open(&path).await?;
+        let metadata = file.metadata().await?;
+        let length = metadata.len();
+
+        let (start, end) = match range {
+            Some(range) => match parse_range(&range, length)? {
+                Some(bounds) => bounds,
+                None => (0, length.saturating_sub(1)),
+            },
+            None => (0, length.saturating_sub(1)),
+        };
+        let content_length = if length == 0 { 0 } else { end - start + 1 };
+        let content_range = if content_length < length {
+            Some(format!("bytes {}-{}/{}", start, end, length))
+        } else {
+            None
+        };
+
+        if start > 0 {
+            file.seek(SeekFrom::Start(start)).await?;
+        }
+
+        Ok(StorageObject {
+            body: Box::pin(ReaderStream::new(file.take(content_length))),
+            content_length: Some(content_length as i64),
+            content_type: Path::new(filename)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| actix_files::file_extension_to_mime(ext).to_string()),
+            // Filenames are content hashes.
+            e_tag: Some(format!("\"{}\"", filename)),
+            content_range,
+            accept_ranges: Some("bytes".to_owned()),
+            last_modified: metadata.modified().ok().map(|modified| {
+                DateTime::<Utc>::from(modified)
+                    .format("%a, %d %b %Y %H:%M:%S GMT")
+                    .to_string()
+            }),
+        })
+    }
+
+    async fn put_object(&self, data: Vec<u8>, filename: &str) -> Result<(), StorageError> {
+        log::info!("LocalStorage: put_object: {}", filename);
+
+        let path = self.get_path(filename)?;
+        let dir = path
+            .parent()
+            .ok_or_else(|| StorageError::InvalidKey(filename.to_owned()))?;
+        tokio::fs::create_dir_all(dir).await?;
+
+        // Write beside the object and move it into place so it is never read half written.
+        let part_path = dir.join(format!(".{}.part", Uuid::new_v4()));
+        let mut file = File::create(&part_path).await?;
+        if let Err(e) = file.write_all(&data).await {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            return Err(e.into());
+        }
+        file.sync_all().await?;
+        tokio::fs::rename(&part_path, &path).await?;
+
+        Ok(())
+    }
+
+    async fn put_file(&self, source: &Path, filename: &str) -> Result<(), StorageError> {
+        log::info!("LocalStorage: put_file: {}", filename);
+
+        let path = self.get_path(filename)?;
+        let dir = path
+            .parent()
+            .ok_or_else(|| StorageError::InvalidKey(filename.to_owned()))?;
+        tokio::fs::create_dir_all(dir).await?;
+
+        let part_path = dir.join(format!(".{}.part", Uuid::new_v4()));
+        if let Err(e) = tokio::fs::copy(source, &part_path).await {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            return Err(e.into());
+        }
+        tokio::fs::rename(&part_path, &path).await?;
+
+        Ok(())
+    }
+}
+
+/// Returns the inclusive byte bounds of a Range header value for an object of this length.
+/// Values which are malformed or have several ranges are ignored and the whole object is sent.
+fn parse_range(range: &str, length: u64) -> Result<Option<(u64, u64)>, StorageError> {
+    let range = match range.trim().strip_prefix("bytes=") {
+        Some(range) if !range.contains(',') => range,
+        _ => return Ok(None),
+    };
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.trim(), end.trim()),
+        None => return Ok(None),
+    };
+
+    // A suffix range is the last bytes of the object.
+    if start.is_empty() {
+        return match end.parse::<u64>() {
+            Ok(0) => Err(StorageError::InvalidRange),
+            Ok(_) if length == 0 => Err(StorageError::InvalidRange),
+            Ok(suffix) => Ok(Some((length.saturating_sub(suffix), length - 1))),
+            Err(_) => Ok(None),
+        };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(start) => start,
+        Err(_) => return Ok(None),
+    };
+    let end = if end.is_empty() {
+        None
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => Some(end),
+            _ => return Ok(None),
+        }
+    };
+
+    if start >= length {
+        return Err(StorageError::InvalidRange);
+    }
+
+    Ok(Some((start, end.unwrap_or(u64::MAX).min(length - 1))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range() {
+        assert_eq!(parse_range("bytes=0-99", 1000).unwrap(), Some((0, 99)));
+        assert_eq!(parse_range("bytes=500-", 1000).unwrap(), Some((500, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000).unwrap(), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-2000", 1000).unwrap(), Some((0, 999)));
+        assert_eq!(
+            parse_range("bytes=900-2000", 1000).unwrap(),
+            Some((900, 999))
+        );
+
+        // Ignored, the whole object is sent.
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000).unwrap(), None);
+        assert_eq!(parse_range("bytes=99-0", 1000).unwrap(), None);
+        assert_eq!(parse_range("lines=0-1", 1000).unwrap(), None);
+        assert_eq!(parse_range("bytes=a-b", 1000).unwrap(), None);
+
+        // Not satisfiable.
+        assert!(matches!(
+            parse_range("bytes=1000-", 1000),
+            Err(StorageError::InvalidRange)
+        ));
+        assert!(matches!(
+            parse_range("bytes=-0", 1000),
+            Err(StorageError::InvalidRange)
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+mod local;
+mod s3;
+
+pub use local::LocalStorage;
+pub use s3::S3Bucket;
+
+use actix_web::web::Bytes;
+use futures::Stream;
+use once_cell::sync::OnceCell;
+use std::fmt;
+use std::path::Path;
+use std::pin::Pin;
+
+static STORAGE: OnceCell<Box<dyn Storage>> = OnceCell::new();
+
+/// Returns the storage backend selected at startup.
+pub fn get_storage() -> &'static dyn Storage {
+    STORAGE
+        .get()
+        .expect("storage::init must be called before get_storage")
+        .as_ref()
+}
+
+/// MUST be called ONCE before using functions in this module.
+/// STORAGE_BACKEND selects 's3' (the default) or 'local'.
+pub fn init() {
+    let backend = std::env::var("STORAGE_BACKEND").unwrap_or_else(|_| "s3".to_owned());
+    let storage: Box<dyn Storage> = match backend.as_str() {
+        "local" => Box::new(LocalStorage::new(std::env::var("DIR_STORAGE").expect(
+            "missing DIR_STORAGE environment variable (hint: 'DIR_STORAGE=./storage')",
+        ))),
+        "s3" => Box::new(S3Bucket::new(
+            rusoto_core::Region::Custom {
+                name: std::env::var("AWS_REGION_NAME").expect(".env missing AWS_REGION_NAME"),
+                endpoint: std::env::var("AWS_API_ENDPOINT")
+                    .expect(".env missing AWS_API_ENDPOINT."),
+            },
+            std::env::var("AWS_BUCKET_NAME").expect(".env missing AWS_BUCKET_NAME."),
+            std::env::var("AWS_PUBLIC_URL").expect(".env missing AWS_PUBLIC_URL."),
+        )),
+        backend => panic!(
+            "unknown STORAGE_BACKEND '{}' (hint: 's3' or 'local')",
+            backend
+        ),
+    };
+
+    if STORAGE.set(storage).is_err() {
+        panic!("STORAGE");
+    }
+}
+
+/// Stores attachments by their canonical filename.
+#[async_trait::async_trait]
+pub trait Storage: Send + Sync {
+    /// Returns true if an object is already stored.
+    async fn exists(&self, filename: &str) -> Result<bool, StorageError>;
+    /// Reads an object, or only part of it if given the value of a Range header.
+    async fn get_object(
+        &self,
+        filename: &str,
+        range: Option<String>,
+    ) -> Result<StorageObject, StorageError>;
+    /// Stores an object, replacing any object with the same filename.
+    async fn put_object(&self, data: Vec<u8>, filename: &str) -> Result<(), StorageError>;
+    /// Stores a file from the disk as an object without reading all of it into memory.
+    async fn put_file(&self, path: &Path, filename: &str) -> Result<(), StorageError>;
+}
+
+pub type ObjectBody = Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>;
+
+/// An object read from storage and the headers it is served with.
+pub struct StorageObject {
+    pub body: ObjectBody,
+    pub content_length: Option<i64>,
+    pub content_type: Option<String>,
+    pub e_tag: Option<String>,
+    /// Only set if part of the object was read.
+    pub content_range: Option<String>,
+    pub accept_ranges: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    /// The filename cannot be used as a key.
+    InvalidKey(String),
+    /// The requested range does not overlap the object.
+    InvalidRange,
+    NotFound,
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidKey(filename) => write!(f, "'{}' is not a valid object key.", filename),
+            Self::InvalidRange => write!(f, "The requested range is not satisfiable."),
+            Self::NotFound => write!(f, "The object does not exist."),
+            Self::Backend(err) => write!(f, "Storage error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(err: std::io::Error) -> Self {
+        match err.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound,
+            _ => Self::Backend(err.to_string()),
+        }
+    }
+}
+
+/// Objects are kept under two prefixes taken from their filename, as in `ab/cd/abcdef.jpg`.
+pub fn get_object_key(filename: &str) -> Result<String, StorageError> {
+    if filename.len() < 4
+        || filename.starts_with('.')
+        || !filename
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.')
+    {
+        return Err(StorageError::InvalidKey(filename.to_owned()));
+    }
+
+    Ok(format!(
+        "{}/{}/{}",
+        &filename[0..2],
+        &filename[2..4],
+        filename
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_key() {
+        assert_eq!(
+            get_object_key("9e0834c0.jpg").unwrap(),
+            "9e/08/9e0834c0.jpg"
+        );
+        assert_eq!(get_object_key("9e0834c0").unwrap(), "9e/08/9e0834c0");
+        assert!(get_object_key("9e0").is_err());
+        assert!(get_object_key("../../etc/passwd").is_err());
+        assert!(get_object_key(".htaccess").is_err());
+    }
+}
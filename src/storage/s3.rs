@@ -0,0 +1,214 @@
+use super::{get_object_key, Storage, StorageError, StorageObject};
+use rusoto_core::Region;
+use rusoto_core::RusotoError;
+use rusoto_s3::{
+    AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload,
+    CompletedPart, CreateMultipartUploadRequest, GetObjectError, GetObjectRequest,
+    ListObjectsV2Request, PutObjectRequest, S3Client, UploadPartRequest, S3,
+};
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+/// Files larger than this are uploaded in parts of this size. Parts must be at least 5 MiB.
+const PART_SIZE: u64 = 8 * 1024 * 1024;
+
+pub struct S3Bucket {
+    s3: S3Client,
+    bucket_name: String,
+    pub pub_url: String,
+}
+
+impl S3Bucket {
+    pub fn new(region: Region, bucket_name: String, pub_url: String) -> S3Bucket {
+        log::info!("Initializing new S3 Bucket.");
+
+        S3Bucket {
+            s3: S3Client::new(region),
+            bucket_name,
+            pub_url,
+        }
+    }
+
+    /// Uploads the rest of a file as parts of a multipart upload.
+    async fn upload_parts(
+        &self,
+        file: &mut File,
+        key: &str,
+        upload_id: &str,
+    ) -> Result<Vec<CompletedPart>, StorageError> {
+        let mut parts = Vec::new();
+
+        loop {
+            let mut data = Vec::with_capacity(PART_SIZE as usize);
+            (&mut *file).take(PART_SIZE).read_to_end(&mut data).await?;
+            if data.is_empty() {
+                break;
+            }
+
+            let part_number = parts.len() as i64 + 1;
+            let res = self
+                .s3
+                .upload_part(UploadPartRequest {
+                    bucket: self.bucket_name.to_owned(),
+                    key: key.to_owned(),
+                    upload_id: upload_id.to_owned(),
+                    part_number,
+                    content_length: Some(data.len() as i64),
+                    body: Some(data.into()),
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+            parts.push(CompletedPart {
+                e_tag: res.e_tag,
+                part_number: Some(part_number),
+            });
+        }
+
+        Ok(parts)
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for S3Bucket {
+    async fn exists(&self, filename: &str) -> Result<bool, StorageError> {
+        log::info!("S3Bucket: list_objects_v2: {}", filename);
+
+        // dude claims list_objects_v2 is faster than head_object
+        // https://www.peterbe.com/plog/fastest-way-to-find-out-if-a-file-exists-in-s3
+        let list_request = ListObjectsV2Request {
+            bucket: self.bucket_name.to_owned(),
+            prefix: Some(get_object_key(filename)?),
+            ..Default::default()
+        };
+
+        let list = self
+            .s3
+            .list_objects_v2(list_request)
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        // key count should only ever be 0 or 1, otherwise there's something wrong with the prefix
+        Ok(list.key_count.unwrap_or(0) > 0)
+    }
+
+    async fn get_object(
+        &self,
+        filename: &str,
+        range: Option<String>,
+    ) -> Result<StorageObject, StorageError> {
+        let res = self
+            .s3
+            .get_object(GetObjectRequest {
+                bucket: self.bucket_name.to_owned(),
+                key: get_object_key(filename)?,
+                range,
+                ..Default::default()
+            })
+            .await
+            .map_err(|err| match err {
+                RusotoError::Service(GetObjectError::NoSuchKey(_)) => StorageError::NotFound,
+                RusotoError::Unknown(ref res) if res.status.as_u16() == 404 => {
+                    StorageError::NotFound
+                }
+                RusotoError::Unknown(ref res) if res.status.as_u16() == 416 => {
+                    StorageError::InvalidRange
+                }
+                err => StorageError::Backend(err.to_string()),
+            })?;
+
+        Ok(StorageObject {
+            body: Box::pin(
+                res.body
+                    .ok_or_else(|| StorageError::Backend("No body for response".to_owned()))?,
+            ),
+            content_length: res.content_length,
+            content_type: res.content_type,
+            e_tag: res.e_tag,
+            content_range: res.content_range,
+            accept_ranges: res.accept_ranges,
+            last_modified: res.last_modified,
+        })
+    }
+
+    async fn put_object(&self, data: Vec<u8>, filename: &str) -> Result<(), StorageError> {
+        log::info!("S3Bucket: put_object: {}", filename);
+
+        let put_request = PutObjectRequest {
+            bucket: self.bucket_name.to_owned(),
+            key: get_object_key(filename)?,
+            body: Some(data.into()),
+            ..Default::default()
+        };
+
+        self.s3
+            .put_object(put_request)
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn put_file(&self, path: &Path, filename: &str) -> Result<(), StorageError> {
+        let mut file = File::open(path).await?;
+        let length = file.metadata().await?.len();
+
+        // Small files are sent in one request.
+        if length <= PART_SIZE {
+            let mut data = Vec::with_capacity(length as usize);
+            file.read_to_end(&mut data).await?;
+            return self.put_object(data, filename).await;
+        }
+
+        log::info!("S3Bucket: create_multipart_upload: {}", filename);
+
+        let key = get_object_key(filename)?;
+        let upload_id = self
+            .s3
+            .create_multipart_upload(CreateMultipartUploadRequest {
+                bucket: self.bucket_name.to_owned(),
+                key: key.to_owned(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?
+            .upload_id
+            .ok_or_else(|| StorageError::Backend("No upload id for response".to_owned()))?;
+
+        let result = match self.upload_parts(&mut file, &key, &upload_id).await {
+            Ok(parts) => self
+                .s3
+                .complete_multipart_upload(CompleteMultipartUploadRequest {
+                    bucket: self.bucket_name.to_owned(),
+                    key: key.to_owned(),
+                    upload_id: upload_id.to_owned(),
+                    multipart_upload: Some(CompletedMultipartUpload { parts: Some(parts) }),
+                    ..Default::default()
+                })
+                .await
+                .map(|_| ())
+                .map_err(|e| StorageError::Backend(e.to_string())),
+            Err(e) => Err(e),
+        };
+
+        // Parts of a failed upload are kept and billed until the upload is aborted.
+        if result.is_err() {
+            if let Err(e) = self
+                .s3
+                .abort_multipart_upload(AbortMultipartUploadRequest {
+                    bucket: self.bucket_name.to_owned(),
+                    key,
+                    upload_id,
+                    ..Default::default()
+                })
+                .await
+            {
+                log::error!("S3Bucket: failed to abort_multipart_upload: {}", e);
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,157 @@
+use crate::orm::{tags, thread_tags};
+use chrono::prelude::Utc;
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr};
+use std::collections::HashMap;
+
+/// Maximum number of characters in a tag.
+pub const MAX_TAG_LENGTH: usize = 64;
+/// Maximum number of tags on a thread.
+pub const MAX_TAGS_PER_THREAD: usize = 10;
+
+/// Normalizes a single tag so it can be used as a URL segment.
+/// Tags are lowercase, and whitespace, hyphens and underscores become a single hyphen.
+pub fn normalize_tag(input: &str) -> Option<String> {
+    let mut tag = String::with_capacity(input.len());
+
+    for c in input.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            tag.push(c);
+        } else if (c.is_whitespace() || c == '-' || c == '_')
+            && !tag.is_empty()
+            && !tag.ends_with('-')
+        {
+            tag.push('-');
+        }
+    }
+
+    let tag: String = tag.chars().take(MAX_TAG_LENGTH).collect();
+    let tag = tag.trim_end_matches('-');
+
+    if tag.is_empty() {
+        None
+    } else {
+        Some(tag.to_owned())
+    }
+}
+
+/// Splits comma separated user input into unique, normalized tags.
+pub fn normalize_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for tag in input.split(',').filter_map(normalize_tag) {
+        if tags.len() >= MAX_TAGS_PER_THREAD {
+            break;
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+/// Replaces the tags on a thread, creating any tags which do not exist yet.
+pub async fn set_thread_tags<C>(conn: &C, thread_id: i32, names: &[String]) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    thread_tags::Entity::delete_many()
+        .filter(thread_tags::Column::ThreadId.eq(thread_id))
+        .exec(conn)
+        .await?;
+
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    let mut tag_ids: Vec<i32> = Vec::with_capacity(names.len());
+    let existing = tags::Entity::find()
+        .filter(tags::Column::Name.is_in(names.to_owned()))
+        .all(conn)
+        .await?;
+
+    for name in names {
+        match existing.iter().find(|tag| &tag.name == name) {
+            Some(tag) => tag_ids.push(tag.id),
+            None => {
+                let tag = tags::ActiveModel {
+                    name: Set(name.to_owned()),
+                    created_at: Set(Utc::now().naive_utc()),
+                    ..Default::default()
+                }
+                .insert(conn)
+                .await?;
+                tag_ids.push(tag.id);
+            }
+        }
+    }
+
+    thread_tags::Entity::insert_many(tag_ids.into_iter().map(|tag_id| thread_tags::ActiveModel {
+        thread_id: Set(thread_id),
+        tag_id: Set(tag_id),
+    }))
+    .exec(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Returns the tags of each thread by thread_id, in name order.
+pub async fn get_tags_for_threads<C>(
+    conn: &C,
+    thread_ids: Vec<i32>,
+) -> Result<HashMap<i32, Vec<tags::Model>>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut result: HashMap<i32, Vec<tags::Model>> = HashMap::new();
+
+    if thread_ids.is_empty() {
+        return Ok(result);
+    }
+
+    let rows = thread_tags::Entity::find()
+        .find_also_related(tags::Entity)
+        .filter(thread_tags::Column::ThreadId.is_in(thread_ids))
+        .order_by_asc(tags::Column::Name)
+        .all(conn)
+        .await?;
+
+    for (thread_tag, tag) in rows {
+        if let Some(tag) = tag {
+            result.entry(thread_tag.thread_id).or_default().push(tag);
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_tag, normalize_tags, MAX_TAGS_PER_THREAD, MAX_TAG_LENGTH};
+
+    #[test]
+    fn normalize_tag_for_url() {
+        assert_eq!(normalize_tag("  Rust Lang "), Some("rust-lang".to_owned()));
+        assert_eq!(normalize_tag("c++ / _ tips__"), Some("c-tips".to_owned()));
+        assert_eq!(normalize_tag(" -- "), None);
+        assert_eq!(
+            normalize_tag(&"a".repeat(MAX_TAG_LENGTH * 2)).map(|tag| tag.len()),
+            Some(MAX_TAG_LENGTH)
+        );
+    }
+
+    #[test]
+    fn normalize_tags_deduplicates() {
+        assert_eq!(
+            normalize_tags("Rust, rust ,,web dev, Web-Dev"),
+            vec!["rust".to_owned(), "web-dev".to_owned()]
+        );
+
+        let many = (0..MAX_TAGS_PER_THREAD * 2)
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+        assert_eq!(normalize_tags(&many).len(), MAX_TAGS_PER_THREAD);
+    }
+}
@@ -1,6 +1,5 @@
 use crate::middleware::ClientCtx;
 use askama_actix::Template;
-use std::ops::Range;
 
 const PAGINATOR_LOOK_AHEAD: i32 = 2;
 
@@ -14,6 +13,8 @@ const PAGINATOR_LOOK_AHEAD: i32 = 2;
 #[derive(Debug)]
 pub struct Paginator {
     pub base_url: String,
+    /// Query string carried into each page link, including the leading `?`, or empty.
+    pub query: String,
     pub this_page: i32,
     pub page_count: i32,
 }
@@ -27,9 +28,9 @@ struct PaginatorTemplate<'a> {
 pub trait PaginatorToHtml {
     fn as_html(&self) -> String;
     fn has_pages(&self) -> bool;
-    fn get_first_pages(&self) -> Range<i32>;
-    fn get_inner_pages(&self) -> Option<Range<i32>>;
-    fn get_last_pages(&self) -> Option<Range<i32>>;
+    fn get_pages(&self) -> Vec<Option<i32>>;
+    fn get_url_for_page(&self, page: &i32) -> String;
+    fn is_this_page(&self, page: &i32) -> bool;
 }
 
 impl PaginatorToHtml for Paginator {
@@ -37,49 +38,45 @@ impl PaginatorToHtml for Paginator {
         self.page_count > 1
     }
 
-    fn get_first_pages(&self) -> Range<i32> {
-        if 1 + PAGINATOR_LOOK_AHEAD < self.this_page - PAGINATOR_LOOK_AHEAD {
-            // if 1+lookahead is less than page-lookahead, we only show page 1
-            // i.e. any page starting with 6
-            1..1
-        } else if self.this_page + PAGINATOR_LOOK_AHEAD < self.page_count - PAGINATOR_LOOK_AHEAD {
-            // if our lookahead is less than the lookbehind of the last page, show up to our lookahead.
-            // i.e. on page 4 of 9, show 1-6 ... 9
-            1..(self.this_page + PAGINATOR_LOOK_AHEAD)
-        } else {
-            // otherwise, just show all pages.
-            // i.e. 5 of 9 is the greatest extent possible
-            1..self.page_count
+    /// Returns the pages to link in order. None is a gap between pages.
+    fn get_pages(&self) -> Vec<Option<i32>> {
+        let mut start = std::cmp::max(2, self.this_page - PAGINATOR_LOOK_AHEAD);
+        let mut end = std::cmp::min(self.page_count - 1, self.this_page + PAGINATOR_LOOK_AHEAD);
+
+        // A gap hiding a single page is replaced by that page.
+        if start == 3 {
+            start = 2;
+        }
+        if end == self.page_count - 2 {
+            end = self.page_count - 1;
         }
-    }
 
-    fn get_inner_pages(&self) -> Option<Range<i32>> {
-        // if our lookahead is gt/eq the lookbehind of the last page, we merge our cursor to the last pages
-        if (1 + PAGINATOR_LOOK_AHEAD >= self.this_page - PAGINATOR_LOOK_AHEAD) ||
-            // if 1+lookahead is less than page-lookahead, we only have first pages
-            (self.this_page + PAGINATOR_LOOK_AHEAD >= self.page_count - PAGINATOR_LOOK_AHEAD)
-        {
-            None
-        } else {
-            // otherwise, show the lookahead and look behind
-            // i.e. 1 .. 4 5 [6] 7 8 .. 11 (minimum number)
-            Some((self.this_page - PAGINATOR_LOOK_AHEAD)..(self.this_page + PAGINATOR_LOOK_AHEAD))
+        let mut pages = vec![Some(1)];
+        if start > 2 {
+            pages.push(None);
         }
+        pages.extend((start..=end).map(Some));
+        if end < self.page_count - 1 {
+            pages.push(None);
+        }
+        if self.page_count > 1 {
+            pages.push(Some(self.page_count));
+        }
+        pages
     }
 
-    fn get_last_pages(&self) -> Option<Range<i32>> {
-        if 1 + PAGINATOR_LOOK_AHEAD >= self.this_page - PAGINATOR_LOOK_AHEAD {
-            // if 1+lookahead is less than page-lookahead, we only have first pages
-            None
-        } else if self.this_page + PAGINATOR_LOOK_AHEAD < self.page_count - PAGINATOR_LOOK_AHEAD {
-            // if our lookahead is less than the lookbehind of the last page, show the last page
-            Some(self.page_count..self.page_count)
+    fn get_url_for_page(&self, page: &i32) -> String {
+        if *page > 1 {
+            format!("{}page-{}{}", self.base_url, page, self.query)
         } else {
-            // otherwise, show from the lookbehind of the cursor to the last page.
-            Some((self.this_page - PAGINATOR_LOOK_AHEAD)..self.page_count)
+            format!("{}{}", self.base_url, self.query)
         }
     }
 
+    fn is_this_page(&self, page: &i32) -> bool {
+        self.this_page == *page
+    }
+
     fn as_html(&self) -> String {
         if self.has_pages() {
             let mut buffer = String::new();
@@ -102,3 +99,76 @@ pub struct CreateUserTemplate<'a> {
     pub logged_in: bool,
     pub username: Option<&'a str>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Paginator, PaginatorToHtml};
+
+    fn get_pages(this_page: i32, page_count: i32) -> Vec<Option<i32>> {
+        Paginator {
+            base_url: "/".to_owned(),
+            query: String::new(),
+            this_page,
+            page_count,
+        }
+        .get_pages()
+    }
+
+    #[test]
+    fn paginator_pages() {
+        assert_eq!(get_pages(1, 1), vec![Some(1)]);
+        assert_eq!(get_pages(1, 2), vec![Some(1), Some(2)]);
+        assert_eq!(
+            get_pages(1, 13),
+            vec![Some(1), Some(2), Some(3), None, Some(13)]
+        );
+        assert_eq!(
+            get_pages(5, 13),
+            vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7),
+                None,
+                Some(13)
+            ]
+        );
+        assert_eq!(
+            get_pages(6, 13),
+            vec![
+                Some(1),
+                None,
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(7),
+                Some(8),
+                None,
+                Some(13)
+            ]
+        );
+        assert_eq!(
+            get_pages(13, 13),
+            vec![Some(1), None, Some(11), Some(12), Some(13)]
+        );
+    }
+
+    #[test]
+    fn paginator_urls() {
+        let paginator = Paginator {
+            base_url: "/forums/1/".to_owned(),
+            query: "?sort=title".to_owned(),
+            this_page: 1,
+            page_count: 3,
+        };
+
+        assert_eq!(paginator.get_url_for_page(&1), "/forums/1/?sort=title");
+        assert_eq!(
+            paginator.get_url_for_page(&3),
+            "/forums/1/page-3?sort=title"
+        );
+    }
+}
@@ -1,9 +1,37 @@
-use crate::orm::{ugc, ugc_revisions};
+use crate::attachment::{get_attachments_for_ugc_by_id, get_bbcode_attachments};
+use crate::bbcode::{get_mentions, parse_with_constructor, Attachments, Constructor, Mentions};
+use crate::mention::{get_mentioned_users, record_mentions};
+use crate::notification::NewNotification;
+use crate::orm::{
+    posts, ugc, ugc_attachments, ugc_deletions, ugc_revision_html, ugc_revision_html_mentions,
+    ugc_revisions,
+};
 use actix_web::{error, Error};
 use chrono::prelude::Utc;
-use sea_orm::sea_query::Expr;
-use sea_orm::ConnectionTrait;
+use sea_orm::entity::prelude::{DeriveActiveEnum, EnumIter};
+use sea_orm::sea_query::{OnConflict, Query};
 use sea_orm::{entity::*, query::*, Set};
+use sea_orm::{ConnectionTrait, DbErr, FromQueryResult, TransactionTrait};
+use std::collections::HashMap;
+
+/// Number of revisions rendered at once when rebuilding the HTML cache.
+const REBUILD_BATCH_SIZE: u64 = 500;
+
+/// Who may see UGC.
+/// Compatible with sea_orm enum type.
+#[derive(Debug, Clone, Copy, PartialEq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "content_status")]
+pub enum ContentStatus {
+    /// Seen by everyone.
+    #[sea_orm(string_value = "visible")]
+    Visible,
+    /// Rejected from the moderation queue. Seen only by moderators.
+    #[sea_orm(string_value = "hidden")]
+    Hidden,
+    /// Awaiting approval. Seen only by moderators and the author.
+    #[sea_orm(string_value = "in_queue")]
+    InQueue,
+}
 
 /// Contains only the UGC we can get from a form submission.
 pub struct NewUgcPartial<'a> {
@@ -12,17 +40,40 @@ pub struct NewUgcPartial<'a> {
     pub content: &'a str,
 }
 
+/// UGC revision content which is to be rendered.
+pub struct UgcForRender<'a> {
+    pub ugc_revision_id: i32,
+    pub content: &'a str,
+    pub attachments: Attachments,
+}
+
+/// Rendered HTML of UGC revisions, keyed by revision id.
+#[derive(Default)]
+pub struct RenderedUgc(HashMap<i32, String>);
+
+impl RenderedUgc {
+    /// Returns the HTML of a revision, if it was rendered.
+    pub fn get(&self, ugc_revision_id: &Option<i32>) -> Option<&str> {
+        ugc_revision_id
+            .and_then(|id| self.0.get(&id))
+            .map(String::as_str)
+    }
+}
+
 /// Creates a new UGC and an accompanying first revision.
+/// Returns the revision and the notifications it created, which are pushed once committed.
 pub async fn create_ugc<'a, C>(
     pool: &'a C,
     revision: NewUgcPartial<'a>,
-) -> Result<ugc_revisions::Model, Error>
+    content_status: ContentStatus,
+) -> Result<(ugc_revisions::Model, Vec<NewNotification>), Error>
 where
     C: ConnectionTrait,
 {
     // Insert new UGC reference with only default values.
     let new_ugc = ugc::ActiveModel {
         ugc_revision_id: Set(None),
+        content_status: Set(content_status),
         ..Default::default()
     }
     .insert(pool)
@@ -33,11 +84,12 @@ where
 }
 
 /// Creates a new UGC revision and sets it as the living revision for the UGC it belongs to.
+/// Returns the revision and the notifications it created, which are pushed once committed.
 pub async fn create_ugc_revision<'a, C>(
     conn: &'a C,
     ugc_id: i32,
     revision: NewUgcPartial<'a>,
-) -> Result<ugc_revisions::Model, Error>
+) -> Result<(ugc_revisions::Model, Vec<NewNotification>), Error>
 where
     C: ConnectionTrait,
 {
@@ -57,14 +109,103 @@ where
     .await
     .map_err(error::ErrorInternalServerError)?;
 
-    ugc::Entity::update_many()
-        .col_expr(ugc::Column::UgcRevisionId, Expr::value(new_revision.id))
-        .filter(ugc::Column::Id.eq(ugc_id))
-        .exec(conn)
+    let ugc = ugc::ActiveModel {
+        id: Unchanged(ugc_id),
+        ugc_revision_id: Set(Some(new_revision.id)),
+        ..Default::default()
+    }
+    .update(conn)
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    // Content awaiting approval notifies nobody until it is approved.
+    let notifications = if ugc.content_status == ContentStatus::Visible {
+        record_mentions(conn, ugc_id, revision.user_id, &new_revision.content)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+    } else {
+        Vec::new()
+    };
+
+    Ok((new_revision, notifications))
+}
+
+/// Creates a new living revision with the content of an earlier one.
+/// The new revision belongs to whoever reverted it and remembers which revision it copied.
+/// Both are written in one transaction.
+/// Returns the revision and the notifications it created, which are pushed once committed.
+pub async fn revert_ugc_revision<C>(
+    conn: &C,
+    revision: &ugc_revisions::Model,
+    user_id: Option<i32>,
+) -> Result<(ugc_revisions::Model, Vec<NewNotification>), Error>
+where
+    C: TransactionTrait,
+{
+    let txn = conn
+        .begin()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let (new_revision, notifications) = create_ugc_revision(
+        &txn,
+        revision.ugc_id,
+        NewUgcPartial {
+            ip_id: None,
+            user_id,
+            content: &revision.content,
+        },
+    )
+    .await?;
+
+    let new_revision = ugc_revisions::ActiveModel {
+        id: Unchanged(new_revision.id),
+        reverted_from_id: Set(Some(revision.id)),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    txn.commit()
         .await
         .map_err(error::ErrorInternalServerError)?;
 
-    Ok(new_revision)
+    Ok((new_revision, notifications))
+}
+
+/// Permanently deletes UGC with its revisions, attachment associations and deletion record.
+/// Whatever owns the UGC, such as a post, must be deleted first.
+pub async fn delete_ugc<C>(conn: &C, ugc_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    ugc_attachments::Entity::delete_many()
+        .filter(ugc_attachments::Column::UgcId.eq(ugc_id))
+        .exec(conn)
+        .await?;
+
+    ugc_deletions::Entity::delete_by_id(ugc_id)
+        .exec(conn)
+        .await?;
+
+    // Revisions and UGC reference each other.
+    ugc::ActiveModel {
+        id: Unchanged(ugc_id),
+        ugc_revision_id: Set(None),
+        ..Default::default()
+    }
+    .update(conn)
+    .await?;
+
+    ugc_revisions::Entity::delete_many()
+        .filter(ugc_revisions::Column::UgcId.eq(ugc_id))
+        .exec(conn)
+        .await?;
+
+    ugc::Entity::delete_by_id(ugc_id).exec(conn).await?;
+
+    Ok(())
 }
 
 fn validate_ugc(revision: NewUgcPartial) -> Result<NewUgcPartial, Error> {
@@ -83,3 +224,249 @@ fn validate_ugc(revision: NewUgcPartial) -> Result<NewUgcPartial, Error> {
         content: clean_content,
     })
 }
+
+/// Returns the HTML of UGC revisions.
+/// Cached HTML is used if it was built by a Constructor of the same version with the same attachments.
+/// Anything else is rendered and cached, so changing smilies or the renderer rebuilds it lazily.
+pub async fn get_rendered_ugc<'a, C>(
+    conn: &C,
+    constructor: &mut Constructor,
+    revisions: Vec<UgcForRender<'a>>,
+) -> Result<RenderedUgc, DbErr>
+where
+    C: ConnectionTrait,
+{
+    if revisions.is_empty() {
+        return Ok(RenderedUgc::default());
+    }
+
+    let version = constructor.get_version();
+    let mut cached: HashMap<i32, ugc_revision_html::Model> = ugc_revision_html::Entity::find()
+        .filter(
+            ugc_revision_html::Column::UgcRevisionId
+                .is_in(revisions.iter().map(|r| r.ugc_revision_id)),
+        )
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|model| (model.ugc_revision_id, model))
+        .collect();
+
+    let mut rendered = HashMap::with_capacity(revisions.len());
+    let revisions = revisions
+        .into_iter()
+        .filter(|r| match cached.remove(&r.ugc_revision_id) {
+            Some(model) if model.version == r.attachments.get_version(&version) => {
+                rendered.insert(r.ugc_revision_id, model.html);
+                false
+            }
+            _ => true,
+        })
+        .collect();
+
+    rendered.extend(render_and_cache_ugc(conn, constructor, revisions).await?);
+
+    Ok(RenderedUgc(rendered))
+}
+
+/// Forgets the cached HTML of revisions which mention a name, so they are rendered again.
+/// Whoever a name refers to changes when a user registers with it.
+pub async fn forget_rendered_mentions<C>(conn: &C, name: &str) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    ugc_revision_html::Entity::delete_many()
+        .filter(
+            ugc_revision_html::Column::UgcRevisionId.in_subquery(
+                Query::select()
+                    .column(ugc_revision_html_mentions::Column::UgcRevisionId)
+                    .from(ugc_revision_html_mentions::Entity)
+                    .and_where(ugc_revision_html_mentions::Column::Name.eq(name.to_lowercase()))
+                    .to_owned(),
+            ),
+        )
+        .exec(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Renders the living revision of every post and replaces its cached HTML.
+/// If `stale_only` is set, HTML which is already current is skipped.
+/// Returns the number of revisions rendered.
+pub async fn rebuild_rendered_ugc<C>(
+    conn: &C,
+    constructor: &mut Constructor,
+    stale_only: bool,
+) -> Result<usize, DbErr>
+where
+    C: ConnectionTrait,
+{
+    #[derive(Debug, FromQueryResult)]
+    struct LivingRevision {
+        post_id: i32,
+        ugc_id: i32,
+        ugc_revision_id: i32,
+        content: String,
+    }
+
+    let version = constructor.get_version();
+    let mut last_post_id = 0;
+    let mut count = 0;
+
+    loop {
+        let batch = posts::Entity::find()
+            .select_only()
+            .column_as(posts::Column::Id, "post_id")
+            .column(posts::Column::UgcId)
+            .inner_join(ugc_revisions::Entity)
+            .column_as(ugc_revisions::Column::Id, "ugc_revision_id")
+            .column(ugc_revisions::Column::Content)
+            .filter(posts::Column::Id.gt(last_post_id))
+            .order_by_asc(posts::Column::Id)
+            .limit(REBUILD_BATCH_SIZE)
+            .into_model::<LivingRevision>()
+            .all(conn)
+            .await?;
+
+        last_post_id = match batch.last() {
+            Some(last) => last.post_id,
+            None => break,
+        };
+
+        let attachments =
+            get_attachments_for_ugc_by_id(batch.iter().map(|r| r.ugc_id).collect()).await;
+
+        let mut revisions: Vec<UgcForRender> = batch
+            .iter()
+            .map(|r| UgcForRender {
+                ugc_revision_id: r.ugc_revision_id,
+                content: &r.content,
+                attachments: attachments
+                    .get(&r.ugc_id)
+                    .map(|a| get_bbcode_attachments(a))
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        if stale_only {
+            let current: HashMap<i32, String> = ugc_revision_html::Entity::find()
+                .filter(
+                    ugc_revision_html::Column::UgcRevisionId
+                        .is_in(batch.iter().map(|r| r.ugc_revision_id)),
+                )
+                .all(conn)
+                .await?
+                .into_iter()
+                .map(|model| (model.ugc_revision_id, model.version))
+                .collect();
+            revisions.retain(|r| {
+                current.get(&r.ugc_revision_id) != Some(&r.attachments.get_version(&version))
+            });
+        }
+
+        count += render_and_cache_ugc(conn, constructor, revisions)
+            .await?
+            .len();
+    }
+
+    Ok(count)
+}
+
+/// Renders revisions and stores their HTML, replacing any older HTML.
+async fn render_and_cache_ugc<'a, C>(
+    conn: &C,
+    constructor: &mut Constructor,
+    revisions: Vec<UgcForRender<'a>>,
+) -> Result<HashMap<i32, String>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut rendered = HashMap::with_capacity(revisions.len());
+
+    if revisions.is_empty() {
+        return Ok(rendered);
+    }
+
+    let version = constructor.get_version();
+    let now = Utc::now().naive_utc();
+    let mut models = Vec::with_capacity(revisions.len());
+
+    // Mentions are resolved once for every revision, as they are not stored with the content.
+    let revision_names: Vec<Vec<String>> = revisions
+        .iter()
+        .map(|revision| {
+            let mut names = get_mentions(revision.content).names;
+            names.sort_unstable();
+            names.dedup();
+            names
+        })
+        .collect();
+    let mut names: Vec<String> = revision_names.iter().flatten().cloned().collect();
+    names.sort_unstable();
+    names.dedup();
+    constructor.mentions = get_mentioned_users(conn, &names).await?;
+
+    let revision_ids: Vec<i32> = revisions.iter().map(|r| r.ugc_revision_id).collect();
+    let mut mention_models = Vec::new();
+
+    for (revision, names) in revisions.into_iter().zip(revision_names) {
+        let revision_version = revision.attachments.get_version(&version);
+        constructor.attachments = revision.attachments;
+        let html = parse_with_constructor(revision.content, constructor);
+
+        models.push(ugc_revision_html::ActiveModel {
+            ugc_revision_id: Set(revision.ugc_revision_id),
+            version: Set(revision_version),
+            html: Set(html.to_owned()),
+            created_at: Set(now),
+        });
+        mention_models.extend(names.into_iter().map(|name| {
+            ugc_revision_html_mentions::ActiveModel {
+                ugc_revision_id: Set(revision.ugc_revision_id),
+                name: Set(name),
+            }
+        }));
+        rendered.insert(revision.ugc_revision_id, html);
+    }
+
+    constructor.attachments = Attachments::default();
+    constructor.mentions = Mentions::default();
+
+    let mut insert = ugc_revision_html::Entity::insert_many(models);
+    insert.query().on_conflict(
+        OnConflict::column(ugc_revision_html::Column::UgcRevisionId)
+            .update_columns([
+                ugc_revision_html::Column::Version,
+                ugc_revision_html::Column::Html,
+                ugc_revision_html::Column::CreatedAt,
+            ])
+            .to_owned(),
+    );
+    insert.exec(conn).await?;
+
+    // The names each revision mentions are kept, so its HTML is forgotten when one is taken.
+    ugc_revision_html_mentions::Entity::delete_many()
+        .filter(ugc_revision_html_mentions::Column::UgcRevisionId.is_in(revision_ids))
+        .exec(conn)
+        .await?;
+
+    if !mention_models.is_empty() {
+        // Executed as a statement, as an insert which skips every row returns nothing to unpack.
+        let insert = ugc_revision_html_mentions::Entity::insert_many(mention_models)
+            .into_query()
+            .on_conflict(
+                OnConflict::columns([
+                    ugc_revision_html_mentions::Column::UgcRevisionId,
+                    ugc_revision_html_mentions::Column::Name,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .to_owned();
+        conn.execute(conn.get_database_backend().build(&insert))
+            .await?;
+    }
+
+    Ok(rendered)
+}
@@ -0,0 +1,720 @@
+use crate::db::get_db_pool;
+use crate::filesystem::{
+    deduplicate_payload, get_dir_tmp, insert_payload_as_attachment, strip_temp_file_into,
+    UploadPayload,
+};
+use crate::middleware::ClientCtx;
+use crate::orm::upload_sessions;
+use actix_web::http::StatusCode;
+use actix_web::{error, get, post, put, web, Error, HttpResponse, Responder, ResponseError};
+use chrono::{NaiveDateTime, Utc};
+use dashmap::{DashMap, DashSet};
+use futures::StreamExt;
+use mime::Mime;
+use once_cell::sync::Lazy;
+use sea_orm::{entity::*, query::*, sea_query::Expr, ConnectionTrait, DbErr};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Hashers by session id. Hashers are lost on restart and are then rebuilt from the disk.
+static HASHERS: Lazy<DashMap<String, UploadHasher>> = Lazy::new(DashMap::new);
+/// Sessions which are receiving a chunk or being finalized.
+static BUSY: Lazy<DashSet<String>> = Lazy::new(DashSet::new);
+
+struct UploadHasher {
+    hasher: blake3::Hasher,
+    /// Bytes hashed.
+    hashed: i64,
+    updated_at: NaiveDateTime,
+}
+
+/// Claim on a session which is released when dropped.
+struct SessionLock(String);
+
+impl SessionLock {
+    fn claim(id: &str) -> Result<Self, UploadError> {
+        if BUSY.insert(id.to_owned()) {
+            Ok(Self(id.to_owned()))
+        } else {
+            Err(UploadError::Busy)
+        }
+    }
+}
+
+impl Drop for SessionLock {
+    fn drop(&mut self) {
+        BUSY.remove(&self.0);
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewUploadFormData {
+    pub filename: String,
+    pub filesize: i64,
+    pub mime: Option<String>,
+    /// BLAKE3 hash of the whole file, which is checked when the upload is finalized.
+    pub hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChunkQuery {
+    pub offset: i64,
+}
+
+#[derive(Serialize)]
+pub struct UploadSessionResponse {
+    pub id: String,
+    /// Bytes received, which is where the next chunk begins.
+    pub offset: i64,
+    pub filesize: i64,
+}
+
+impl From<&upload_sessions::Model> for UploadSessionResponse {
+    fn from(session: &upload_sessions::Model) -> Self {
+        Self {
+            id: session.id.to_owned(),
+            offset: session.received,
+            filesize: session.filesize,
+        }
+    }
+}
+
+/// Reasons a chunked upload request is refused.
+#[derive(Debug)]
+pub enum UploadError {
+    /// Only users may begin uploads.
+    Forbidden,
+    NotFound,
+    Invalid(&'static str),
+    /// The upload declares more bytes than any upload may have.
+    FileTooLarge {
+        max: i64,
+    },
+    /// The user has too many unfinished uploads.
+    TooManyUploads {
+        max: u64,
+    },
+    /// Another request is writing to the session.
+    Busy,
+    /// Chunks must begin where the last chunk ended.
+    WrongOffset {
+        offset: i64,
+    },
+    TooLarge {
+        filesize: i64,
+    },
+    Incomplete {
+        offset: i64,
+        filesize: i64,
+    },
+    /// The file does not match the hash given when the session was created.
+    HashMismatch,
+}
+
+impl UploadError {
+    /// Returns a stable identifier for clients.
+    pub fn get_code(&self) -> &'static str {
+        match self {
+            Self::Forbidden => "forbidden",
+            Self::NotFound => "not_found",
+            Self::Invalid(_) => "invalid",
+            Self::FileTooLarge { .. } => "file_too_large",
+            Self::TooManyUploads { .. } => "too_many_uploads",
+            Self::Busy => "busy",
+            Self::WrongOffset { .. } => "wrong_offset",
+            Self::TooLarge { .. } => "too_large",
+            Self::Incomplete { .. } => "incomplete",
+            Self::HashMismatch => "hash_mismatch",
+        }
+    }
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Forbidden => write!(f, "You must be logged in to upload files."),
+            Self::NotFound => write!(f, "The upload does not exist."),
+            Self::Invalid(reason) => write!(f, "{}", reason),
+            Self::FileTooLarge { max } => write!(f, "Uploads may be at most {} bytes.", max),
+            Self::TooManyUploads { max } => write!(
+                f,
+                "You already have {} unfinished uploads. Finish or wait for one to expire.",
+                max
+            ),
+            Self::Busy => write!(f, "The upload is receiving another chunk."),
+            Self::WrongOffset { offset } => {
+                write!(f, "The next chunk must begin at byte {}.", offset)
+            }
+            Self::TooLarge { filesize } => {
+                write!(
+                    f,
+                    "The upload is larger than the {} bytes declared.",
+                    filesize
+                )
+            }
+            Self::Incomplete { offset, filesize } => write!(
+                f,
+                "The upload has received {} of {} bytes.",
+                offset, filesize
+            ),
+            Self::HashMismatch => write!(f, "The upload does not match its hash."),
+        }
+    }
+}
+
+impl ResponseError for UploadError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Forbidden => StatusCode::FORBIDDEN,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Invalid(_) | Self::HashMismatch => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Busy | Self::WrongOffset { .. } | Self::Incomplete { .. } => StatusCode::CONFLICT,
+            Self::TooLarge { .. } | Self::FileTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::TooManyUploads { .. } => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut body = serde_json::json!({
+            "error": self.get_code(),
+            "message": self.to_string(),
+        });
+        // Clients resume from the offset.
+        if let Self::WrongOffset { offset } | Self::Incomplete { offset, .. } = self {
+            body["offset"] = serde_json::json!(offset);
+        }
+
+        HttpResponse::build(self.status_code()).json(body)
+    }
+}
+
+/// Chunks are written to DIR_TMP under the session id.
+fn get_upload_path(id: &str) -> PathBuf {
+    Path::new(get_dir_tmp()).join(id)
+}
+
+/// Returns a session if it belongs to the client. Guests have no sessions.
+async fn get_upload_session(client: &ClientCtx, id: &str) -> Result<upload_sessions::Model, Error> {
+    let user_id = client.get_id().ok_or(UploadError::NotFound)?;
+    upload_sessions::Entity::find_by_id(id.to_owned())
+        .one(get_db_pool())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .filter(|session| session.user_id == Some(user_id))
+        .ok_or_else(|| UploadError::NotFound.into())
+}
+
+/// Claims a session which belongs to the client.
+/// Ownership is checked first, so nobody may keep another user's session busy.
+/// The session is read again once claimed, as another request may have written to it.
+async fn claim_upload_session(
+    client: &ClientCtx,
+    id: &str,
+) -> Result<(SessionLock, upload_sessions::Model), Error> {
+    get_upload_session(client, id).await?;
+    let lock = SessionLock::claim(id)?;
+    let session = get_upload_session(client, id).await?;
+    Ok((lock, session))
+}
+
+/// Chunks must begin where the last chunk ended.
+fn check_offset(session: &upload_sessions::Model, offset: i64) -> Result<(), UploadError> {
+    if offset == session.received {
+        Ok(())
+    } else {
+        Err(UploadError::WrongOffset {
+            offset: session.received,
+        })
+    }
+}
+
+/// An upload may not receive more than the size it declared.
+fn check_length(
+    session: &upload_sessions::Model,
+    received: i64,
+    length: i64,
+) -> Result<(), UploadError> {
+    if received + length > session.filesize {
+        Err(UploadError::TooLarge {
+            filesize: session.filesize,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Hashes up to `received` bytes of an upload's data.
+/// Returns the hasher and how many bytes it hashed, which is fewer if data was lost.
+fn hash_upload_data(path: &Path, received: u64) -> std::io::Result<(blake3::Hasher, u64)> {
+    let mut hasher = blake3::Hasher::new();
+    let hashed = match File::open(path) {
+        Ok(f) => std::io::copy(&mut f.take(received), &mut hasher)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => 0,
+        Err(e) => return Err(e),
+    };
+    Ok((hasher, hashed))
+}
+
+/// Returns a hasher which has hashed everything the session has received.
+/// If data was lost, the session is rewound to what remains and the client must resume from there.
+async fn take_hasher(session: &upload_sessions::Model) -> Result<blake3::Hasher, Error> {
+    if let Some((_, upload)) = HASHERS.remove(&session.id) {
+        if upload.hashed == session.received {
+            return Ok(upload.hasher);
+        }
+    }
+
+    if session.received == 0 {
+        return Ok(blake3::Hasher::new());
+    }
+
+    log::info!("take_hasher: rehashing upload {}", session.id);
+    let path = get_upload_path(&session.id);
+    let received = session.received as u64;
+    let (hasher, hashed) = web::block(move || hash_upload_data(&path, received))
+        .await
+        .map_err(|e| {
+            log::error!("take_hasher: {}", e);
+            error::ErrorInternalServerError("put_upload_chunk: reading data")
+        })?
+        .map_err(|e: std::io::Error| {
+            log::error!("take_hasher: {}", e);
+            error::ErrorInternalServerError("put_upload_chunk: reading data")
+        })?;
+
+    if hashed == received {
+        return Ok(hasher);
+    }
+
+    log::warn!(
+        "take_hasher: upload {} has {} of {} bytes received",
+        session.id,
+        hashed,
+        received
+    );
+    let offset = hashed as i64;
+    upload_sessions::Entity::update_many()
+        .col_expr(upload_sessions::Column::Received, Expr::value(offset))
+        .filter(upload_sessions::Column::Id.eq(session.id.to_owned()))
+        .exec(get_db_pool())
+        .await
+        .map_err(|e| {
+            log::error!("take_hasher: {}", e);
+            error::ErrorInternalServerError("put_upload_chunk: failed to record progress")
+        })?;
+    HASHERS.insert(
+        session.id.to_owned(),
+        UploadHasher {
+            hasher,
+            hashed: offset,
+            updated_at: Utc::now().naive_utc(),
+        },
+    );
+
+    Err(UploadError::WrongOffset { offset }.into())
+}
+
+/// Removes a session and its hasher. The data is left for the caller.
+async fn delete_upload_session<C>(conn: &C, id: &str) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    HASHERS.remove(id);
+    upload_sessions::Entity::delete_by_id(id.to_owned())
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+fn remove_upload_data(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::error!("remove_upload_data: {}", e);
+        }
+    }
+}
+
+/// Deletes upload sessions which have received nothing for UPLOAD_SESSION_HOURS, and their data.
+/// Sessions which are receiving a chunk or being finalized are skipped.
+/// Returns how many sessions were deleted.
+pub async fn prune_upload_sessions<C>(conn: &C) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let cutoff = Utc::now().naive_utc() - crate::global::get_upload_session_age();
+    let sessions = upload_sessions::Entity::find()
+        .filter(upload_sessions::Column::UpdatedAt.lt(cutoff))
+        .all(conn)
+        .await?;
+
+    let mut count = 0;
+    for session in sessions {
+        let _lock = match SessionLock::claim(&session.id) {
+            Ok(lock) => lock,
+            Err(_) => continue,
+        };
+
+        // A session which received something since it was selected is kept.
+        let res = upload_sessions::Entity::delete_many()
+            .filter(upload_sessions::Column::Id.eq(session.id.to_owned()))
+            .filter(upload_sessions::Column::UpdatedAt.lt(cutoff))
+            .exec(conn)
+            .await?;
+        if res.rows_affected > 0 {
+            HASHERS.remove(&session.id);
+            remove_upload_data(&get_upload_path(&session.id));
+            count += res.rows_affected;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Begins a resumable upload for files too large to send in one request.
+/// The file is then PUT in chunks, each beginning where the last one ended, and finalized.
+/// After a disconnect the client views the upload to learn where to continue from.
+#[post("/fs/uploads")]
+pub async fn create_upload(
+    client: ClientCtx,
+    form: web::Json<NewUploadFormData>,
+) -> Result<impl Responder, Error> {
+    let user_id = match client.get_id() {
+        Some(user_id) if client.is_user() => user_id,
+        _ => return Err(UploadError::Forbidden.into()),
+    };
+
+    if form.filename.is_empty() {
+        return Err(UploadError::Invalid("The upload has no filename.").into());
+    }
+    if form.filesize <= 0 {
+        return Err(UploadError::Invalid("The upload is empty.").into());
+    }
+    let max_size = crate::global::get_upload_max_size();
+    if form.filesize > max_size {
+        return Err(UploadError::FileTooLarge { max: max_size }.into());
+    }
+    if let Some(hash) = &form.hash {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(UploadError::Invalid("Malformed BLAKE3 hash.").into());
+        }
+    }
+
+    let mime = form
+        .mime
+        .as_deref()
+        .and_then(|mime| mime.parse::<Mime>().ok())
+        .unwrap_or(mime::APPLICATION_OCTET_STREAM);
+    let now = Utc::now().naive_utc();
+
+    // Abandoned uploads are pruned from the database by a task, and from memory here.
+    let cutoff = now - crate::global::get_upload_session_age();
+    HASHERS.retain(|_, upload| upload.updated_at > cutoff);
+
+    // Abandoned uploads still count until they are pruned.
+    let max_sessions = crate::global::get_upload_max_sessions();
+    let sessions = upload_sessions::Entity::find()
+        .filter(upload_sessions::Column::UserId.eq(user_id))
+        .count(get_db_pool())
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    if sessions as u64 >= max_sessions {
+        return Err(UploadError::TooManyUploads { max: max_sessions }.into());
+    }
+
+    let session = upload_sessions::ActiveModel {
+        id: Set(Uuid::new_v4().to_string()),
+        user_id: Set(Some(user_id)),
+        filename: Set(form.filename.to_owned()),
+        mime: Set(mime.to_string()),
+        filesize: Set(form.filesize),
+        received: Set(0),
+        hash: Set(form.hash.as_ref().map(|hash| hash.to_ascii_lowercase())),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(get_db_pool())
+    .await
+    .map_err(|e| {
+        log::error!("create_upload: {}", e);
+        error::ErrorInternalServerError("create_upload: failed to create upload")
+    })?;
+
+    Ok(web::Json(UploadSessionResponse::from(&session)))
+}
+
+/// Returns how much of an upload has been received, so a client can resume it.
+#[get("/fs/uploads/{id}")]
+pub async fn view_upload(
+    client: ClientCtx,
+    id: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    let session = get_upload_session(&client, &id).await?;
+    Ok(web::Json(UploadSessionResponse::from(&session)))
+}
+
+/// Appends a chunk to an upload. Whatever arrives before a disconnect is kept.
+#[put("/fs/uploads/{id}")]
+pub async fn put_upload_chunk(
+    client: ClientCtx,
+    id: web::Path<String>,
+    query: web::Query<ChunkQuery>,
+    mut body: web::Payload,
+) -> Result<impl Responder, Error> {
+    let (_lock, session) = claim_upload_session(&client, &id).await?;
+    check_offset(&session, query.offset)?;
+
+    let mut hasher = take_hasher(&session).await?;
+    let path = get_upload_path(&session.id);
+    let mut received = session.received;
+    let mut f = web::block(move || {
+        let mut f = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)?;
+        // Data past what was recorded was never hashed and is written again.
+        f.set_len(received as u64)?;
+        f.seek(SeekFrom::Start(received as u64))?;
+        Ok(f)
+    })
+    .await
+    .map_err(|e| {
+        log::error!("put_upload_chunk: {}", e);
+        error::ErrorInternalServerError("put_upload_chunk: saving data")
+    })?
+    .map_err(|e: std::io::Error| {
+        log::error!("put_upload_chunk: {}", e);
+        error::ErrorInternalServerError("put_upload_chunk: saving data")
+    })?;
+
+    let mut result: Result<(), Error> = Ok(());
+    while let Some(chunk) = body.next().await {
+        let bytes = match chunk {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::debug!("put_upload_chunk: payload error: {}", e);
+                result = Err(e.into());
+                break;
+            }
+        };
+
+        let length = bytes.len() as i64;
+        if let Err(e) = check_length(&session, received, length) {
+            result = Err(e.into());
+            break;
+        }
+
+        match web::block(move || f.write_all(&bytes).map(|_| (f, bytes))).await {
+            Ok(Ok((file, bytes))) => {
+                f = file;
+                hasher.update(&bytes);
+                received += length;
+            }
+            Ok(Err(e)) => {
+                log::error!("put_upload_chunk: {}", e);
+                result = Err(error::ErrorInternalServerError(
+                    "put_upload_chunk: saving data",
+                ));
+                break;
+            }
+            Err(e) => {
+                log::error!("put_upload_chunk: {}", e);
+                result = Err(error::ErrorInternalServerError(
+                    "put_upload_chunk: saving data",
+                ));
+                break;
+            }
+        }
+    }
+
+    // Progress is recorded even if the chunk was cut short.
+    let now = Utc::now().naive_utc();
+    HASHERS.insert(
+        session.id.to_owned(),
+        UploadHasher {
+            hasher,
+            hashed: received,
+            updated_at: now,
+        },
+    );
+    let mut active: upload_sessions::ActiveModel = session.into();
+    active.received = Set(received);
+    active.updated_at = Set(now);
+    let session = active.update(get_db_pool()).await.map_err(|e| {
+        log::error!("put_upload_chunk: {}", e);
+        error::ErrorInternalServerError("put_upload_chunk: failed to record progress")
+    })?;
+
+    result?;
+    Ok(web::Json(UploadSessionResponse::from(&session)))
+}
+
+/// Verifies a complete upload and turns it into an attachment.
+#[post("/fs/uploads/{id}/finalize")]
+pub async fn finalize_upload(
+    client: ClientCtx,
+    id: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    let (_lock, session) = claim_upload_session(&client, &id).await?;
+
+    if session.received != session.filesize {
+        return Err(UploadError::Incomplete {
+            offset: session.received,
+            filesize: session.filesize,
+        }
+        .into());
+    }
+
+    let hash = take_hasher(&session).await?.finalize();
+    let path = get_upload_path(&session.id);
+
+    // Data which is not the file the client meant to send cannot be resumed.
+    if let Some(expected) = &session.hash {
+        if *expected != hash.to_string() {
+            delete_upload_session(get_db_pool(), &session.id)
+                .await
+                .map_err(|e| {
+                    log::error!("finalize_upload: {}", e);
+                    error::ErrorInternalServerError("finalize_upload: failed to finish upload")
+                })?;
+            remove_upload_data(&path);
+            return Err(UploadError::HashMismatch.into());
+        }
+    }
+
+    // Anything else which fails leaves the upload as it was, so finalizing may be tried again.
+    let keep_metadata = client.can_upload_original_media();
+    let response =
+        insert_upload_as_attachment(&session, hash, path.to_owned(), keep_metadata).await?;
+
+    // The attachment exists now, and a session left behind is pruned with the others.
+    if let Err(e) = delete_upload_session(get_db_pool(), &session.id).await {
+        log::error!("finalize_upload: {}", e);
+    }
+    // Attachments delete their data once stored, everything else is deleted here.
+    remove_upload_data(&path);
+
+    Ok(web::Json(response))
+}
+
+/// Stores a complete upload as an attachment.
+/// The upload's data is only deleted if it is stored, as stripped images are written beside it.
+async fn insert_upload_as_attachment(
+    session: &upload_sessions::Model,
+    hash: blake3::Hash,
+    path: PathBuf,
+    keep_metadata: bool,
+) -> Result<Option<crate::filesystem::UploadResponse>, Error> {
+    let stripped_path = get_upload_path(&format!("{}.stripped", session.id));
+    let stripped = if keep_metadata {
+        None
+    } else {
+        strip_temp_file_into(path.to_owned(), stripped_path.to_owned()).await?
+    };
+    let (hash, filesize, path) = match stripped {
+        Some((hash, filesize)) => (hash, filesize, stripped_path.to_owned()),
+        None => (hash, session.filesize as u64, path),
+    };
+
+    let payload = UploadPayload::new(
+        session.filename.to_owned(),
+        filesize,
+        hash,
+        path,
+        session
+            .mime
+            .parse()
+            .unwrap_or(mime::APPLICATION_OCTET_STREAM),
+    );
+
+    let result = match deduplicate_payload(&payload).await {
+        Some(response) => Ok(Some(response)),
+        None => insert_payload_as_attachment(payload, None).await,
+    };
+
+    if stripped.is_some() {
+        remove_upload_data(&stripped_path);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(received: i64, filesize: i64) -> upload_sessions::Model {
+        let now = Utc::now().naive_utc();
+        upload_sessions::Model {
+            id: Uuid::new_v4().to_string(),
+            user_id: Some(1),
+            filename: "video.mp4".to_owned(),
+            mime: "video/mp4".to_owned(),
+            filesize,
+            received,
+            hash: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn offset() {
+        let session = session(100, 1000);
+        assert!(check_offset(&session, 100).is_ok());
+        assert!(matches!(
+            check_offset(&session, 0),
+            Err(UploadError::WrongOffset { offset: 100 })
+        ));
+        assert!(matches!(
+            check_offset(&session, 200),
+            Err(UploadError::WrongOffset { offset: 100 })
+        ));
+    }
+
+    #[test]
+    fn too_large() {
+        let session = session(100, 1000);
+        assert!(check_length(&session, 100, 900).is_ok());
+        assert!(matches!(
+            check_length(&session, 100, 901),
+            Err(UploadError::TooLarge { filesize: 1000 })
+        ));
+        assert!(matches!(
+            check_length(&session, 1000, 1),
+            Err(UploadError::TooLarge { filesize: 1000 })
+        ));
+    }
+
+    #[test]
+    fn rehash() {
+        let data: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+        let path = std::env::temp_dir().join(format!("ruforo-upload-{}", Uuid::new_v4()));
+        std::fs::write(&path, &data).unwrap();
+
+        // Everything received is on the disk.
+        let (hasher, hashed) = hash_upload_data(&path, 10_000).unwrap();
+        assert_eq!(hashed, 10_000);
+        assert_eq!(hasher.finalize(), blake3::hash(&data));
+
+        // Data past what was received is ignored.
+        let (hasher, hashed) = hash_upload_data(&path, 4_000).unwrap();
+        assert_eq!(hashed, 4_000);
+        assert_eq!(hasher.finalize(), blake3::hash(&data[..4_000]));
+
+        // Lost data is reported so the client can resume before it.
+        std::fs::write(&path, &data[..6_000]).unwrap();
+        let (hasher, hashed) = hash_upload_data(&path, 10_000).unwrap();
+        assert_eq!(hashed, 6_000);
+        assert_eq!(hasher.finalize(), blake3::hash(&data[..6_000]));
+
+        std::fs::remove_file(&path).unwrap();
+        let (hasher, hashed) = hash_upload_data(&path, 10_000).unwrap();
+        assert_eq!(hashed, 0);
+        assert_eq!(hasher.finalize(), blake3::hash(b""));
+    }
+}
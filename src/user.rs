@@ -41,6 +41,7 @@ pub struct Profile {
     pub avatar_filename: Option<String>,
     pub avatar_height: Option<i32>,
     pub avatar_width: Option<i32>,
+    pub threads_per_page: Option<i32>,
 }
 
 impl Profile {
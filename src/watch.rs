@@ -0,0 +1,113 @@
+use crate::orm::{forum_watches, thread_watches};
+use chrono::prelude::Utc;
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, Statement};
+
+/// Watches a thread, which notifies the user of replies. Watching twice does nothing.
+pub async fn watch_thread<C>(conn: &C, user_id: i32, thread_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    conn.execute(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        r#"INSERT INTO thread_watches (user_id, thread_id, created_at) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, thread_id) DO NOTHING"#,
+        vec![
+            user_id.into(),
+            thread_id.into(),
+            Utc::now().naive_utc().into(),
+        ],
+    ))
+    .await?;
+
+    Ok(())
+}
+
+pub async fn unwatch_thread<C>(conn: &C, user_id: i32, thread_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    thread_watches::Entity::delete_by_id((user_id, thread_id))
+        .exec(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn is_watching_thread<C>(conn: &C, user_id: i32, thread_id: i32) -> Result<bool, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(thread_watches::Entity::find_by_id((user_id, thread_id))
+        .one(conn)
+        .await?
+        .is_some())
+}
+
+/// Returns the users watching a thread.
+pub async fn get_thread_watchers<C>(conn: &C, thread_id: i32) -> Result<Vec<i32>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(thread_watches::Entity::find()
+        .filter(thread_watches::Column::ThreadId.eq(thread_id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|watch| watch.user_id)
+        .collect())
+}
+
+/// Watches a forum, which notifies the user of new threads. Watching twice does nothing.
+pub async fn watch_forum<C>(conn: &C, user_id: i32, forum_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    conn.execute(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        r#"INSERT INTO forum_watches (user_id, forum_id, created_at) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, forum_id) DO NOTHING"#,
+        vec![
+            user_id.into(),
+            forum_id.into(),
+            Utc::now().naive_utc().into(),
+        ],
+    ))
+    .await?;
+
+    Ok(())
+}
+
+pub async fn unwatch_forum<C>(conn: &C, user_id: i32, forum_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    forum_watches::Entity::delete_by_id((user_id, forum_id))
+        .exec(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn is_watching_forum<C>(conn: &C, user_id: i32, forum_id: i32) -> Result<bool, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(forum_watches::Entity::find_by_id((user_id, forum_id))
+        .one(conn)
+        .await?
+        .is_some())
+}
+
+/// Returns the users watching a forum.
+pub async fn get_forum_watchers<C>(conn: &C, forum_id: i32) -> Result<Vec<i32>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    Ok(forum_watches::Entity::find()
+        .filter(forum_watches::Column::ForumId.eq(forum_id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|watch| watch.user_id)
+        .collect())
+}
@@ -1,14 +1,30 @@
 use crate::db::get_db_pool;
 use crate::middleware::ClientCtx;
+use crate::notification::NotificationForTemplate;
+use crate::template::{Paginator, PaginatorToHtml};
 use crate::user::Profile as UserProfile;
 use actix_multipart::Multipart;
-use actix_web::{error, get, post, Error, HttpResponse, Responder};
+use actix_web::{error, get, post, routes, web, Error, HttpResponse, Responder};
 use askama_actix::{Template, TemplateToResponse};
 use chrono::Utc;
 use sea_orm::entity::*;
+use serde::Deserialize;
 
 pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
-    conf.service(update_avatar).service(view_account);
+    conf.service(update_avatar)
+        .service(update_preferences)
+        .service(view_account)
+        // Pages and mark-read are registered before the {notification_id} route they resemble.
+        .service(view_notifications)
+        .service(view_notifications_page)
+        .service(read_all_notifications)
+        .service(view_notification);
+}
+
+#[derive(Deserialize)]
+pub struct PreferencesFormData {
+    /// Empty for the forum's default.
+    pub threads_per_page: String,
 }
 
 #[derive(Template)]
@@ -18,6 +34,14 @@ pub struct AccountTemplate {
     pub profile: UserProfile,
 }
 
+#[derive(Template)]
+#[template(path = "notifications.html")]
+pub struct NotificationsTemplate<'a> {
+    pub client: ClientCtx,
+    pub notifications: &'a Vec<NotificationForTemplate>,
+    pub paginator: Paginator,
+}
+
 #[post("/account/avatar")]
 async fn update_avatar(client: ClientCtx, mutipart: Option<Multipart>) -> impl Responder {
     use crate::filesystem::{
@@ -41,7 +65,12 @@ async fn update_avatar(client: ClientCtx, mutipart: Option<Multipart>) -> impl R
                 match field_name {
                     "avatar" => {
                         // Save the file to a temporary location and get payload data.
-                        let payload = match save_field_as_temp_file(&mut field).await? {
+                        let payload = match save_field_as_temp_file(
+                            &mut field,
+                            client.can_upload_original_media(),
+                        )
+                        .await?
+                        {
                             Some(payload) => payload,
                             None => {
                                 return Err(error::ErrorBadRequest("Upload is empty or improper."))
@@ -89,6 +118,47 @@ async fn update_avatar(client: ClientCtx, mutipart: Option<Multipart>) -> impl R
         .finish())
 }
 
+#[post("/account/preferences")]
+async fn update_preferences(
+    client: ClientCtx,
+    form: web::Form<PreferencesFormData>,
+) -> Result<impl Responder, Error> {
+    use super::forum::{MAX_THREADS_PER_PAGE, MIN_THREADS_PER_PAGE};
+    use crate::orm::users;
+
+    let user_id = client
+        .get_id()
+        .ok_or_else(|| error::ErrorUnauthorized("You must be logged in to do that."))?;
+
+    let threads_per_page = match form.threads_per_page.trim() {
+        "" => None,
+        value => match value.parse::<i32>() {
+            Ok(value) if (MIN_THREADS_PER_PAGE..=MAX_THREADS_PER_PAGE).contains(&value) => {
+                Some(value)
+            }
+            _ => {
+                return Err(error::ErrorUnprocessableEntity(format!(
+                    "Threads per page must be between {} and {}.",
+                    MIN_THREADS_PER_PAGE, MAX_THREADS_PER_PAGE
+                )))
+            }
+        },
+    };
+
+    users::ActiveModel {
+        id: Unchanged(user_id),
+        threads_per_page: Set(threads_per_page),
+        ..Default::default()
+    }
+    .update(get_db_pool())
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", "/account"))
+        .finish())
+}
+
 #[get("/account")]
 async fn view_account(client: ClientCtx) -> Result<impl Responder, Error> {
     if !client.is_user() {
@@ -105,3 +175,97 @@ async fn view_account(client: ClientCtx) -> Result<impl Responder, Error> {
 
     Ok(AccountTemplate { client, profile }.to_response())
 }
+
+/// Marks every notification the client has as read.
+#[post("/account/notifications/mark-read")]
+async fn read_all_notifications(client: ClientCtx) -> Result<impl Responder, Error> {
+    use crate::notification::mark_notifications_read;
+
+    let user_id = client
+        .get_id()
+        .ok_or_else(|| error::ErrorUnauthorized("You must be logged in to do that."))?;
+
+    mark_notifications_read(get_db_pool(), user_id, None)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", "/account/notifications"))
+        .finish())
+}
+
+/// Marks a notification read and redirects to what it is about.
+#[get("/account/notifications/{notification_id}")]
+async fn view_notification(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    use crate::notification::read_notification;
+
+    let user_id = client
+        .get_id()
+        .ok_or_else(|| error::ErrorUnauthorized("You must be logged in to do that."))?;
+
+    let notification = read_notification(get_db_pool(), user_id, path.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Notification not found."))?;
+
+    Ok(HttpResponse::Found()
+        .append_header((
+            "Location",
+            notification
+                .get_url()
+                .unwrap_or_else(|| "/account/notifications".to_owned()),
+        ))
+        .finish())
+}
+
+#[routes]
+#[get("/account/notifications")]
+#[get("/account/notifications/")]
+async fn view_notifications(client: ClientCtx) -> Result<impl Responder, Error> {
+    render_notifications(client, 1).await
+}
+
+#[get("/account/notifications/page-{page}")]
+async fn view_notifications_page(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    render_notifications(client, std::cmp::max(1, path.into_inner())).await
+}
+
+/// Lists the client's notifications, newest first. Unread notifications are marked as such.
+async fn render_notifications(client: ClientCtx, page: i32) -> Result<impl Responder, Error> {
+    use super::forum::get_pages_in_forum;
+    use crate::notification::{
+        count_notifications, get_notifications_for_template, NOTIFICATIONS_PER_PAGE,
+    };
+
+    let user_id = client
+        .get_id()
+        .ok_or_else(|| error::ErrorUnauthorized("You must be logged in to do that."))?;
+
+    let db = get_db_pool();
+    let notification_count = count_notifications(db, user_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let notifications = get_notifications_for_template(db, user_id, page)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let paginator = Paginator {
+        base_url: "/account/notifications/".to_owned(),
+        query: String::new(),
+        this_page: page,
+        page_count: get_pages_in_forum(notification_count as i32, NOTIFICATIONS_PER_PAGE),
+    };
+
+    Ok(NotificationsTemplate {
+        client,
+        notifications: &notifications,
+        paginator,
+    }
+    .to_response())
+}
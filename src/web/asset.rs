@@ -1,10 +1,16 @@
+use crate::attachment::{get_attachment_by_hash, get_thumbnail_by_hash};
+use crate::orm::attachment_thumbnails::ThumbnailSize;
+use crate::storage::{get_storage, StorageError};
 use actix_files as fs;
 use actix_web::http::{header, header::ContentEncoding, StatusCode};
 use actix_web::{get, Error, HttpRequest, HttpResponse, Responder};
+use sea_orm::ActiveEnum;
 use std::path::PathBuf;
 
 pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
-    conf.service(view_file_by_hash).service(view_public_file);
+    conf.service(view_file_by_hash)
+        .service(view_thumbnail_by_hash)
+        .service(view_public_file);
 }
 
 /// Route for passing local assets through the webserver.
@@ -12,13 +18,42 @@ pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
 #[get("/content/{hash:.*}/{filename:.*}")]
 async fn view_file_by_hash(req: HttpRequest) -> impl Responder {
     let hash: String = req.match_info().query("hash").parse().expect("Bad hash.");
-    let key: String = match crate::attachment::get_attachment_by_hash(hash).await {
+    let key: String = match get_attachment_by_hash(hash).await {
         Some(attachment) => attachment.filename,
         None => {
             return HttpResponse::NotFound().body("404 - Resource not found");
         }
     };
 
+    get_object_response(&req, &key).await
+}
+
+/// Route for passing attachment thumbnails through the webserver.
+/// Images without a thumbnail fall back to their native size.
+/// /thumbnails/l/9e0834c0d3dd1f6a775b9af7523eff7b35e750afb8fcd2753eef06735e13c46f/whatever.jpg
+#[get("/thumbnails/{size}/{hash}/{filename:.*}")]
+async fn view_thumbnail_by_hash(req: HttpRequest) -> impl Responder {
+    let size = match ThumbnailSize::try_from_value(&req.match_info().query("size").to_owned()) {
+        Ok(size) => size,
+        Err(_) => return HttpResponse::NotFound().body("404 - Resource not found"),
+    };
+    let hash = req.match_info().query("hash").to_owned();
+
+    let key: String = match get_thumbnail_by_hash(hash.to_owned(), size).await {
+        Some(thumbnail) => thumbnail.filename,
+        None => match get_attachment_by_hash(hash).await {
+            Some(attachment) if attachment.mime.starts_with("image/") => attachment.filename,
+            _ => {
+                return HttpResponse::NotFound().body("404 - Resource not found");
+            }
+        },
+    };
+
+    get_object_response(&req, &key).await
+}
+
+/// Streams an object from storage, passing through its headers.
+async fn get_object_response(req: &HttpRequest, key: &str) -> HttpResponse {
     //let name: String = req
     //    .match_info()
     //    .query("filename")
@@ -32,16 +67,18 @@ async fn view_file_by_hash(req: HttpRequest) -> impl Responder {
         .and_then(|r| r.to_str().ok())
         .map(From::from);
 
-    let res = match crate::filesystem::get_s3().get_object(&key, range).await {
+    let res = match get_storage().get_object(key, range).await {
         Ok(output) => output,
+        Err(StorageError::InvalidRange) => {
+            return HttpResponse::RangeNotSatisfiable().body("416 - Range not satisfiable");
+        }
         Err(err) => {
             log::debug!("{:?}", err);
             return HttpResponse::NotFound().body("404 - Content not found");
         }
     };
 
-    //let body = res.body.expect("No body for response").map(Bytes::from).map_err(Error::from);
-    let body = res.body.expect("No body for response");
+    let body = res.body;
     let mut builder = HttpResponse::Ok();
 
     if let Some(content_length) = res.content_length {
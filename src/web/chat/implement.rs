@@ -1,4 +1,5 @@
 use super::message;
+use crate::bbcode::Mentions;
 use crate::user::Profile;
 use actix::prelude::*;
 use chrono::NaiveDateTime;
@@ -156,6 +157,18 @@ impl Smilie {
     }
 }
 
+/// Returns a layer's smilies for a Constructor.
+pub async fn load_smilies(layer: &dyn ChatLayer) -> crate::bbcode::Smilies {
+    crate::bbcode::Smilies::new_from_tuples(
+        layer
+            .get_smilie_list()
+            .await
+            .into_iter()
+            .map(|smilie| (smilie.replace.to_string(), smilie.to_html()))
+            .collect(),
+    )
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SpriteParams {
     h: usize,
@@ -185,6 +198,7 @@ pub trait ChatLayer {
     async fn can_view(&self, session_id: u32, room_id: u32) -> bool;
     async fn delete_message(&self, id: u32);
     async fn edit_message(&self, id: u32, author: Author, message: String) -> Option<Message>;
+    async fn get_mentioned_users(&self, names: Vec<String>) -> Mentions;
     async fn get_message(&self, message_id: u32) -> Option<Message>;
     async fn get_room_history(&self, room_id: u32, limit: usize) -> Vec<(Author, Message)>;
     async fn get_room_list(&self) -> Vec<Room>;
@@ -201,7 +215,7 @@ pub mod default {
     use super::*;
     use crate::middleware::ClientCtx;
     use crate::orm::{chat_messages, chat_rooms, ugc_revisions};
-    use crate::ugc::{create_ugc, NewUgcPartial};
+    use crate::ugc::{create_ugc, ContentStatus, NewUgcPartial};
     use crate::user::{find_also_user, Profile as UserProfile};
     use sea_orm::{entity::*, query::*, DatabaseConnection, EntityTrait, QuerySelect};
 
@@ -233,6 +247,12 @@ pub mod default {
             None
         }
 
+        async fn get_mentioned_users(&self, names: Vec<String>) -> Mentions {
+            crate::mention::get_mentioned_users(&self.db, &names)
+                .await
+                .unwrap_or_default()
+        }
+
         async fn get_message(&self, id: u32) -> Option<super::Message> {
             chat_messages::Entity::find_by_id(id as i32)
                 .select_only()
@@ -339,13 +359,14 @@ pub mod default {
         }
 
         async fn insert_chat_message(&self, message: &message::Post) -> Option<super::Message> {
-            let ugc_revision = match create_ugc(
+            let (ugc_revision, notifications) = match create_ugc(
                 &self.db,
                 NewUgcPartial {
                     ip_id: None,
                     user_id: Some(message.session.id as i32),
                     content: &message.message,
                 },
+                ContentStatus::Visible,
             )
             .await
             {
@@ -377,6 +398,12 @@ pub mod default {
                 }
             };
 
+            if let Err(err) =
+                crate::notification::push_notifications(&self.db, &notifications).await
+            {
+                log::error!("Failed to push chat_message notifications: {:?}", err);
+            }
+
             Some(super::Message {
                 user_id: chat_message.user_id.unwrap_or(0) as u32,
                 room_id: chat_message.chat_room_id as u32,
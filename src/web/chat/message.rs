@@ -65,6 +65,17 @@ impl Message for Join {
     type Result = ();
 }
 
+/// Alert sent to every connection of a user.
+/// Usually a serialized JSON string.
+pub struct Notify {
+    pub user_id: u32,
+    pub message: String,
+}
+
+impl Message for Notify {
+    type Result = ();
+}
+
 #[derive(Serialize)]
 pub struct Post {
     /// Conn Id
@@ -5,11 +5,11 @@ pub mod server;
 
 use actix::Addr;
 use actix_files as fs;
-use actix_web::{get, web, web::Data, Error, HttpRequest, HttpResponse, Responder};
+use actix_web::{error, get, post, web, web::Data, Error, HttpRequest, HttpResponse, Responder};
 use actix_web_actors::ws;
 use askama_actix::Template;
 use implement::{ChatLayer, Room};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -22,7 +22,91 @@ pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
 pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
-    conf.service(view_chat_socket).service(view_chat);
+    conf.service(react_to_message)
+        .service(view_chat_socket)
+        .service(view_chat);
+}
+
+/// A reaction total on a chat message.
+#[derive(Serialize)]
+pub struct ReactionSummary {
+    pub id: i32,
+    pub label: String,
+    pub emoji: String,
+    pub count: i64,
+}
+
+/// Reactions on a chat message after the client reacted to it.
+#[derive(Serialize)]
+pub struct ReactionResponse {
+    pub reactions: Vec<ReactionSummary>,
+    pub chosen: Option<i32>,
+}
+
+/// Toggles the client's reaction to a chat message in a room they can view.
+#[post("/chat/messages/{message_id}/react")]
+pub async fn react_to_message(
+    client: ClientCtx,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    form: web::Json<crate::reaction::ReactionFormData>,
+) -> Result<impl Responder, Error> {
+    use crate::db::get_db_pool;
+    use crate::orm::chat_messages;
+    use crate::reaction::{is_reaction_type, toggle_reaction, UgcReactions};
+    use sea_orm::EntityTrait;
+
+    let db = get_db_pool();
+    let message = chat_messages::Entity::find_by_id(path.into_inner())
+        .one(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Message not found."))?;
+
+    let user_id = match client.get_id() {
+        Some(user_id) if client.can_react_to(&message.user_id) => user_id,
+        _ => {
+            return Err(error::ErrorForbidden(
+                "You do not have permission to react to this message.",
+            ))
+        }
+    };
+
+    // Messages in rooms the client cannot view do not exist to them.
+    let layer = req
+        .app_data::<Data<Arc<dyn ChatLayer>>>()
+        .expect("No chat layer.");
+    if !layer
+        .can_view(user_id as u32, message.chat_room_id as u32)
+        .await
+    {
+        return Err(error::ErrorNotFound("Message not found."));
+    }
+
+    if !is_reaction_type(form.reaction) {
+        return Err(error::ErrorUnprocessableEntity("Unknown reaction."));
+    }
+
+    let chosen = toggle_reaction(db, message.ugc_id, user_id, message.user_id, form.reaction)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let reactions = UgcReactions::load(db, vec![message.ugc_id], None)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(web::Json(ReactionResponse {
+        reactions: reactions
+            .get_summary(&message.ugc_id)
+            .into_iter()
+            .map(|(reaction_type, count)| ReactionSummary {
+                id: reaction_type.id,
+                label: reaction_type.label.to_owned(),
+                emoji: reaction_type.emoji.to_owned(),
+                count,
+            })
+            .collect(),
+        chosen,
+    }))
 }
 
 /// Entry point for our websocket route
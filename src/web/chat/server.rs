@@ -1,7 +1,7 @@
 use super::implement::{self, UserActivity};
 use super::implement::{ChatLayer, Connection};
 use super::message::{self, SanitaryPost, SanitaryPosts};
-use crate::bbcode::{tokenize, Constructor, Parser, Smilies};
+use crate::bbcode::{get_mentions, tokenize, Constructor, Mentions, Parser};
 use actix::prelude::*;
 use rand::{self, rngs::ThreadRng, Rng};
 use std::collections::{HashMap, HashSet};
@@ -31,14 +31,12 @@ impl ChatServer {
 
         // Constructor
         let constructor = Constructor {
-            smilies: Smilies::new_from_tuples(
-                layer
-                    .get_smilie_list()
-                    .await
-                    .into_iter()
-                    .map(|smilie| (smilie.replace.to_string(), smilie.to_html()))
-                    .collect(),
-            ),
+            smilies: Arc::new(implement::load_smilies(layer.as_ref()).await),
+            // Block spoilers can take up a lot of space in chat, so they may be disabled.
+            allow_block_spoilers: std::env::var("CHAT_BLOCK_SPOILERS")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            ..Default::default()
         };
 
         Self {
@@ -105,6 +103,21 @@ impl ChatServer {
         }
     }
 
+    /// Prepares messages with the users they mention, who are forgotten afterwards.
+    fn prepare_messages(
+        &mut self,
+        messages: Vec<(implement::Author, implement::Message)>,
+        mentions: Mentions,
+    ) -> Vec<SanitaryPost> {
+        self.constructor.mentions = mentions;
+        let messages = messages
+            .into_iter()
+            .map(|(author, message)| self.prepare_message(author, message))
+            .collect();
+        self.constructor.mentions = Mentions::default();
+        messages
+    }
+
     /// Receives session+message database data to create a SanitaryPost.
     fn prepare_message(
         &self,
@@ -119,7 +132,7 @@ impl ChatServer {
             }
         };
 
-        let mut parser = Parser::new();
+        let mut parser = Parser::new_for_constructor(&self.constructor);
         let ast = parser.parse(&tokens);
 
         message::SanitaryPost {
@@ -140,6 +153,15 @@ impl ChatServer {
         }
     }
 
+    /// Send message to every connection of a user
+    fn send_message_to_user(&self, user_id: u32, message: String) {
+        for conn in self.connections.values() {
+            if conn.session.id == user_id {
+                conn.recipient.do_send(message::Reply(message.to_owned()));
+            }
+        }
+    }
+
     /// Send message to all users in a room
     fn send_message_to_room(&self, room: u32, message: String) {
         if let Some(connections) = self.rooms.get(&room) {
@@ -152,6 +174,24 @@ impl ChatServer {
     }
 }
 
+/// Resolves the users mentioned in messages, so they can be prepared.
+async fn get_mentions_for_messages<'a, I>(layer: &Arc<dyn ChatLayer>, messages: I) -> Mentions
+where
+    I: Iterator<Item = &'a implement::Message>,
+{
+    let mut names: Vec<String> = messages
+        .flat_map(|message| get_mentions(&message.message).names)
+        .collect();
+
+    if names.is_empty() {
+        return Mentions::default();
+    }
+
+    names.sort_unstable();
+    names.dedup();
+    layer.get_mentioned_users(names).await
+}
+
 /// Make actor from `ChatServer`
 impl Actor for ChatServer {
     /// We are going to use simple Context, we just need ability to communicate with other actors.
@@ -258,34 +298,38 @@ impl Handler<message::Edit> for ChatServer {
                 let res = layer.get_message(msg.message_id).await;
 
                 // If we got the message, check if we can delete it.
-                if let Some(message) = &res {
-                    if message.user_id == session.id {
+                let res = match &res {
+                    Some(message) if message.user_id == session.id => {
                         // Delete message.
-                        return layer
+                        layer
                             .edit_message(message.message_id, author, msg.message)
-                            .await;
-                    } else {
+                            .await
+                    }
+                    Some(_) => {
                         log::warn!(
                             "User {} tried to edit message {:?}",
                             msg.session.id,
                             msg.message_id
                         );
-                        return None;
+                        None
                     }
-                }
+                    None => res,
+                };
 
-                res
+                let mentions = get_mentions_for_messages(&layer, res.iter()).await;
+                (res, mentions)
             }
             .into_actor(self)
-            .map(move |message, actor, _ctx| {
+            .map(move |(message, mentions), actor, _ctx| {
                 if let Some(message) = message {
+                    let room_id = message.room_id;
+                    let messages = actor.prepare_messages(
+                        vec![(implement::Author::from(&session), message)],
+                        mentions,
+                    );
                     actor.send_message_to_room(
-                        message.room_id,
-                        serde_json::to_string(&message::SanitaryPosts {
-                            messages: vec![
-                                actor.prepare_message(implement::Author::from(&session), message)
-                            ],
-                        })
+                        room_id,
+                        serde_json::to_string(&message::SanitaryPosts { messages })
                         .expect("ClientMessages serialize failure"),
                     );
                 } else {
@@ -315,19 +359,18 @@ impl Handler<message::Join> for ChatServer {
         Box::pin(
             async move {
                 if layer.can_view(session.id, room_id).await {
-                    (true, layer.get_room_history(room_id, 40).await)
+                    let history = layer.get_room_history(room_id, 40).await;
+                    let mentions =
+                        get_mentions_for_messages(&layer, history.iter().map(|(_, m)| m)).await;
+                    (true, history, mentions)
                 } else {
-                    (false, Vec::default())
+                    (false, Vec::default(), Mentions::default())
                 }
             }
             .into_actor(self)
-            .map(move |(can_view, unsanitized), actor, _ctx| {
+            .map(move |(can_view, unsanitized, mentions), actor, _ctx| {
                 if can_view {
-                    let mut messages: Vec<SanitaryPost> = Vec::with_capacity(unsanitized.len());
-
-                    for (author, message) in unsanitized {
-                        messages.push(actor.prepare_message(author, message));
-                    }
+                    let messages = actor.prepare_messages(unsanitized, mentions);
 
                     actor.send_message_to_conn(
                         id,
@@ -357,6 +400,18 @@ impl Handler<message::Join> for ChatServer {
     }
 }
 
+/// Handler for Notify message.
+impl Handler<message::Notify> for ChatServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: message::Notify, _: &mut Context<Self>) {
+        // Guests share an id and are never notified.
+        if msg.user_id > 0 {
+            self.send_message_to_user(msg.user_id, msg.message);
+        }
+    }
+}
+
 /// Handler for Message message.
 impl Handler<message::Post> for ChatServer {
     type Result = ResponseActFuture<Self, ()>;
@@ -368,18 +423,23 @@ impl Handler<message::Post> for ChatServer {
             let session = msg.session.to_owned();
 
             Box::pin(
-                async move { layer.insert_chat_message(&msg).await }
+                async move {
+                    let message = layer.insert_chat_message(&msg).await;
+                    let mentions = get_mentions_for_messages(&layer, message.iter()).await;
+                    (message, mentions)
+                }
                     .into_actor(self)
-                    .map(move |message, actor, _| {
+                    .map(move |(message, mentions), actor, _| {
                         if let Some(message) = message {
                             let room_id = message.room_id;
+                            let messages = actor.prepare_messages(
+                                vec![(implement::Author::from(&session), message)],
+                                mentions,
+                            );
 
                             actor.send_message_to_room(
                                 room_id,
-                                serde_json::to_string(&message::SanitaryPosts {
-                                    messages: vec![actor
-                                        .prepare_message(implement::Author::from(&session), message)],
-                                })
+                                serde_json::to_string(&message::SanitaryPosts { messages })
                                 .expect("message::Post serialize failure"),
                             );
                         }
@@ -1,30 +1,546 @@
-use super::thread::{validate_thread_form, NewThreadFormData, ThreadForTemplate};
+use super::thread::{
+    get_thread_content_status, validate_thread_form, NewThreadFormData, ThreadForTemplate,
+};
 use crate::db::get_db_pool;
 use crate::middleware::ClientCtx;
-use crate::orm::{posts, threads, user_names};
+use crate::orm::{
+    forum_thread_prefixes, forums, posts, tags, thread_prefixes, thread_tags, threads, ugc,
+    user_names,
+};
+use crate::read_marker::{get_unread_forum_ids, ReadMarkers};
+use crate::template::{Paginator, PaginatorToHtml};
+use crate::ugc::ContentStatus;
+use crate::watch::is_watching_forum;
 use actix_web::{error, get, post, web, Error, HttpResponse, Responder};
 use askama_actix::{Template, TemplateToResponse};
-use sea_orm::{entity::*, query::*, sea_query::Expr};
+use chrono::prelude::Utc;
+use sea_orm::{
+    entity::*,
+    query::*,
+    sea_query::{Expr, Query},
+    ConnectionTrait, DbErr, FromQueryResult, UpdateMany,
+};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
 
 pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
     conf.service(create_thread)
+        .service(mark_forum_read)
+        .service(unwatch_forum)
         .service(view_forums)
-        .service(view_forum);
+        .service(view_forum)
+        .service(view_forum_page)
+        .service(watch_forum);
+}
+
+/// Default number of threads on each page of a forum.
+pub const THREADS_PER_PAGE: i32 = 20;
+/// Bounds for page sizes chosen by forums and users.
+pub const MIN_THREADS_PER_PAGE: i32 = 5;
+pub const MAX_THREADS_PER_PAGE: i32 = 100;
+
+/// Returns the number of threads on each page.
+/// The client's preference is used first, then the forum's setting, then the default.
+pub fn get_threads_per_page(client: &ClientCtx, forum: Option<&forums::Model>) -> i32 {
+    client
+        .get_user()
+        .and_then(|user| user.threads_per_page)
+        .or_else(|| forum.and_then(|forum| forum.threads_per_page))
+        .unwrap_or(THREADS_PER_PAGE)
+        .clamp(MIN_THREADS_PER_PAGE, MAX_THREADS_PER_PAGE)
+}
+
+/// Returns how many pages a listing of this many threads spans.
+pub fn get_pages_in_forum(cnt: i32, per_page: i32) -> i32 {
+    ((std::cmp::max(1, cnt) - 1) / per_page) + 1
+}
+
+/// Orders in which a forum's threads may be listed.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadSort {
+    #[default]
+    LastPost,
+    Created,
+    Replies,
+    Views,
+    Title,
+}
+
+impl ThreadSort {
+    pub const ALL: [ThreadSort; 5] = [
+        ThreadSort::LastPost,
+        ThreadSort::Created,
+        ThreadSort::Replies,
+        ThreadSort::Views,
+        ThreadSort::Title,
+    ];
+
+    /// Returns the value used in query strings.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThreadSort::LastPost => "last_post",
+            ThreadSort::Created => "created",
+            ThreadSort::Replies => "replies",
+            ThreadSort::Views => "views",
+            ThreadSort::Title => "title",
+        }
+    }
+
+    /// TODO: l10n
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ThreadSort::LastPost => "Last post",
+            ThreadSort::Created => "Creation date",
+            ThreadSort::Replies => "Replies",
+            ThreadSort::Views => "Views",
+            ThreadSort::Title => "Title",
+        }
+    }
+
+    pub fn get_column(&self) -> threads::Column {
+        match self {
+            ThreadSort::LastPost => threads::Column::LastPostAt,
+            ThreadSort::Created => threads::Column::CreatedAt,
+            ThreadSort::Replies => threads::Column::PostCount,
+            ThreadSort::Views => threads::Column::ViewCount,
+            ThreadSort::Title => threads::Column::Title,
+        }
+    }
+
+    /// Titles read alphabetically. Everything else starts with the greatest.
+    pub fn get_default_order(&self) -> SortOrder {
+        match self {
+            ThreadSort::Title => SortOrder::Asc,
+            _ => SortOrder::Desc,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ForumFilterQuery {
+    /// Thread prefix. Zero or absent for any.
+    pub prefix: Option<i32>,
+    /// Tag name. Empty or absent for any.
+    pub tag: Option<String>,
+    pub sort: Option<ThreadSort>,
+    /// Absent for the sort's default order.
+    pub order: Option<SortOrder>,
 }
 
 #[derive(Template)]
 #[template(path = "forum.html")]
 pub struct ForumTemplate<'a> {
     pub client: ClientCtx,
-    pub forum: &'a crate::orm::forums::Model,
+    pub forum: &'a forums::Model,
+    pub ancestors: &'a Vec<forums::Model>,
+    pub children: &'a Vec<ForumNode>,
+    pub last_threads: &'a HashMap<i32, threads::Model>,
+    pub unread_forums: &'a HashSet<i32>,
     pub threads: &'a Vec<ThreadForTemplate>,
+    pub read_markers: &'a ReadMarkers,
+    pub tags: &'a HashMap<i32, Vec<tags::Model>>,
+    /// Prefixes which may be used in this forum.
+    pub prefixes: &'a Vec<thread_prefixes::Model>,
+    pub filter_prefix: Option<thread_prefixes::Model>,
+    pub filter_tag: Option<tags::Model>,
+    pub sort: ThreadSort,
+    pub order: SortOrder,
+    pub paginator: Paginator,
+    /// True if the client is notified of new threads.
+    pub is_watching: bool,
+}
+
+impl ForumTemplate<'_> {
+    pub fn get_last_thread(&self, node: &ForumNode) -> Option<&threads::Model> {
+        node.last_thread_id
+            .and_then(|id| self.last_threads.get(&id))
+    }
+
+    pub fn is_forum_unread(&self, node: &ForumNode) -> bool {
+        node.is_unread(self.unread_forums)
+    }
+
+    pub fn get_sorts(&self) -> &'static [ThreadSort] {
+        &ThreadSort::ALL
+    }
+
+    pub fn is_sort(&self, sort: &ThreadSort) -> bool {
+        self.sort == *sort
+    }
+
+    pub fn is_ascending(&self) -> bool {
+        self.order == SortOrder::Asc
+    }
+
+    pub fn is_filtered(&self) -> bool {
+        self.filter_prefix.is_some() || self.filter_tag.is_some()
+    }
+
+    pub fn is_filter_prefix(&self, prefix_id: &i32) -> bool {
+        matches!(&self.filter_prefix, Some(prefix) if prefix.id == *prefix_id)
+    }
 }
 
 #[derive(Template)]
 #[template(path = "forums.html")]
 pub struct ForumIndexTemplate<'a> {
     pub client: ClientCtx,
-    pub forums: &'a Vec<crate::orm::forums::Model>,
+    pub forums: &'a Vec<ForumNode>,
+    pub last_threads: &'a HashMap<i32, threads::Model>,
+    pub unread_forums: &'a HashSet<i32>,
+}
+
+impl ForumIndexTemplate<'_> {
+    pub fn get_last_thread(&self, node: &ForumNode) -> Option<&threads::Model> {
+        node.last_thread_id
+            .and_then(|id| self.last_threads.get(&id))
+    }
+
+    pub fn is_forum_unread(&self, node: &ForumNode) -> bool {
+        node.is_unread(self.unread_forums)
+    }
+}
+
+/// Deepest level of sub-forums which is built into a tree.
+const MAX_FORUM_DEPTH: usize = 16;
+
+/// A forum with its sub-forums, and statistics which include its sub-forums.
+#[derive(Debug)]
+pub struct ForumNode {
+    pub forum: forums::Model,
+    pub children: Vec<ForumNode>,
+    pub thread_count: i32,
+    pub post_count: i32,
+    pub last_thread_id: Option<i32>,
+    pub last_post_id: Option<i32>,
+    pub last_post_at: Option<chrono::NaiveDateTime>,
+}
+
+impl ForumNode {
+    /// Builds the tree of forums below a parent, or from the root if None.
+    /// Forums should already be in display order.
+    pub fn build(forums: &[forums::Model], parent_id: Option<i32>) -> Vec<Self> {
+        Self::build_to_depth(forums, parent_id, MAX_FORUM_DEPTH)
+    }
+
+    fn build_to_depth(forums: &[forums::Model], parent_id: Option<i32>, depth: usize) -> Vec<Self> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        forums
+            .iter()
+            .filter(|forum| forum.parent_id == parent_id)
+            .map(|forum| {
+                let mut node = Self {
+                    forum: forum.to_owned(),
+                    children: Self::build_to_depth(forums, Some(forum.id), depth - 1),
+                    thread_count: forum.thread_count,
+                    post_count: forum.post_count,
+                    last_thread_id: forum.last_thread_id,
+                    last_post_id: forum.last_post_id,
+                    last_post_at: forum.last_post_at,
+                };
+
+                for child in node.children.iter() {
+                    node.thread_count += child.thread_count;
+                    node.post_count += child.post_count;
+                    if child.last_post_at > node.last_post_at {
+                        node.last_thread_id = child.last_thread_id;
+                        node.last_post_id = child.last_post_id;
+                        node.last_post_at = child.last_post_at;
+                    }
+                }
+
+                node
+            })
+            .collect()
+    }
+
+    /// Returns true if this forum or any sub-forum is unread.
+    pub fn is_unread(&self, unread_forums: &HashSet<i32>) -> bool {
+        unread_forums.contains(&self.forum.id)
+            || self
+                .children
+                .iter()
+                .any(|child| child.is_unread(unread_forums))
+    }
+
+    /// Returns the ids of this forum and every sub-forum.
+    pub fn get_forum_ids(&self) -> Vec<i32> {
+        let mut ids = vec![self.forum.id];
+        for child in self.children.iter() {
+            ids.extend(child.get_forum_ids());
+        }
+        ids
+    }
+}
+
+/// Returns every forum in display order.
+pub async fn get_all_forums<C>(conn: &C) -> Result<Vec<forums::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    forums::Entity::find()
+        .order_by_asc(forums::Column::DisplayOrder)
+        .order_by_asc(forums::Column::Id)
+        .all(conn)
+        .await
+}
+
+/// Returns the forums above this one, starting from the root.
+pub fn get_forum_ancestors(forums: &[forums::Model], forum: &forums::Model) -> Vec<forums::Model> {
+    let mut ancestors: Vec<forums::Model> = Vec::new();
+    let mut parent_id = forum.parent_id;
+
+    while let Some(parent) = parent_id.and_then(|id| forums.iter().find(|f| f.id == id)) {
+        if ancestors.len() >= MAX_FORUM_DEPTH {
+            break;
+        }
+        parent_id = parent.parent_id;
+        ancestors.push(parent.to_owned());
+    }
+
+    ancestors.reverse();
+    ancestors
+}
+
+/// Returns the threads holding the latest post of each forum.
+async fn get_last_threads<C>(
+    conn: &C,
+    nodes: &[ForumNode],
+) -> Result<HashMap<i32, threads::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    fn collect(nodes: &[ForumNode], ids: &mut Vec<i32>) {
+        for node in nodes {
+            ids.extend(node.last_thread_id);
+            collect(&node.children, ids);
+        }
+    }
+
+    let mut ids: Vec<i32> = Vec::new();
+    collect(nodes, &mut ids);
+
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    Ok(threads::Entity::find()
+        .filter(threads::Column::Id.is_in(ids))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|thread| (thread.id, thread))
+        .collect())
+}
+
+/// Selects the threads a forum counts, which are those whose first post is visible.
+fn find_counted_threads(forum_id: i32) -> Select<threads::Entity> {
+    threads::Entity::find()
+        .join(JoinType::InnerJoin, threads::Relation::FirstPost.def())
+        .join(JoinType::InnerJoin, posts::Relation::Ugc.def())
+        .filter(threads::Column::ForumId.eq(forum_id))
+        .filter(ugc::Column::ContentStatus.eq(ContentStatus::Visible))
+}
+
+/// Returns the counted thread in a forum with the latest post.
+async fn find_last_thread<C>(conn: &C, forum_id: i32) -> Result<Option<threads::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    find_counted_threads(forum_id)
+        .filter(threads::Column::LastPostAt.is_not_null())
+        .order_by_desc(threads::Column::LastPostAt)
+        .order_by_desc(threads::Column::Id)
+        .one(conn)
+        .await
+}
+
+/// Sets a forum's latest post to that of a thread.
+fn set_last_thread(
+    update: UpdateMany<forums::Entity>,
+    last_thread: Option<&threads::Model>,
+) -> UpdateMany<forums::Entity> {
+    update
+        .col_expr(
+            forums::Column::LastThreadId,
+            Expr::value(last_thread.map(|thread| thread.id)),
+        )
+        .col_expr(
+            forums::Column::LastPostId,
+            Expr::value(last_thread.and_then(|thread| thread.last_post_id)),
+        )
+        .col_expr(
+            forums::Column::LastPostAt,
+            Expr::value(last_thread.and_then(|thread| thread.last_post_at)),
+        )
+}
+
+/// Recounts a forum's threads and posts and finds its latest post.
+/// Only threads whose first post is visible are counted.
+/// This reads every thread in the forum, so it is kept for merging, moving and deleting threads.
+pub async fn update_forum_stats<C>(conn: &C, forum_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    #[derive(Debug, FromQueryResult)]
+    struct ForumTotals {
+        thread_count: i64,
+        post_count: Option<i64>,
+    }
+
+    let totals_query = find_counted_threads(forum_id)
+        .select_only()
+        .column_as(threads::Column::Id.count(), "thread_count")
+        .column_as(threads::Column::PostCount.sum(), "post_count")
+        .into_model::<ForumTotals>()
+        .one(conn);
+    let last_thread_query = find_last_thread(conn, forum_id);
+
+    let (totals, last_thread) =
+        futures::try_join!(totals_query, last_thread_query).map_err(|e| {
+            log::error!("update_forum_stats: {:#?}", e);
+            e
+        })?;
+
+    let (thread_count, post_count) = totals
+        .map(|totals| (totals.thread_count, totals.post_count.unwrap_or(0)))
+        .unwrap_or_default();
+
+    set_last_thread(
+        forums::Entity::update_many()
+            .col_expr(
+                forums::Column::ThreadCount,
+                Expr::value(thread_count as i32),
+            )
+            .col_expr(forums::Column::PostCount, Expr::value(post_count as i32)),
+        last_thread.as_ref(),
+    )
+    .filter(forums::Column::Id.eq(forum_id))
+    .exec(conn)
+    .await
+    .map_err(|e| {
+        log::error!("update_forum_stats: {:#?}", e);
+        e
+    })?;
+
+    Ok(())
+}
+
+/// Counts a change to one thread in its forum's stats without recounting the forum.
+/// `old` is the thread before the change, or None for a thread the forum has not counted yet.
+/// Threads whose first post is not visible are not counted.
+pub async fn update_forum_stats_for_thread<C>(
+    conn: &C,
+    old: Option<&threads::Model>,
+    thread: &threads::Model,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    if get_thread_content_status(conn, thread).await? != ContentStatus::Visible {
+        return Ok(());
+    }
+
+    let forum = match forums::Entity::find_by_id(thread.forum_id)
+        .one(conn)
+        .await?
+    {
+        Some(forum) => forum,
+        None => return Ok(()),
+    };
+
+    let (thread_count, post_count) = match old {
+        Some(old) => (0, thread.post_count - old.post_count),
+        None => (1, thread.post_count),
+    };
+
+    let mut update = forums::Entity::update_many()
+        .col_expr(
+            forums::Column::ThreadCount,
+            Expr::col(forums::Column::ThreadCount).add(thread_count),
+        )
+        .col_expr(
+            forums::Column::PostCount,
+            Expr::col(forums::Column::PostCount).add(post_count),
+        );
+
+    if thread.last_post_at.is_some() && thread.last_post_at >= forum.last_post_at {
+        update = set_last_thread(update, Some(thread));
+    } else if forum.last_thread_id == Some(thread.id) {
+        // The forum's latest post was in this thread and is gone.
+        update = set_last_thread(update, find_last_thread(conn, forum.id).await?.as_ref());
+    }
+
+    update
+        .filter(forums::Column::Id.eq(forum.id))
+        .exec(conn)
+        .await
+        .map_err(|e| {
+            log::error!("update_forum_stats_for_thread: {:#?}", e);
+            e
+        })?;
+
+    Ok(())
+}
+
+/// Returns a forum the client may post threads in.
+/// Forums the client cannot see are not found, like any other forum route.
+pub async fn get_forum_for_posting<C>(
+    conn: &C,
+    client: &ClientCtx,
+    forum_id: i32,
+) -> Result<forums::Model, Error>
+where
+    C: ConnectionTrait,
+{
+    let forum = forums::Entity::find_by_id(forum_id)
+        .one(conn)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .filter(|forum| client.can_view_forum(forum))
+        .ok_or_else(|| error::ErrorNotFound("Forum not found."))?;
+
+    if !client.can_post_in_forum() {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to post in this forum.",
+        ));
+    }
+    if forum.is_category {
+        return Err(error::ErrorUnprocessableEntity(
+            "Threads cannot be posted in a category.",
+        ));
+    }
+
+    Ok(forum)
 }
 
 #[post("/forums/{forum}/post-thread")]
@@ -33,11 +549,27 @@ pub async fn create_thread(
     form: web::Form<NewThreadFormData>,
     path: web::Path<i32>,
 ) -> Result<impl Responder, Error> {
+    use crate::notification::{notify_forum_watchers, push_notifications};
     use crate::ugc::{create_ugc, NewUgcPartial};
+    use crate::watch::watch_thread;
     let forum_id = path.into_inner();
 
     // Run form data through validator.
     let form = validate_thread_form(form).map_err(|err| err)?;
+    let tags = crate::tag::normalize_tags(form.tags.as_deref().unwrap_or_default());
+
+    let forum = get_forum_for_posting(get_db_pool(), &client, forum_id).await?;
+
+    if let Some(prefix_id) = form.prefix_id {
+        let prefixes = get_prefixes_for_forum(get_db_pool(), forum.id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        if !prefixes.iter().any(|prefix| prefix.id == prefix_id) {
+            return Err(error::ErrorUnprocessableEntity(
+                "This prefix cannot be used in this forum.",
+            ));
+        }
+    }
 
     // Begin Transaction
     let txn = get_db_pool()
@@ -46,13 +578,15 @@ pub async fn create_thread(
         .map_err(error::ErrorInternalServerError)?;
 
     // Step 1. Create the UGC.
-    let revision = create_ugc(
+    let content_status = client.get_new_content_status();
+    let (revision, mut notifications) = create_ugc(
         &txn,
         NewUgcPartial {
             ip_id: None,
             user_id: None,
             content: &form.content,
         },
+        content_status,
     )
     .await
     .map_err(error::ErrorInternalServerError)?;
@@ -60,7 +594,7 @@ pub async fn create_thread(
     // Step 2. Create a thread.
     let thread = threads::ActiveModel {
         user_id: Set(client.get_id()),
-        forum_id: Set(forum_id),
+        forum_id: Set(forum.id),
         created_at: Set(revision.created_at),
         title: Set(form.title.trim().to_owned()),
         subtitle: Set(form
@@ -70,6 +604,7 @@ pub async fn create_thread(
             .filter(|s| s.is_empty())),
         view_count: Set(0),
         post_count: Set(1),
+        prefix_id: Set(form.prefix_id),
         ..Default::default()
     };
     let thread_res = threads::Entity::insert(thread)
@@ -91,23 +626,49 @@ pub async fn create_thread(
     .map_err(error::ErrorInternalServerError)?;
 
     // Step 4. Update the thread to include last, first post id info.
-    threads::Entity::update_many()
-        .col_expr(threads::Column::PostCount, Expr::value(1))
-        .col_expr(threads::Column::FirstPostId, Expr::value(new_post.id))
-        .col_expr(threads::Column::LastPostId, Expr::value(new_post.id))
-        .col_expr(
-            threads::Column::LastPostAt,
-            Expr::value(revision.created_at),
-        )
-        .filter(threads::Column::Id.eq(thread_res.last_insert_id))
-        .exec(&txn)
+    let thread = threads::ActiveModel {
+        id: Unchanged(thread_res.last_insert_id),
+        post_count: Set(1),
+        first_post_id: Set(Some(new_post.id)),
+        last_post_id: Set(Some(new_post.id)),
+        last_post_at: Set(Some(revision.created_at)),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    // Step 5. Tag the thread.
+    crate::tag::set_thread_tags(&txn, thread_res.last_insert_id, &tags)
         .await
         .map_err(error::ErrorInternalServerError)?;
 
+    // Step 6. Count the thread in its forum.
+    update_forum_stats_for_thread(&txn, None, &thread)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    // Step 7. The author watches their thread. Forum watchers hear of it once it is visible.
+    if let Some(user_id) = client.get_id() {
+        watch_thread(&txn, user_id, thread_res.last_insert_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+    if content_status == ContentStatus::Visible {
+        notifications.extend(
+            notify_forum_watchers(&txn, forum.id, revision.ugc_id, client.get_id())
+                .await
+                .map_err(error::ErrorInternalServerError)?,
+        );
+    }
+
     // Close transaction
     txn.commit()
         .await
         .map_err(error::ErrorInternalServerError)?;
+    push_notifications(get_db_pool(), &notifications)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
 
     Ok(HttpResponse::Found()
         .append_header((
@@ -117,60 +678,372 @@ pub async fn create_thread(
         .finish())
 }
 
-#[get("/forums/{forum}/")]
-pub async fn view_forum(client: ClientCtx, path: web::Path<i32>) -> Result<impl Responder, Error> {
-    use crate::orm::forums;
-
-    let forum_id = path.into_inner();
-    let forum = forums::Entity::find_by_id(forum_id)
-        .one(get_db_pool())
+/// Returns the prefixes which may be used in a forum, in order.
+pub async fn get_prefixes_for_forum<C>(
+    conn: &C,
+    forum_id: i32,
+) -> Result<Vec<thread_prefixes::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    thread_prefixes::Entity::find()
+        .inner_join(forum_thread_prefixes::Entity)
+        .filter(forum_thread_prefixes::Column::ForumId.eq(forum_id))
+        .order_by_asc(thread_prefixes::Column::Sort)
+        .order_by_asc(thread_prefixes::Column::Id)
+        .all(conn)
         .await
-        .map_err(|_| error::ErrorInternalServerError("Could not look up forum."))?
-        .ok_or_else(|| error::ErrorNotFound("Forum not found."))?;
+}
 
-    let threads: Vec<ThreadForTemplate> = match threads::Entity::find()
-        // Authoring User
-        .left_join(user_names::Entity)
-        .column_as(user_names::Column::Name, "username")
+/// Returns a select for threads and the data needed to list them.
+/// Threads the client cannot read are excluded.
+pub fn find_threads_for_template(client: &ClientCtx) -> Select<threads::Entity> {
+    let select = threads::Entity::find()
+        // First Post, whose status is the thread's
+        .join(JoinType::InnerJoin, threads::Relation::FirstPost.def())
+        .join(JoinType::InnerJoin, posts::Relation::Ugc.def())
+        .column_as(ugc::Column::ContentStatus, "content_status")
         // Last Post
         // TODO: This is an actual nightmare.
         //.join_join(JoinType::LeftJoin, threads::Relations::::to(), threads::Relation::LastPost<posts::Entity>::via())
         //.column_as(users::Column::Name, "username")
-        // Execute
-        .filter(threads::Column::ForumId.eq(forum_id))
-        .order_by_desc(threads::Column::LastPostAt)
+        // Prefix
+        .left_join(thread_prefixes::Entity)
+        .column_as(thread_prefixes::Column::Label, "prefix_label")
+        .column_as(thread_prefixes::Column::Color, "prefix_color")
+        // Authoring User
+        .left_join(user_names::Entity)
+        .column_as(user_names::Column::Name, "username");
+
+    if client.can_approve_content() {
+        select
+    } else {
+        select.filter(ugc::Column::ContentStatus.eq(ContentStatus::Visible))
+    }
+}
+
+/// Restricts a thread select to threads with this tag.
+pub fn filter_threads_by_tag(
+    select: Select<threads::Entity>,
+    tag_id: i32,
+) -> Select<threads::Entity> {
+    select.filter(
+        threads::Column::Id.in_subquery(
+            Query::select()
+                .column(thread_tags::Column::ThreadId)
+                .from(thread_tags::Entity)
+                .and_where(thread_tags::Column::TagId.eq(tag_id))
+                .to_owned(),
+        ),
+    )
+}
+
+/// Orders a thread select, breaking ties by the newest thread.
+pub fn order_threads(
+    select: Select<threads::Entity>,
+    sort: ThreadSort,
+    order: SortOrder,
+) -> Select<threads::Entity> {
+    select
+        .order_by(sort.get_column(), order.into())
+        .order_by(threads::Column::Id, order.into())
+}
+
+/// Returns a Responder for a forum's threads at a specific page.
+/// Sticky threads are listed before the first page, whatever the sort.
+async fn get_forum_and_threads_for_page(
+    client: ClientCtx,
+    forum_id: i32,
+    page: i32,
+    query: ForumFilterQuery,
+) -> Result<impl Responder, Error> {
+    use crate::tag::{get_tags_for_threads, normalize_tag};
+
+    let db = get_db_pool();
+    let forums = get_all_forums(db)
+        .await
+        .map_err(|_| error::ErrorInternalServerError("Could not look up forum."))?;
+    let forum = forums
+        .iter()
+        .find(|forum| forum.id == forum_id && client.can_view_forum(forum))
+        .cloned()
+        .ok_or_else(|| error::ErrorNotFound("Forum not found."))?;
+    let ancestors = get_forum_ancestors(&forums, &forum);
+    let children = ForumNode::build(&forums, Some(forum.id));
+    let last_threads = get_last_threads(db, &children)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let prefixes = get_prefixes_for_forum(db, forum_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let mut select =
+        find_threads_for_template(&client).filter(threads::Column::ForumId.eq(forum_id));
+    let mut filter_query: Vec<String> = Vec::new();
+
+    let filter_prefix = match query.prefix.filter(|id| *id > 0) {
+        Some(prefix_id) => {
+            let prefix = prefixes
+                .iter()
+                .find(|prefix| prefix.id == prefix_id)
+                .cloned()
+                .ok_or_else(|| error::ErrorNotFound("Prefix not found."))?;
+            select = select.filter(threads::Column::PrefixId.eq(prefix.id));
+            filter_query.push(format!("prefix={}", prefix.id));
+            Some(prefix)
+        }
+        None => None,
+    };
+
+    let filter_tag = match query.tag.as_deref().and_then(normalize_tag) {
+        Some(name) => {
+            let tag = tags::Entity::find()
+                .filter(tags::Column::Name.eq(name))
+                .one(db)
+                .await
+                .map_err(error::ErrorInternalServerError)?
+                .ok_or_else(|| error::ErrorNotFound("Tag not found."))?;
+            select = filter_threads_by_tag(select, tag.id);
+            filter_query.push(format!("tag={}", tag.name));
+            Some(tag)
+        }
+        None => None,
+    };
+
+    let sort = query.sort.unwrap_or_default();
+    let order = query.order.unwrap_or_else(|| sort.get_default_order());
+    if sort != ThreadSort::default() {
+        filter_query.push(format!("sort={}", sort.as_str()));
+    }
+    if order != sort.get_default_order() {
+        filter_query.push(format!("order={}", order.as_str()));
+    }
+
+    let per_page = get_threads_per_page(&client, Some(&forum));
+    let thread_count = select
+        .clone()
+        .filter(threads::Column::IsSticky.eq(false))
+        .count(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let mut threads: Vec<ThreadForTemplate> = if page == 1 {
+        order_threads(
+            select.clone().filter(threads::Column::IsSticky.eq(true)),
+            sort,
+            order,
+        )
         .into_model::<ThreadForTemplate>()
-        .all(get_db_pool())
+        .all(db)
         .await
-    {
-        Ok(threads) => threads,
-        Err(_) => Default::default(),
+        .map_err(error::ErrorInternalServerError)?
+    } else {
+        Vec::new()
+    };
+
+    threads.extend(
+        order_threads(
+            select.filter(threads::Column::IsSticky.eq(false)),
+            sort,
+            order,
+        )
+        .offset(((page - 1) * per_page) as u64)
+        .limit(per_page as u64)
+        .into_model::<ThreadForTemplate>()
+        .all(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?,
+    );
+
+    let tags = get_tags_for_threads(db, threads.iter().map(|thread| thread.id).collect())
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let read_markers = ReadMarkers::load(
+        db,
+        client.get_id(),
+        threads.iter().map(|thread| thread.id).collect(),
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+    let unread_forums = get_unread_forums(&client, &children).await;
+
+    let is_watching = match client.get_id() {
+        Some(user_id) => is_watching_forum(db, user_id, forum_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?,
+        None => false,
+    };
+
+    let paginator = Paginator {
+        base_url: format!("/forums/{}/", forum_id),
+        query: if filter_query.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", filter_query.join("&"))
+        },
+        this_page: page,
+        page_count: get_pages_in_forum(thread_count as i32, per_page),
     };
 
     Ok(ForumTemplate {
-        client: client.to_owned(),
+        client,
         forum: &forum,
+        ancestors: &ancestors,
+        children: &children,
+        last_threads: &last_threads,
+        unread_forums: &unread_forums,
         threads: &threads,
+        read_markers: &read_markers,
+        tags: &tags,
+        prefixes: &prefixes,
+        filter_prefix,
+        filter_tag,
+        sort,
+        order,
+        paginator,
+        is_watching,
     }
     .to_response())
 }
 
+#[get("/forums/{forum}/")]
+pub async fn view_forum(
+    client: ClientCtx,
+    path: web::Path<i32>,
+    query: web::Query<ForumFilterQuery>,
+) -> Result<impl Responder, Error> {
+    get_forum_and_threads_for_page(client, path.into_inner(), 1, query.into_inner()).await
+}
+
+#[get("/forums/{forum}/page-{page}")]
+pub async fn view_forum_page(
+    client: ClientCtx,
+    path: web::Path<(i32, i32)>,
+    query: web::Query<ForumFilterQuery>,
+) -> Result<impl Responder, Error> {
+    let (forum_id, page) = path.into_inner();
+    get_forum_and_threads_for_page(client, forum_id, std::cmp::max(1, page), query.into_inner())
+        .await
+}
+
+/// Marks a forum and its sub-forums as read.
+#[post("/forums/{forum}/mark-read")]
+pub async fn mark_forum_read(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    let user_id = client
+        .get_id()
+        .ok_or_else(|| error::ErrorUnauthorized("You must be logged in to do that."))?;
+    let forum_id = path.into_inner();
+
+    let db = get_db_pool();
+    let forums = get_all_forums(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    if !forums.iter().any(|forum| forum.id == forum_id) {
+        return Err(error::ErrorNotFound("Forum not found."));
+    }
+
+    let mut forum_ids = vec![forum_id];
+    for child in ForumNode::build(&forums, Some(forum_id)) {
+        forum_ids.extend(child.get_forum_ids());
+    }
+
+    let read_at = Utc::now().naive_utc();
+    let txn = db.begin().await.map_err(error::ErrorInternalServerError)?;
+    for id in forum_ids {
+        crate::read_marker::mark_forum_read(&txn, user_id, id, read_at)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+    txn.commit()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/forums/{}/", forum_id)))
+        .finish())
+}
+
+/// Sets whether the client is notified of new threads in a forum.
+async fn set_forum_watch(
+    client: ClientCtx,
+    forum_id: i32,
+    watching: bool,
+) -> Result<impl Responder, Error> {
+    let user_id = client
+        .get_id()
+        .ok_or_else(|| error::ErrorUnauthorized("You must be logged in to do that."))?;
+
+    let db = get_db_pool();
+    let forum = forums::Entity::find_by_id(forum_id)
+        .one(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Forum not found."))?;
+
+    if watching {
+        crate::watch::watch_forum(db, user_id, forum.id).await
+    } else {
+        crate::watch::unwatch_forum(db, user_id, forum.id).await
+    }
+    .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/forums/{}/", forum.id)))
+        .finish())
+}
+
+#[post("/forums/{forum}/unwatch")]
+pub async fn unwatch_forum(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    set_forum_watch(client, path.into_inner(), false).await
+}
+
+#[post("/forums/{forum}/watch")]
+pub async fn watch_forum(client: ClientCtx, path: web::Path<i32>) -> Result<impl Responder, Error> {
+    set_forum_watch(client, path.into_inner(), true).await
+}
+
+/// Returns which of these forums and their sub-forums have threads the client has not read.
+/// Guests have none.
+async fn get_unread_forums(client: &ClientCtx, forums: &[ForumNode]) -> HashSet<i32> {
+    let forum_ids: Vec<i32> = forums
+        .iter()
+        .flat_map(|node| node.get_forum_ids())
+        .collect();
+    match client.get_id() {
+        Some(user_id) => get_unread_forum_ids(get_db_pool(), user_id, &forum_ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("get_unread_forums: {}", e);
+                Default::default()
+            }),
+        None => Default::default(),
+    }
+}
+
 #[get("/forums")]
 pub async fn view_forums(client: ClientCtx) -> Result<impl Responder, Error> {
     render_forum_list(client).await
 }
 
 pub async fn render_forum_list(client: ClientCtx) -> Result<impl Responder, Error> {
-    use crate::orm::forums;
-
-    let forums = match forums::Entity::find().all(get_db_pool()).await {
-        Ok(forums) => forums,
-        Err(_) => Default::default(),
-    };
+    let db = get_db_pool();
+    let forums = ForumNode::build(&get_all_forums(db).await.unwrap_or_default(), None);
+    let last_threads = get_last_threads(db, &forums).await.unwrap_or_default();
+    let unread_forums = get_unread_forums(&client, &forums).await;
 
     Ok(ForumIndexTemplate {
         client: client.to_owned(),
         forums: &forums,
+        last_threads: &last_threads,
+        unread_forums: &unread_forums,
     }
     .to_response())
 }
@@ -1,6 +1,7 @@
 use crate::db::get_db_pool;
 use crate::middleware::ClientCtx;
 use crate::orm::{attachments, user_names, users};
+use crate::reaction::get_received_reaction_count;
 use crate::user::Profile as UserProfile;
 use actix_web::{error, get, web, Error, Responder};
 use askama_actix::{Template, TemplateToResponse};
@@ -20,6 +21,7 @@ pub async fn view_member(
     pub struct MemberTemplate {
         pub client: ClientCtx,
         pub user: UserProfile,
+        pub reaction_count: usize,
     }
 
     let user_id = path.into_inner().0;
@@ -36,7 +38,18 @@ pub async fn view_member(
         .await
     {
         Ok(user) => match user {
-            Some(user) => Ok(MemberTemplate { client, user }.to_response()),
+            Some(user) => {
+                let reaction_count = get_received_reaction_count(get_db_pool(), user.id)
+                    .await
+                    .map_err(error::ErrorInternalServerError)?;
+
+                Ok(MemberTemplate {
+                    client,
+                    user,
+                    reaction_count,
+                }
+                .to_response())
+            }
             None => Err(error::ErrorNotFound("User not found.")),
         },
         Err(e) => {
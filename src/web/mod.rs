@@ -7,7 +7,9 @@ pub mod index;
 pub mod login;
 pub mod logout;
 pub mod member;
+pub mod moderation;
 pub mod post;
+pub mod tag;
 pub mod thread;
 
 /// Configures the web app by adding services from each web file.
@@ -24,7 +26,9 @@ pub fn configure(conf: &mut actix_web::web::ServiceConfig) {
     login::configure(conf);
     logout::configure(conf);
     member::configure(conf);
+    moderation::configure(conf);
     post::configure(conf);
+    tag::configure(conf);
     thread::configure(conf);
 
     conf.service(crate::create_user::create_user_get)
@@ -32,5 +36,9 @@ pub fn configure(conf: &mut actix_web::web::ServiceConfig) {
         .service(crate::auth_2fa::user_enable_2fa)
         .service(crate::filesystem::post_file_hash)
         .service(crate::filesystem::put_file)
+        .service(crate::upload::create_upload)
+        .service(crate::upload::view_upload)
+        .service(crate::upload::put_upload_chunk)
+        .service(crate::upload::finalize_upload)
         .service(crate::session::view_task_expire_sessions);
 }
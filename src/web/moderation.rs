@@ -0,0 +1,231 @@
+use super::forum::update_forum_stats;
+use super::post::PostForTemplate;
+use super::thread::{renumber_thread_posts, update_thread_stats};
+use crate::attachment::{get_attachments_for_ugc_by_id, get_bbcode_attachments};
+use crate::bbcode::Constructor;
+use crate::db::get_db_pool;
+use crate::middleware::ClientCtx;
+use crate::orm::{posts, threads, ugc, ugc_deletions, ugc_revisions};
+use crate::ugc::{get_rendered_ugc, ContentStatus, RenderedUgc, UgcForRender};
+use crate::user::Profile as UserProfile;
+use actix_web::{error, get, post, web, Error, HttpResponse, Responder};
+use askama_actix::{Template, TemplateToResponse};
+use sea_orm::{entity::*, query::*, sea_query::Expr};
+use std::collections::HashMap;
+
+pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
+    conf.service(approve_post)
+        .service(reject_post)
+        .service(view_queue);
+}
+
+/// Number of queued posts shown at once.
+const QUEUE_PAGE_SIZE: u64 = 50;
+
+#[derive(Template)]
+#[template(path = "moderation_queue.html")]
+pub struct ModerationQueueTemplate<'a> {
+    pub client: ClientCtx,
+    pub posts: &'a Vec<(PostForTemplate, Option<UserProfile>)>,
+    pub threads: &'a HashMap<i32, threads::Model>,
+    pub rendered: &'a RenderedUgc,
+}
+
+/// Approves a queued post, which counts it in its thread and sends its notifications.
+#[post("/moderation/queue/{post_id}/approve")]
+pub async fn approve_post(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    use crate::mention::record_mentions;
+    use crate::notification::{notify_forum_watchers, notify_thread_watchers, push_notifications};
+
+    if !client.can_approve_content() {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to approve content.",
+        ));
+    }
+
+    let txn = get_db_pool()
+        .begin()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let (post, revision) =
+        set_queued_post_status(&txn, path.into_inner(), ContentStatus::Visible).await?;
+
+    renumber_thread_posts(&txn, post.thread_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    update_thread_stats(&txn, post.thread_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let mut notifications = match revision {
+        Some(revision) => record_mentions(&txn, post.ugc_id, post.user_id, &revision.content)
+            .await
+            .map_err(error::ErrorInternalServerError)?,
+        None => Vec::new(),
+    };
+
+    // A queued first post is a new thread for forum watchers. Anything else is a reply.
+    let thread = threads::Entity::find_by_id(post.thread_id)
+        .one(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Thread not found."))?;
+    if thread.first_post_id == Some(post.id) {
+        // The thread is counted in its forum for the first time.
+        update_forum_stats(&txn, thread.forum_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        notifications.extend(
+            notify_forum_watchers(&txn, thread.forum_id, post.ugc_id, post.user_id)
+                .await
+                .map_err(error::ErrorInternalServerError)?,
+        );
+    } else {
+        notifications.extend(
+            notify_thread_watchers(&txn, thread.id, post.ugc_id, post.user_id)
+                .await
+                .map_err(error::ErrorInternalServerError)?,
+        );
+    }
+
+    txn.commit()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    push_notifications(get_db_pool(), &notifications)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", "/moderation/queue"))
+        .finish())
+}
+
+/// Rejects a queued post, which hides it from everyone but moderators.
+#[post("/moderation/queue/{post_id}/reject")]
+pub async fn reject_post(client: ClientCtx, path: web::Path<i32>) -> Result<impl Responder, Error> {
+    if !client.can_approve_content() {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to reject content.",
+        ));
+    }
+
+    set_queued_post_status(get_db_pool(), path.into_inner(), ContentStatus::Hidden).await?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", "/moderation/queue"))
+        .finish())
+}
+
+#[get("/moderation/queue")]
+pub async fn view_queue(client: ClientCtx) -> Result<impl Responder, Error> {
+    if !client.can_approve_content() {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to view the moderation queue.",
+        ));
+    }
+
+    let db = get_db_pool();
+    let posts = crate::user::find_also_user(
+        posts::Entity::find()
+            .left_join(ugc_revisions::Entity)
+            .column_as(ugc_revisions::Column::Id, "ugc_revision_id")
+            .column_as(ugc_revisions::Column::Content, "content")
+            .column_as(ugc_revisions::Column::IpId, "ip_id")
+            .column_as(ugc_revisions::Column::CreatedAt, "updated_at")
+            .column_as(ugc::Column::ContentStatus, "content_status")
+            .left_join(ugc_deletions::Entity)
+            .column_as(ugc_deletions::Column::UserId, "deleted_by")
+            .column_as(ugc_deletions::Column::DeletedAt, "deleted_at")
+            .column_as(ugc_deletions::Column::Reason, "deleted_reason"),
+        posts::Column::UserId,
+    )
+    .filter(ugc::Column::ContentStatus.eq(ContentStatus::InQueue))
+    .order_by_asc(posts::Column::CreatedAt)
+    .limit(QUEUE_PAGE_SIZE)
+    .into_model::<PostForTemplate, UserProfile>()
+    .all(db)
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    let threads: HashMap<i32, threads::Model> = threads::Entity::find()
+        .filter(threads::Column::Id.is_in(posts.iter().map(|(post, _)| post.thread_id)))
+        .all(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .into_iter()
+        .map(|thread| (thread.id, thread))
+        .collect();
+
+    let attachments =
+        get_attachments_for_ugc_by_id(posts.iter().map(|p| p.0.ugc_id).collect()).await;
+
+    let rendered = get_rendered_ugc(
+        db,
+        &mut Constructor::new(),
+        posts
+            .iter()
+            .filter_map(|(post, _)| {
+                Some(UgcForRender {
+                    ugc_revision_id: post.ugc_revision_id?,
+                    content: post.content.as_deref()?,
+                    attachments: attachments
+                        .get(&post.ugc_id)
+                        .map(|a| get_bbcode_attachments(a))
+                        .unwrap_or_default(),
+                })
+            })
+            .collect(),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("view_queue: {}", e);
+        Default::default()
+    });
+
+    Ok(ModerationQueueTemplate {
+        client,
+        posts: &posts,
+        threads: &threads,
+        rendered: &rendered,
+    }
+    .to_response())
+}
+
+/// Moves a post out of the moderation queue.
+/// Returns the post and its living revision.
+async fn set_queued_post_status<C>(
+    conn: &C,
+    post_id: i32,
+    content_status: ContentStatus,
+) -> Result<(posts::Model, Option<ugc_revisions::Model>), Error>
+where
+    C: ConnectionTrait,
+{
+    let post = posts::Entity::find_by_id(post_id)
+        .inner_join(ugc::Entity)
+        .filter(ugc::Column::ContentStatus.eq(ContentStatus::InQueue))
+        .one(conn)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Post not found in the moderation queue."))?;
+
+    ugc::Entity::update_many()
+        .col_expr(ugc::Column::ContentStatus, Expr::value(content_status))
+        .filter(ugc::Column::Id.eq(post.ugc_id))
+        .exec(conn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let revision = posts::Entity::find_by_id(post.id)
+        .find_also_related(ugc_revisions::Entity)
+        .one(conn)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .and_then(|(_, revision)| revision);
+
+    Ok((post, revision))
+}
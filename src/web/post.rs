@@ -1,7 +1,9 @@
 use super::thread::get_url_for_pos;
 use crate::db::get_db_pool;
 use crate::middleware::ClientCtx;
-use crate::orm::{posts, ugc_deletions, ugc_revisions};
+use crate::notification::push_notifications;
+use crate::orm::{posts, ugc, ugc_deletions, ugc_revisions};
+use crate::reaction::ReactionFormData;
 use crate::ugc::{create_ugc_revision, NewUgcPartial};
 use crate::user::Profile as UserProfile;
 use actix_web::{error, get, post, web, Error, HttpResponse, Responder};
@@ -15,6 +17,10 @@ pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
     conf.service(delete_post)
         .service(destroy_post)
         .service(edit_post)
+        .service(hard_delete_post)
+        .service(react_to_post)
+        .service(revert_post)
+        .service(undelete_post)
         .service(update_post)
         .service(view_post_by_id)
         .service(view_post_in_thread)
@@ -41,19 +47,28 @@ pub struct PostForTemplate {
     pub ugc_revision_id: Option<i32>,
     pub content: Option<String>,
     pub ip_id: Option<i32>,
+    pub content_status: crate::ugc::ContentStatus,
     // join ugc deletions
     pub deleted_by: Option<i32>,
     pub deleted_at: Option<chrono::NaiveDateTime>,
     pub deleted_reason: Option<String>,
 }
 
-impl PostForTemplate {}
+impl PostForTemplate {
+    /// Returns true if this revision is the one which is shown.
+    pub fn is_living_revision(&self, ugc_revision_id: &i32) -> bool {
+        self.ugc_revision_id == Some(*ugc_revision_id)
+    }
+}
 
 #[derive(Template)]
 #[template(path = "post_delete.html")]
 pub struct PostDeleteTemplate<'a> {
     pub client: ClientCtx,
     pub post: &'a PostForTemplate,
+    /// Each form is only offered if the route it posts to would accept it.
+    pub can_delete: bool,
+    pub can_hard_delete: bool,
 }
 
 #[derive(Template)]
@@ -86,6 +101,12 @@ pub struct UgcRevisionLineItem {
     pub user_id: Option<i32>,
     pub ugc_id: i32,
     pub created_at: chrono::NaiveDateTime,
+    pub reverted_from_id: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct RevertPostFormData {
+    pub revision: i32,
 }
 
 #[derive(Deserialize)]
@@ -117,7 +138,9 @@ pub async fn delete_post(client: ClientCtx, path: web::Path<i32>) -> Result<impl
         .map_err(error::ErrorInternalServerError)?
         .ok_or_else(|| error::ErrorNotFound("Post not found."))?;
 
-    if !client.can_delete_post(&post) {
+    let can_delete = client.can_delete_post(&post) && post.deleted_at.is_none();
+    let can_hard_delete = client.can_hard_delete_post(&post);
+    if !can_delete && !can_hard_delete {
         return Err(error::ErrorForbidden(
             "You do not have permission to delete this post.",
         ));
@@ -126,6 +149,8 @@ pub async fn delete_post(client: ClientCtx, path: web::Path<i32>) -> Result<impl
     Ok(PostDeleteTemplate {
         client,
         post: &post,
+        can_delete,
+        can_hard_delete,
     }
     .to_response())
 }
@@ -150,49 +175,243 @@ pub async fn destroy_post(
     if post.deleted_at.is_some() {
         ugc_deletions::Entity::update_many()
             .col_expr(ugc_deletions::Column::UserId, Expr::value(client.get_id()))
-            .filter(ugc_deletions::Column::Id.eq(post.id))
+            .filter(ugc_deletions::Column::Id.eq(post.ugc_id))
             .exec(db)
             .await
             .map_err(error::ErrorInternalServerError)?;
     } else {
+        use super::thread::{renumber_thread_posts, update_thread_stats};
+
+        let txn = db.begin().await.map_err(error::ErrorInternalServerError)?;
+
         ugc_deletions::Entity::insert(ugc_deletions::ActiveModel {
             id: Set(post.ugc_id),
             user_id: Set(client.get_id()),
             deleted_at: Set(Utc::now().naive_utc()),
             reason: Set(Some("Temporary reason holder".to_owned())),
         })
-        .exec(db)
+        .exec(&txn)
         .await
         .map_err(error::ErrorInternalServerError)?;
 
-        // Spawn a thread to handle post-deletion work.
-        actix_web::rt::spawn(async move {
-            use super::thread::update_thread_after_reply_is_deleted;
-
-            // Update subsequent posts's position.
-            let _post_res = posts::Entity::update_many()
-                .col_expr(posts::Column::Position, Expr::cust("position - 1"))
-                .filter(
-                    Condition::all()
-                        .add(posts::Column::ThreadId.eq(post.thread_id))
-                        .add(posts::Column::Position.gt(post.position)),
-                )
-                .exec(db)
-                .await
-                .map_err(|e| log::error!("destroy_post thread: {}", e));
-
-            // Update post_count and last_post info.
-            let _thread_res = update_thread_after_reply_is_deleted(post.thread_id)
-                .await
-                .map_err(|e| log::error!("destroy_post thread: {}", e));
-        });
+        renumber_thread_posts(&txn, post.thread_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        update_thread_stats(&txn, post.thread_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+
+        txn.commit()
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", get_url_for_pos(post.thread_id, post.position)))
+        .finish())
+}
+
+/// Permanently deletes a post and its UGC, then renumbers the thread.
+/// A thread left without posts is deleted with it.
+#[post("/posts/{post_id}/hard-delete")]
+pub async fn hard_delete_post(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    use super::forum::update_forum_stats;
+    use super::thread::{renumber_thread_posts, update_thread_stats};
+    use crate::orm::threads;
+    use crate::ugc::delete_ugc;
+
+    let db = get_db_pool();
+    let (post, _) = get_post_and_author_for_template(db, path.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Post not found."))?;
+
+    if !client.can_hard_delete_post(&post) {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to delete this post.",
+        ));
+    }
+
+    let txn = db.begin().await.map_err(error::ErrorInternalServerError)?;
+
+    posts::Entity::delete_by_id(post.id)
+        .exec(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    delete_ugc(&txn, post.ugc_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let remaining = posts::Entity::find()
+        .filter(posts::Column::ThreadId.eq(post.thread_id))
+        .count(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let location = if remaining > 0 {
+        renumber_thread_posts(&txn, post.thread_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        update_thread_stats(&txn, post.thread_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        get_url_for_pos(post.thread_id, post.position)
+    } else {
+        let thread = threads::Entity::find_by_id(post.thread_id)
+            .one(&txn)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .ok_or_else(|| error::ErrorNotFound("Thread not found."))?;
+        threads::Entity::delete_by_id(thread.id)
+            .exec(&txn)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        update_forum_stats(&txn, thread.forum_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        format!("/forums/{}/", thread.forum_id)
+    };
+
+    txn.commit()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", location))
+        .finish())
+}
+
+/// Toggles the client's reaction to a post.
+#[post("/posts/{post_id}/react")]
+pub async fn react_to_post(
+    client: ClientCtx,
+    path: web::Path<i32>,
+    form: web::Form<ReactionFormData>,
+) -> Result<impl Responder, Error> {
+    use crate::reaction::{is_reaction_type, toggle_reaction};
+
+    let db = get_db_pool();
+    let (post, _) = get_post_and_author_for_template(db, path.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .filter(|(post, _)| client.can_read_post(post))
+        .ok_or_else(|| error::ErrorNotFound("Post not found."))?;
+
+    let user_id = match client.get_id() {
+        Some(user_id) if client.can_react_to(&post.user_id) => user_id,
+        _ => {
+            return Err(error::ErrorForbidden(
+                "You do not have permission to react to this post.",
+            ))
+        }
+    };
+
+    if !is_reaction_type(form.reaction) {
+        return Err(error::ErrorUnprocessableEntity("Unknown reaction."));
     }
 
+    toggle_reaction(db, post.ugc_id, user_id, post.user_id, form.reaction)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
     Ok(HttpResponse::Found()
         .append_header(("Location", get_url_for_pos(post.thread_id, post.position)))
         .finish())
 }
 
+/// Makes an earlier revision of a post's content the living revision again.
+#[post("/posts/{post_id}/revert")]
+pub async fn revert_post(
+    client: ClientCtx,
+    path: web::Path<i32>,
+    form: web::Form<RevertPostFormData>,
+) -> Result<impl Responder, Error> {
+    use crate::ugc::revert_ugc_revision;
+
+    let db = get_db_pool();
+    let (post, _) = get_post_and_author_for_template(db, path.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Post not found."))?;
+
+    if !client.can_update_post(&post) {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to update this post.",
+        ));
+    }
+
+    let revision = ugc_revisions::Entity::find_by_id(form.revision)
+        .filter(ugc_revisions::Column::UgcId.eq(post.ugc_id))
+        .one(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Revision not found."))?;
+
+    if post.is_living_revision(&revision.id) {
+        return Err(error::ErrorUnprocessableEntity(
+            "This is already the current revision.",
+        ));
+    }
+
+    let (_, notifications) = revert_ugc_revision(db, &revision, client.get_id()).await?;
+    push_notifications(db, &notifications)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/posts/{}/history", post.id)))
+        .finish())
+}
+
+/// Removes the deletion record from a post, then renumbers the thread.
+#[post("/posts/{post_id}/undelete")]
+pub async fn undelete_post(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    use super::thread::{renumber_thread_posts, update_thread_stats};
+
+    let db = get_db_pool();
+    let (post, _) = get_post_and_author_for_template(db, path.into_inner())
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Post not found."))?;
+
+    if !client.can_undelete_post(&post) {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to undelete this post.",
+        ));
+    }
+
+    if post.deleted_at.is_none() {
+        return Err(error::ErrorUnprocessableEntity("This post is not deleted."));
+    }
+
+    let txn = db.begin().await.map_err(error::ErrorInternalServerError)?;
+
+    ugc_deletions::Entity::delete_by_id(post.ugc_id)
+        .exec(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    renumber_thread_posts(&txn, post.thread_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    update_thread_stats(&txn, post.thread_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    txn.commit()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/posts/{}", post.id)))
+        .finish())
+}
+
 #[get("/posts/{post_id}/edit")]
 pub async fn edit_post(client: ClientCtx, path: web::Path<i32>) -> Result<impl Responder, Error> {
     let db = get_db_pool();
@@ -232,7 +451,7 @@ pub async fn update_post(
         ));
     }
 
-    create_ugc_revision(
+    let (_, notifications) = create_ugc_revision(
         db,
         post.ugc_id,
         NewUgcPartial {
@@ -243,6 +462,9 @@ pub async fn update_post(
     )
     .await
     .map_err(error::ErrorInternalServerError)?;
+    push_notifications(db, &notifications)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
 
     Ok(HttpResponse::Found()
         .append_header(("Location", get_url_for_pos(post.thread_id, post.position)))
@@ -270,10 +492,9 @@ pub async fn view_post_history(
     let (post, user) = get_post_and_author_for_template(db, path.into_inner())
         .await
         .map_err(error::ErrorInternalServerError)?
+        .filter(|(post, _)| client.can_read_post(post))
         .ok_or_else(|| error::ErrorNotFound("Post not found."))?;
 
-    // TODO: Auth
-
     let revisions = UgcRevisionLineItem::get_for_ugc_id(db, post.ugc_id)
         .await
         .map_err(error::ErrorInternalServerError)?;
@@ -296,6 +517,7 @@ pub async fn view_post_history_diff(
     let (post, user) = get_post_and_author_for_template(db, path.into_inner())
         .await
         .map_err(error::ErrorInternalServerError)?
+        .filter(|(post, _)| client.can_read_post(post))
         .ok_or_else(|| error::ErrorNotFound("Post not found."))?;
 
     let revisions = ugc_revisions::Entity::find()
@@ -307,8 +529,6 @@ pub async fn view_post_history_diff(
         .await
         .map_err(error::ErrorInternalServerError)?;
 
-    // TODO: Auth
-
     if revisions.len() < 2 {
         return Err(error::ErrorBadRequest(
             "Requested revisions either do not exist or are not attached to this resource as expected.",
@@ -339,6 +559,7 @@ pub async fn get_post_and_author_for_template(
             .column_as(ugc_revisions::Column::Content, "content")
             .column_as(ugc_revisions::Column::IpId, "ip_id")
             .column_as(ugc_revisions::Column::CreatedAt, "updated_at")
+            .column_as(ugc::Column::ContentStatus, "content_status")
             .left_join(ugc_deletions::Entity)
             .column_as(ugc_deletions::Column::UserId, "deleted_by")
             .column_as(ugc_deletions::Column::DeletedAt, "deleted_at")
@@ -362,6 +583,7 @@ pub async fn get_replies_and_author_for_template(
             .column_as(ugc_revisions::Column::Content, "content")
             .column_as(ugc_revisions::Column::IpId, "ip_id")
             .column_as(ugc_revisions::Column::CreatedAt, "updated_at")
+            .column_as(ugc::Column::ContentStatus, "content_status")
             .left_join(ugc_deletions::Entity)
             .column_as(ugc_deletions::Column::UserId, "deleted_by")
             .column_as(ugc_deletions::Column::DeletedAt, "deleted_at")
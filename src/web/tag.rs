@@ -0,0 +1,146 @@
+use super::forum::{
+    filter_threads_by_tag, find_threads_for_template, get_pages_in_forum, get_threads_per_page,
+};
+use super::thread::ThreadForTemplate;
+use crate::db::get_db_pool;
+use crate::middleware::ClientCtx;
+use crate::orm::{tags, thread_tags, threads};
+use crate::read_marker::ReadMarkers;
+use crate::template::{Paginator, PaginatorToHtml};
+use actix_web::{error, get, web, Error, Responder};
+use askama_actix::{Template, TemplateToResponse};
+use sea_orm::{entity::*, query::*, FromQueryResult};
+use std::collections::HashMap;
+
+pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
+    conf.service(view_tag)
+        .service(view_tag_page)
+        .service(view_tags);
+}
+
+#[derive(Debug, FromQueryResult)]
+pub struct TagForTemplate {
+    pub id: i32,
+    pub name: String,
+    pub thread_count: i64,
+}
+
+#[derive(Template)]
+#[template(path = "tags.html")]
+pub struct TagIndexTemplate<'a> {
+    pub client: ClientCtx,
+    pub tags: &'a Vec<TagForTemplate>,
+}
+
+#[derive(Template)]
+#[template(path = "tag.html")]
+pub struct TagTemplate<'a> {
+    pub client: ClientCtx,
+    pub tag: &'a tags::Model,
+    pub threads: &'a Vec<ThreadForTemplate>,
+    pub tags: &'a HashMap<i32, Vec<tags::Model>>,
+    pub read_markers: &'a ReadMarkers,
+    pub paginator: Paginator,
+}
+
+/// Returns a Responder for a tag's threads at a specific page.
+async fn get_tag_and_threads_for_page(
+    client: ClientCtx,
+    name: String,
+    page: i32,
+) -> Result<impl Responder, Error> {
+    use crate::tag::{get_tags_for_threads, normalize_tag};
+
+    let db = get_db_pool();
+    let tag = tags::Entity::find()
+        .filter(tags::Column::Name.eq(normalize_tag(&name).unwrap_or_default()))
+        .one(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Tag not found."))?;
+
+    let select = filter_threads_by_tag(find_threads_for_template(&client), tag.id);
+    let per_page = get_threads_per_page(&client, None);
+
+    let thread_count = select
+        .clone()
+        .count(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let threads: Vec<ThreadForTemplate> = select
+        .order_by_desc(threads::Column::LastPostAt)
+        .offset(((page - 1) * per_page) as u64)
+        .limit(per_page as u64)
+        .into_model::<ThreadForTemplate>()
+        .all(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let tags = get_tags_for_threads(db, threads.iter().map(|thread| thread.id).collect())
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let read_markers = ReadMarkers::load(
+        db,
+        client.get_id(),
+        threads.iter().map(|thread| thread.id).collect(),
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    let paginator = Paginator {
+        base_url: format!("/tags/{}/", tag.name),
+        query: String::new(),
+        this_page: page,
+        page_count: get_pages_in_forum(thread_count as i32, per_page),
+    };
+
+    Ok(TagTemplate {
+        client,
+        tag: &tag,
+        threads: &threads,
+        tags: &tags,
+        read_markers: &read_markers,
+        paginator,
+    }
+    .to_response())
+}
+
+#[get("/tags/{tag}/")]
+pub async fn view_tag(client: ClientCtx, path: web::Path<String>) -> Result<impl Responder, Error> {
+    get_tag_and_threads_for_page(client, path.into_inner(), 1).await
+}
+
+#[get("/tags/{tag}/page-{page}")]
+pub async fn view_tag_page(
+    client: ClientCtx,
+    path: web::Path<(String, i32)>,
+) -> Result<impl Responder, Error> {
+    let (name, page) = path.into_inner();
+    get_tag_and_threads_for_page(client, name, std::cmp::max(1, page)).await
+}
+
+#[get("/tags")]
+pub async fn view_tags(client: ClientCtx) -> Result<impl Responder, Error> {
+    let tags = tags::Entity::find()
+        .select_only()
+        .column(tags::Column::Id)
+        .column(tags::Column::Name)
+        .column_as(thread_tags::Column::ThreadId.count(), "thread_count")
+        .inner_join(thread_tags::Entity)
+        .group_by(tags::Column::Id)
+        .group_by(tags::Column::Name)
+        .order_by_desc(thread_tags::Column::ThreadId.count())
+        .order_by_asc(tags::Column::Name)
+        .into_model::<TagForTemplate>()
+        .all(get_db_pool())
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(TagIndexTemplate {
+        client,
+        tags: &tags,
+    }
+    .to_response())
+}
@@ -1,28 +1,45 @@
+use super::forum::{update_forum_stats, update_forum_stats_for_thread};
 use super::post::PostForTemplate;
 use crate::attachment::AttachmentForTemplate;
 use crate::db::get_db_pool;
 use crate::middleware::ClientCtx;
 use crate::orm::posts::Entity as Post;
 use crate::orm::threads::Entity as Thread;
-use crate::orm::{posts, threads, ugc_deletions};
+use crate::orm::{posts, threads, ugc, ugc_deletions};
+use crate::reaction::UgcReactions;
 use crate::template::{Paginator, PaginatorToHtml};
+use crate::ugc::{ContentStatus, RenderedUgc};
 use crate::user::Profile as UserProfile;
 use actix_multipart::Multipart;
 use actix_web::{error, get, post, web, Error, HttpResponse, Responder};
 use askama_actix::{Template, TemplateToResponse};
-use sea_orm::{entity::*, query::*, sea_query::Expr, DbErr, FromQueryResult, QueryFilter};
+use sea_orm::{
+    entity::*, query::*, sea_query::Expr, ConnectionTrait, DbErr, FromQueryResult, QueryFilter,
+    Statement,
+};
 use serde::Deserialize;
 use std::{collections::HashMap, str};
 
 pub(super) fn configure(conf: &mut actix_web::web::ServiceConfig) {
     conf.service(create_reply)
+        .service(lock_thread)
+        .service(merge_thread)
+        .service(move_thread)
+        .service(split_thread)
+        .service(sticky_thread)
+        .service(unlock_thread)
+        .service(unsticky_thread)
+        .service(unwatch_thread)
         .service(view_thread)
-        .service(view_thread_page);
+        .service(view_thread_page)
+        .service(view_thread_unread)
+        .service(watch_thread);
 }
 
 #[derive(Debug, FromQueryResult)]
 pub struct ThreadForTemplate {
     pub id: i32,
+    pub forum_id: i32,
     pub user_id: Option<i32>,
     pub created_at: chrono::naive::NaiveDateTime,
     pub title: String,
@@ -32,6 +49,13 @@ pub struct ThreadForTemplate {
     pub first_post_id: i32,
     pub last_post_id: i32,
     pub last_post_at: chrono::naive::NaiveDateTime,
+    pub is_locked: bool,
+    pub is_sticky: bool,
+    // join prefix
+    pub prefix_label: Option<String>,
+    pub prefix_color: Option<String>,
+    // join first post ugc
+    pub content_status: ContentStatus,
     // join user
     pub username: Option<String>,
 }
@@ -41,6 +65,10 @@ pub struct NewThreadFormData {
     pub title: String,
     pub subtitle: Option<String>,
     pub content: String,
+    /// Thread prefix. Zero or absent for none.
+    pub prefix_id: Option<i32>,
+    /// Comma separated tags.
+    pub tags: Option<String>,
 }
 
 #[derive(Template)]
@@ -49,14 +77,36 @@ pub struct ThreadTemplate<'a> {
     pub client: ClientCtx,
     pub forum: crate::orm::forums::Model,
     pub thread: crate::orm::threads::Model,
+    pub prefix: Option<crate::orm::thread_prefixes::Model>,
+    pub tags: Vec<crate::orm::tags::Model>,
+    /// Destinations for moving the thread, if the client can move it.
+    pub forums: Vec<crate::orm::forums::Model>,
     pub paginator: Paginator,
     pub posts: &'a Vec<(PostForTemplate, Option<UserProfile>)>,
     pub attachments: &'a HashMap<i32, Vec<AttachmentForTemplate>>,
+    pub rendered: &'a RenderedUgc,
+    pub reactions: &'a UgcReactions,
+    /// True if the client is notified of replies.
+    pub is_watching: bool,
 }
 
 mod filters {
-    pub fn ugc(s: &str) -> ::askama::Result<String> {
-        Ok(crate::bbcode::parse(s))
+    use crate::attachment::{get_bbcode_attachments, AttachmentForTemplate};
+    use crate::bbcode::{parse_with_constructor, Constructor};
+
+    pub fn ugc(
+        s: &str,
+        attachments: &Option<&Vec<AttachmentForTemplate>>,
+    ) -> ::askama::Result<String> {
+        let constructor = Constructor {
+            attachments: match attachments {
+                Some(attachments) => get_bbcode_attachments(attachments),
+                None => Default::default(),
+            },
+            ..Default::default()
+        };
+
+        Ok(parse_with_constructor(s, &constructor))
     }
 }
 
@@ -93,8 +143,14 @@ async fn get_thread_and_replies_for_page(
     page: i32,
 ) -> Result<impl Responder, Error> {
     use super::post::get_replies_and_author_for_template;
-    use crate::attachment::get_attachments_for_ugc_by_id;
-    use crate::orm::forums;
+    use crate::attachment::{get_attachments_for_ugc_by_id, get_bbcode_attachments};
+    use crate::bbcode::Constructor;
+    use crate::notification::mark_notifications_read;
+    use crate::orm::{forums, thread_prefixes};
+    use crate::read_marker::mark_thread_read;
+    use crate::tag::get_tags_for_threads;
+    use crate::ugc::{get_rendered_ugc, UgcForRender};
+    use crate::watch::is_watching_thread;
 
     let db = get_db_pool();
     let thread = Thread::find_by_id(thread_id)
@@ -102,6 +158,13 @@ async fn get_thread_and_replies_for_page(
         .await
         .map_err(error::ErrorInternalServerError)?
         .ok_or_else(|| error::ErrorNotFound("Thread not found."))?;
+    let status = get_thread_content_status(db, &thread)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    if !client.can_read_thread(&thread, status) {
+        return Err(error::ErrorNotFound("Thread not found."));
+    }
+
     let forum = forums::Entity::find_by_id(thread.forum_id)
         .one(db)
         .await
@@ -128,8 +191,97 @@ async fn get_thread_and_replies_for_page(
     let attachments =
         get_attachments_for_ugc_by_id(posts.iter().map(|p| p.0.ugc_id).collect()).await;
 
+    // Load cached HTML. If this fails, the template renders posts itself.
+    let rendered = get_rendered_ugc(
+        db,
+        &mut Constructor::new(),
+        posts
+            .iter()
+            .filter_map(|(post, _)| {
+                Some(UgcForRender {
+                    ugc_revision_id: post.ugc_revision_id?,
+                    content: post.content.as_deref()?,
+                    attachments: attachments
+                        .get(&post.ugc_id)
+                        .map(|a| get_bbcode_attachments(a))
+                        .unwrap_or_default(),
+                })
+            })
+            .collect(),
+    )
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("get_thread_and_replies_for_page: {}", e);
+        Default::default()
+    });
+
+    let reactions = UgcReactions::load(
+        db,
+        posts.iter().map(|(post, _)| post.ugc_id).collect(),
+        client.get_id(),
+    )
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    // Mark the thread read up to the newest post on this page. Guests are not tracked.
+    if let (Some(user_id), Some(read_at)) = (
+        client.get_id(),
+        posts.iter().map(|(post, _)| post.created_at).max(),
+    ) {
+        mark_thread_read(db, user_id, thread_id, read_at)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+
+    // Notifications about posts on this page have been seen.
+    let is_watching = match client.get_id() {
+        Some(user_id) => {
+            mark_notifications_read(
+                db,
+                user_id,
+                Some(posts.iter().map(|(post, _)| post.ugc_id).collect()),
+            )
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+            is_watching_thread(db, user_id, thread_id)
+                .await
+                .map_err(error::ErrorInternalServerError)?
+        }
+        None => false,
+    };
+
+    let prefix = match thread.prefix_id {
+        Some(prefix_id) => thread_prefixes::Entity::find_by_id(prefix_id)
+            .one(db)
+            .await
+            .map_err(error::ErrorInternalServerError)?,
+        None => None,
+    };
+
+    let tags = get_tags_for_threads(db, vec![thread_id])
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .remove(&thread_id)
+        .unwrap_or_default();
+
+    let forums = if client.can_move_thread(&thread) {
+        forums::Entity::find()
+            .filter(forums::Column::IsCategory.eq(false))
+            .order_by_asc(forums::Column::DisplayOrder)
+            .order_by_asc(forums::Column::Id)
+            .all(db)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .into_iter()
+            .filter(|forum| client.can_view_forum(forum))
+            .collect()
+    } else {
+        Default::default()
+    };
+
     let paginator = Paginator {
         base_url: format!("/threads/{}/", thread_id),
+        query: String::new(),
         this_page: page,
         page_count: get_pages_in_thread(thread.post_count),
     };
@@ -137,79 +289,185 @@ async fn get_thread_and_replies_for_page(
     Ok(ThreadTemplate {
         client,
         forum,
+        forums,
         thread,
+        prefix,
+        tags,
         posts: &posts,
         paginator,
         attachments: &attachments,
+        rendered: &rendered,
+        reactions: &reactions,
+        is_watching,
     }
     .to_response())
 }
 
-/// Updates the post_count and last_post information on a thread.
-/// This DOES NOT update post positions. It only updates the thread.
-pub async fn update_thread_after_reply_is_deleted(id: i32) -> Result<(), DbErr> {
+/// Returns the status of a thread, which is the status of its first post.
+pub async fn get_thread_content_status<C>(
+    conn: &C,
+    thread: &threads::Model,
+) -> Result<ContentStatus, DbErr>
+where
+    C: ConnectionTrait,
+{
     #[derive(Debug, FromQueryResult)]
-    struct LastPost {
-        id: i32,
-        created_at: chrono::NaiveDateTime,
+    struct FirstPost {
+        content_status: ContentStatus,
     }
 
-    let db = get_db_pool();
+    let first_post_id = match thread.first_post_id {
+        Some(first_post_id) => first_post_id,
+        None => return Ok(ContentStatus::Visible),
+    };
 
-    let last_post_query = Post::find()
+    Ok(Post::find_by_id(first_post_id)
         .select_only()
-        .column_as(posts::Column::Id, "id")
-        .column_as(posts::Column::CreatedAt, "created_at")
-        .left_join(ugc_deletions::Entity)
-        .filter(posts::Column::ThreadId.eq(id))
-        .filter(ugc_deletions::Column::DeletedAt.is_null())
-        .into_model::<LastPost>()
-        .one(db);
+        .inner_join(ugc::Entity)
+        .column_as(ugc::Column::ContentStatus, "content_status")
+        .into_model::<FirstPost>()
+        .one(conn)
+        .await?
+        .map(|first_post| first_post.content_status)
+        .unwrap_or(ContentStatus::Visible))
+}
 
-    let post_count_query = Post::find()
+/// Selects posts in a thread which everyone can see.
+fn find_visible_posts(thread_id: i32) -> Select<posts::Entity> {
+    Post::find()
+        .inner_join(ugc::Entity)
         .left_join(ugc_deletions::Entity)
-        .filter(posts::Column::ThreadId.eq(id))
+        .filter(posts::Column::ThreadId.eq(thread_id))
+        .filter(ugc::Column::ContentStatus.eq(ContentStatus::Visible))
         .filter(ugc_deletions::Column::DeletedAt.is_null())
-        .into_model::<LastPost>()
-        .count(db);
-
-    let (last_post_res, post_count_res) = futures::join!(last_post_query, post_count_query);
+}
 
-    if post_count_res.is_err() {
-        let err = post_count_res.unwrap_err();
-        log::error!("post_count error in update_thread: {:#?}", err);
-        return Err(err);
+/// Updates the post_count, first_post and last_post information on a thread, then its forum.
+/// This DOES NOT update post positions. See `renumber_thread_posts`.
+/// The forum's thread count is not changed. See `update_forum_stats`.
+pub async fn update_thread_stats<C>(conn: &C, id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    #[derive(Debug, FromQueryResult)]
+    struct ThreadPost {
+        id: i32,
+        created_at: chrono::NaiveDateTime,
     }
 
-    if last_post_res.is_err() {
-        let err = last_post_res.unwrap_err();
-        log::error!("last_post error in update_thread: {:#?}", err);
-        return Err(err);
-    } else if let Some(last_post) = last_post_res.unwrap() {
-        let post_count = post_count_res.unwrap();
+    let old = match Thread::find_by_id(id).one(conn).await? {
+        Some(thread) => thread,
+        None => return Ok(()),
+    };
+
+    let visible_posts = || {
+        find_visible_posts(id)
+            .select_only()
+            .column_as(posts::Column::Id, "id")
+            .column_as(posts::Column::CreatedAt, "created_at")
+    };
+
+    let first_post_query = visible_posts()
+        .order_by_asc(posts::Column::CreatedAt)
+        .order_by_asc(posts::Column::Id)
+        .into_model::<ThreadPost>()
+        .one(conn);
+    let last_post_query = visible_posts()
+        .order_by_desc(posts::Column::CreatedAt)
+        .order_by_desc(posts::Column::Id)
+        .into_model::<ThreadPost>()
+        .one(conn);
+    let post_count_query = visible_posts().count(conn);
 
-        let update_res = Thread::update_many()
+    let (first_post, last_post, post_count) =
+        futures::try_join!(first_post_query, last_post_query, post_count_query).map_err(|e| {
+            log::error!("update_thread_stats: {:#?}", e);
+            e
+        })?;
+
+    if let (Some(first_post), Some(last_post)) = (first_post, last_post) {
+        Thread::update_many()
             .col_expr(threads::Column::PostCount, Expr::value(post_count as i32))
+            .col_expr(threads::Column::FirstPostId, Expr::value(first_post.id))
             .col_expr(threads::Column::LastPostId, Expr::value(last_post.id))
             .col_expr(
                 threads::Column::LastPostAt,
                 Expr::value(last_post.created_at),
             )
-            .exec(db)
-            .await;
-
-        if update_res.is_err() {
-            let err = update_res.unwrap_err();
-            log::error!("update query error in update_thread: {:#?}", err);
-            return Err(err);
-        }
+            .filter(threads::Column::Id.eq(id))
+            .exec(conn)
+            .await
+            .map_err(|e| {
+                log::error!("update_thread_stats: {:#?}", e);
+                e
+            })?;
     } else {
-        log::error!("thread has no last_post when trying to update thread.");
+        log::error!("thread has no visible posts when trying to update thread.");
+    }
+
+    if let Some(thread) = Thread::find_by_id(id).one(conn).await? {
+        update_forum_stats_for_thread(conn, Some(&old), &thread).await?;
     }
 
     Ok(())
 }
 
+/// Gives a thread's watches, read markers and tags to another thread, for merging.
+/// Rows the target already has are kept as they are.
+async fn move_thread_rows<C>(conn: &C, source_id: i32, target_id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    for sql in [
+        r#"INSERT INTO thread_watches (user_id, thread_id, created_at)
+        SELECT user_id, $2, created_at FROM thread_watches WHERE thread_id = $1
+        ON CONFLICT DO NOTHING"#,
+        r#"INSERT INTO thread_reads (user_id, thread_id, read_at)
+        SELECT user_id, $2, read_at FROM thread_reads WHERE thread_id = $1
+        ON CONFLICT DO NOTHING"#,
+        r#"INSERT INTO thread_tags (thread_id, tag_id)
+        SELECT $2, tag_id FROM thread_tags WHERE thread_id = $1
+        ON CONFLICT DO NOTHING"#,
+    ] {
+        conn.execute(Statement::from_sql_and_values(
+            conn.get_database_backend(),
+            sql,
+            vec![source_id.into(), target_id.into()],
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Numbers the posts in a thread by the order they were made.
+/// A deleted or unapproved post shares its position with the next visible post.
+pub async fn renumber_thread_posts<C>(conn: &C, id: i32) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    conn.execute(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        r#"UPDATE posts SET position = numbered.position
+        FROM (
+            SELECT posts.id, 1 + COALESCE(SUM(
+                CASE WHEN ugc.content_status = 'visible' AND ugc_deletions.id IS NULL THEN 1 ELSE 0 END
+            ) OVER (
+                ORDER BY posts.created_at, posts.id
+                ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING
+            ), 0) AS position
+            FROM posts
+            INNER JOIN ugc ON ugc.id = posts.ugc_id
+            LEFT JOIN ugc_deletions ON ugc_deletions.id = posts.ugc_id
+            WHERE posts.thread_id = $1
+        ) AS numbered
+        WHERE posts.id = numbered.id"#,
+        vec![id.into()],
+    ))
+    .await
+    .map(|_| ())
+}
+
 #[post("/threads/{thread_id}/post-reply")]
 pub async fn create_reply(
     client: ClientCtx,
@@ -217,8 +475,10 @@ pub async fn create_reply(
     mutipart: Option<Multipart>,
 ) -> Result<impl Responder, Error> {
     use crate::filesystem::{insert_field_as_attachment, UploadResponse};
+    use crate::notification::{notify_thread_watchers, push_notifications};
     use crate::orm::{posts, threads, ugc_attachments};
     use crate::ugc::{create_ugc, NewUgcPartial};
+    use crate::watch;
     use futures::{future::try_join_all, StreamExt, TryStreamExt};
 
     let mut content: String = String::new();
@@ -247,7 +507,12 @@ pub async fn create_reply(
                         content = str::from_utf8(&buf).unwrap().to_owned();
                     }
                     "attachment" => {
-                        if let Some(payload) = insert_field_as_attachment(&mut field).await? {
+                        if let Some(payload) = insert_field_as_attachment(
+                            &mut field,
+                            client.can_upload_original_media(),
+                        )
+                        .await?
+                        {
                             let filename = field
                                 .content_disposition()
                                 .get_filename()
@@ -278,15 +543,23 @@ pub async fn create_reply(
         .map_err(|_| error::ErrorInternalServerError("Could not look up thread."))?
         .ok_or_else(|| error::ErrorNotFound("Thread not found."))?;
 
+    if !client.can_post_in_thread(&our_thread) {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to post in this thread.",
+        ));
+    }
+
     // Insert ugc and first revision
     let user_id = client.get_id();
-    let ugc_revision = create_ugc(
+    let content_status = client.get_new_content_status();
+    let (ugc_revision, mut notifications) = create_ugc(
         &txn,
         NewUgcPartial {
             ip_id: None,
             user_id,
             content: &content,
         },
+        content_status,
     )
     .await
     .map_err(error::ErrorInternalServerError)?;
@@ -322,25 +595,42 @@ pub async fn create_reply(
         .map_err(error::ErrorInternalServerError)?;
     }
 
+    // Replying watches the thread. Watchers hear of the reply once it is visible.
+    if let Some(user_id) = user_id {
+        watch::watch_thread(&txn, user_id, our_thread.id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+    if content_status == ContentStatus::Visible {
+        notifications.extend(
+            notify_thread_watchers(&txn, our_thread.id, ugc_revision.ugc_id, user_id)
+                .await
+                .map_err(error::ErrorInternalServerError)?,
+        );
+    }
+
+    // Update thread and forum. Queued posts are counted once they are approved.
+    if content_status == ContentStatus::Visible {
+        let thread = threads::ActiveModel {
+            id: Unchanged(our_thread.id),
+            post_count: Set(our_thread.post_count + 1),
+            last_post_id: Set(Some(new_post.id)),
+            last_post_at: Set(Some(new_post.created_at)),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+        update_forum_stats_for_thread(&txn, Some(&our_thread), &thread)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+
     // Commit transaction
     txn.commit()
         .await
         .map_err(error::ErrorInternalServerError)?;
-
-    // Update thread
-    let post_id = new_post.id;
-    threads::Entity::update_many()
-        .col_expr(
-            threads::Column::PostCount,
-            Expr::value(our_thread.post_count + 1),
-        )
-        .col_expr(threads::Column::LastPostId, Expr::value(post_id))
-        .col_expr(
-            threads::Column::LastPostAt,
-            Expr::value(new_post.created_at),
-        )
-        .filter(threads::Column::Id.eq(thread_id))
-        .exec(db)
+    push_notifications(db, &notifications)
         .await
         .map_err(error::ErrorInternalServerError)?;
 
@@ -352,6 +642,363 @@ pub async fn create_reply(
         .finish())
 }
 
+/// Returns a thread the client may take a moderation action on.
+async fn get_thread_for_moderation<C>(
+    conn: &C,
+    client: &ClientCtx,
+    thread_id: i32,
+    can: fn(&ClientCtx, &threads::Model) -> bool,
+) -> Result<threads::Model, Error>
+where
+    C: ConnectionTrait,
+{
+    let thread = Thread::find_by_id(thread_id)
+        .one(conn)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Thread not found."))?;
+
+    if !can(client, &thread) {
+        return Err(error::ErrorForbidden(
+            "You do not have permission to moderate this thread.",
+        ));
+    }
+
+    Ok(thread)
+}
+
+/// Sets a boolean column on a thread, such as is_locked or is_sticky.
+async fn set_thread_flag(
+    client: ClientCtx,
+    thread_id: i32,
+    column: threads::Column,
+    value: bool,
+    can: fn(&ClientCtx, &threads::Model) -> bool,
+) -> Result<impl Responder, Error> {
+    let db = get_db_pool();
+    let thread = get_thread_for_moderation(db, &client, thread_id, can).await?;
+
+    Thread::update_many()
+        .col_expr(column, Expr::value(value))
+        .filter(threads::Column::Id.eq(thread.id))
+        .exec(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/threads/{}/", thread.id)))
+        .finish())
+}
+
+#[post("/threads/{thread_id}/lock")]
+pub async fn lock_thread(client: ClientCtx, path: web::Path<i32>) -> Result<impl Responder, Error> {
+    set_thread_flag(
+        client,
+        path.into_inner(),
+        threads::Column::IsLocked,
+        true,
+        ClientCtx::can_lock_thread,
+    )
+    .await
+}
+
+#[derive(Deserialize)]
+pub struct MergeThreadFormData {
+    /// The thread which receives the posts.
+    pub thread_id: i32,
+}
+
+/// Moves every post, watch, read marker and tag in this thread into another, then deletes this thread.
+/// Posts are ordered by when they were made.
+#[post("/threads/{thread_id}/merge")]
+pub async fn merge_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+    form: web::Form<MergeThreadFormData>,
+) -> Result<impl Responder, Error> {
+    let txn = get_db_pool()
+        .begin()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let source = get_thread_for_moderation(
+        &txn,
+        &client,
+        path.into_inner(),
+        ClientCtx::can_merge_thread,
+    )
+    .await?;
+    if source.id == form.thread_id {
+        return Err(error::ErrorUnprocessableEntity(
+            "A thread cannot be merged into itself.",
+        ));
+    }
+    let target =
+        get_thread_for_moderation(&txn, &client, form.thread_id, ClientCtx::can_merge_thread)
+            .await?;
+
+    Post::update_many()
+        .col_expr(posts::Column::ThreadId, Expr::value(target.id))
+        .filter(posts::Column::ThreadId.eq(source.id))
+        .exec(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    // The source's own rows are deleted with it.
+    move_thread_rows(&txn, source.id, target.id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Thread::delete_by_id(source.id)
+        .exec(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    renumber_thread_posts(&txn, target.id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    update_thread_stats(&txn, target.id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    update_forum_stats(&txn, source.forum_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    txn.commit()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/threads/{}/", target.id)))
+        .finish())
+}
+
+#[derive(Deserialize)]
+pub struct MoveThreadFormData {
+    pub forum_id: i32,
+}
+
+#[post("/threads/{thread_id}/move")]
+pub async fn move_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+    form: web::Form<MoveThreadFormData>,
+) -> Result<impl Responder, Error> {
+    use super::forum::{get_forum_for_posting, get_prefixes_for_forum};
+
+    let db = get_db_pool();
+    let thread =
+        get_thread_for_moderation(db, &client, path.into_inner(), ClientCtx::can_move_thread)
+            .await?;
+
+    // Threads may only be moved where the moderator could have posted them.
+    let forum = get_forum_for_posting(db, &client, form.forum_id).await?;
+
+    // Prefixes belong to forums, so a prefix the new forum does not allow is removed.
+    let prefix_id = match thread.prefix_id {
+        Some(prefix_id) => get_prefixes_for_forum(db, forum.id)
+            .await
+            .map_err(error::ErrorInternalServerError)?
+            .iter()
+            .any(|prefix| prefix.id == prefix_id)
+            .then_some(prefix_id),
+        None => None,
+    };
+
+    let txn = db.begin().await.map_err(error::ErrorInternalServerError)?;
+
+    Thread::update_many()
+        .col_expr(threads::Column::ForumId, Expr::value(forum.id))
+        .col_expr(threads::Column::PrefixId, Expr::value(prefix_id))
+        .filter(threads::Column::Id.eq(thread.id))
+        .exec(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    update_forum_stats(&txn, thread.forum_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    update_forum_stats(&txn, forum.id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    txn.commit()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/threads/{}/", thread.id)))
+        .finish())
+}
+
+/// Moves the selected posts into a new thread in the same forum.
+/// Form data is a title and any number of post_id fields.
+#[post("/threads/{thread_id}/split")]
+pub async fn split_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+    form: web::Form<Vec<(String, String)>>,
+) -> Result<impl Responder, Error> {
+    let mut title = String::new();
+    let mut post_ids: Vec<i32> = Vec::new();
+
+    for (field_name, value) in form.into_inner() {
+        match field_name.as_str() {
+            "title" => title = value.trim().to_owned(),
+            "post_id" => post_ids.push(
+                value
+                    .parse()
+                    .map_err(|_| error::ErrorBadRequest("Invalid post id."))?,
+            ),
+            _ => {
+                return Err(error::ErrorBadRequest(format!(
+                    "Unrecognized field '{}'",
+                    field_name,
+                )));
+            }
+        }
+    }
+
+    post_ids.sort_unstable();
+    post_ids.dedup();
+
+    if title.is_empty() {
+        return Err(error::ErrorUnprocessableEntity(
+            "Threads must have a title.",
+        ));
+    }
+    if post_ids.is_empty() {
+        return Err(error::ErrorUnprocessableEntity("No posts were selected."));
+    }
+
+    let txn = get_db_pool()
+        .begin()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let thread = get_thread_for_moderation(
+        &txn,
+        &client,
+        path.into_inner(),
+        ClientCtx::can_split_thread,
+    )
+    .await?;
+
+    let selected = Post::find()
+        .filter(posts::Column::ThreadId.eq(thread.id))
+        .filter(posts::Column::Id.is_in(post_ids.to_owned()))
+        .order_by_asc(posts::Column::CreatedAt)
+        .order_by_asc(posts::Column::Id)
+        .all(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    if selected.len() != post_ids.len() {
+        return Err(error::ErrorUnprocessableEntity(
+            "Selected posts must belong to this thread.",
+        ));
+    }
+
+    // Both threads must be left with something to show.
+    let visible_selected = find_visible_posts(thread.id)
+        .filter(posts::Column::Id.is_in(post_ids.to_owned()))
+        .count(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    let visible_total = find_visible_posts(thread.id)
+        .count(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    if visible_selected == 0 || visible_selected == visible_total {
+        return Err(error::ErrorUnprocessableEntity(
+            "Both threads must keep at least one visible post.",
+        ));
+    }
+
+    let new_thread = threads::ActiveModel {
+        forum_id: Set(thread.forum_id),
+        user_id: Set(selected[0].user_id),
+        created_at: Set(selected[0].created_at),
+        title: Set(title),
+        view_count: Set(0),
+        post_count: Set(0),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(error::ErrorInternalServerError)?;
+
+    Post::update_many()
+        .col_expr(posts::Column::ThreadId, Expr::value(new_thread.id))
+        .filter(posts::Column::Id.is_in(post_ids))
+        .exec(&txn)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    for thread_id in [thread.id, new_thread.id] {
+        renumber_thread_posts(&txn, thread_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+        update_thread_stats(&txn, thread_id)
+            .await
+            .map_err(error::ErrorInternalServerError)?;
+    }
+    update_forum_stats(&txn, thread.forum_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    txn.commit()
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/threads/{}/", new_thread.id)))
+        .finish())
+}
+
+#[post("/threads/{thread_id}/sticky")]
+pub async fn sticky_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    set_thread_flag(
+        client,
+        path.into_inner(),
+        threads::Column::IsSticky,
+        true,
+        ClientCtx::can_sticky_thread,
+    )
+    .await
+}
+
+#[post("/threads/{thread_id}/unlock")]
+pub async fn unlock_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    set_thread_flag(
+        client,
+        path.into_inner(),
+        threads::Column::IsLocked,
+        false,
+        ClientCtx::can_lock_thread,
+    )
+    .await
+}
+
+#[post("/threads/{thread_id}/unsticky")]
+pub async fn unsticky_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    set_thread_flag(
+        client,
+        path.into_inner(),
+        threads::Column::IsSticky,
+        false,
+        ClientCtx::can_sticky_thread,
+    )
+    .await
+}
+
 #[get("/threads/{thread_id}/")]
 pub async fn view_thread(client: ClientCtx, path: web::Path<i32>) -> Result<impl Responder, Error> {
     get_thread_and_replies_for_page(client, path.into_inner(), 1).await
@@ -373,6 +1020,110 @@ pub async fn view_thread_page(
     }
 }
 
+/// Sets whether the client is notified of replies to a thread.
+async fn set_thread_watch(
+    client: ClientCtx,
+    thread_id: i32,
+    watching: bool,
+) -> Result<impl Responder, Error> {
+    let user_id = client
+        .get_id()
+        .ok_or_else(|| error::ErrorUnauthorized("You must be logged in to do that."))?;
+
+    let db = get_db_pool();
+    let thread = Thread::find_by_id(thread_id)
+        .one(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Thread not found."))?;
+    let status = get_thread_content_status(db, &thread)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    if !client.can_read_thread(&thread, status) {
+        return Err(error::ErrorNotFound("Thread not found."));
+    }
+
+    if watching {
+        crate::watch::watch_thread(db, user_id, thread.id).await
+    } else {
+        crate::watch::unwatch_thread(db, user_id, thread.id).await
+    }
+    .map_err(error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", format!("/threads/{}/", thread.id)))
+        .finish())
+}
+
+#[post("/threads/{thread_id}/unwatch")]
+pub async fn unwatch_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    set_thread_watch(client, path.into_inner(), false).await
+}
+
+#[post("/threads/{thread_id}/watch")]
+pub async fn watch_thread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    set_thread_watch(client, path.into_inner(), true).await
+}
+
+/// Redirects to the first post the client has not read, or the last post if all are read.
+/// Guests are sent to the start of the thread. Threads the client cannot read are not found.
+#[get("/threads/{thread_id}/unread")]
+pub async fn view_thread_unread(
+    client: ClientCtx,
+    path: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    use crate::read_marker::get_thread_read_at;
+
+    let db = get_db_pool();
+    let thread = Thread::find_by_id(path.into_inner())
+        .one(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?
+        .ok_or_else(|| error::ErrorNotFound("Thread not found."))?;
+    let status = get_thread_content_status(db, &thread)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+    if !client.can_read_thread(&thread, status) {
+        return Err(error::ErrorNotFound("Thread not found."));
+    }
+
+    let user_id = match client.get_id() {
+        Some(user_id) => user_id,
+        None => {
+            return Ok(HttpResponse::Found()
+                .append_header(("Location", get_url_for_pos(thread.id, 1)))
+                .finish())
+        }
+    };
+
+    let read_at = get_thread_read_at(db, user_id, thread.id, thread.forum_id)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let first_unread = find_visible_posts(thread.id)
+        .filter(posts::Column::CreatedAt.gt(read_at))
+        .order_by_asc(posts::Column::CreatedAt)
+        .order_by_asc(posts::Column::Id)
+        .one(db)
+        .await
+        .map_err(error::ErrorInternalServerError)?;
+
+    let position = match first_unread {
+        Some(post) => post.position,
+        None => thread.post_count,
+    };
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", get_url_for_pos(thread.id, position)))
+        .finish())
+}
+
 pub fn validate_thread_form(
     form: web::Form<NewThreadFormData>,
 ) -> Result<NewThreadFormData, Error> {
@@ -389,5 +1140,7 @@ pub fn validate_thread_form(
         title,
         subtitle,
         content: form.content.to_owned(),
+        prefix_id: form.prefix_id.filter(|id| *id > 0),
+        tags: form.tags.to_owned(),
     })
 }